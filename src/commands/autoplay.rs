@@ -0,0 +1,40 @@
+use serenity::{
+    all::{Color, CommandInteraction, CreateEmbed},
+    client::Context,
+};
+use tracing::{error, info};
+
+use crate::utils::{guild_settings::update_guild_settings, response::respond_to_followup};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer autoplay command: {}", err);
+        return;
+    }
+
+    let guild_id = command.guild_id.unwrap();
+
+    let enabled = update_guild_settings(&ctx.data, guild_id, |settings| {
+        settings.autoplay = !settings.autoplay;
+        settings.autoplay
+    })
+    .await;
+
+    info!("Autoplay set to {} in guild {}", enabled, guild_id);
+
+    let description = if enabled {
+        "Enabled **autoplay!** A related song will be queued when the queue runs dry"
+    } else {
+        "Disabled **autoplay!**"
+    };
+
+    let embed = CreateEmbed::new()
+        .description(description)
+        .color(Color::DARK_GREEN);
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("autoplay")
+        .description("Toggle queueing related songs when the queue ends")
+}
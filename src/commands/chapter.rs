@@ -0,0 +1,176 @@
+use serenity::{
+    all::{
+        CommandDataOptionValue, CommandInteraction, CommandOptionType, ComponentInteraction,
+        ComponentInteractionDataKind, GuildId,
+    },
+    builder::{CreateActionRow, CreateEmbed, CreateInteractionResponseFollowup},
+    client::Context,
+    model::colour::Color,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    components::chapter_menu::create_chapter_menu,
+    utils::{
+        chapters::{ChapterTarget, current_chapter, describe_chapter, resolve_target},
+        response::{respond_to_button, respond_to_error_button, respond_to_followup},
+        track_utils::track_metadata,
+    },
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer chapter command: {}", err);
+        return;
+    }
+
+    let target = match command.data.options.first().map(|data| &data.value) {
+        Some(CommandDataOptionValue::String(value)) => ChapterTarget::parse(value),
+        _ => None,
+    };
+
+    let Some(target) = target else {
+        let embed = CreateEmbed::new()
+            .description("Please provide **next**, **prev** or a chapter number!")
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    };
+
+    let guild_id = command.guild_id.unwrap();
+
+    match seek_to_chapter(ctx, guild_id, &target).await {
+        Ok(result) => {
+            let embed = CreateEmbed::new()
+                .description(result.description)
+                .color(Color::DARK_GREEN);
+
+            let message = CreateInteractionResponseFollowup::new()
+                .embed(embed)
+                .components(vec![result.menu]);
+
+            if let Err(err) = command.create_followup(&ctx.http, message).await {
+                error!("Failed to send chapter response: {}", err);
+            }
+        }
+        Err(description) => {
+            let embed = CreateEmbed::new()
+                .description(description)
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, embed, false).await;
+        }
+    }
+}
+
+/// Handle a pick from the chapter select menu
+pub async fn handle_select(ctx: &Context, interaction: &ComponentInteraction) {
+    let number = match &interaction.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => values
+            .first()
+            .and_then(|value| value.parse::<usize>().ok())
+            .map(|index| index + 1),
+        _ => None,
+    };
+
+    let Some(number) = number else {
+        error!("Invalid chapter selection: {:?}", interaction.data.kind);
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    let guild_id = interaction.guild_id.unwrap();
+
+    match seek_to_chapter(ctx, guild_id, &ChapterTarget::Number(number)).await {
+        Ok(result) => respond_to_button(interaction, &ctx.http, result.description, false).await,
+        Err(description) => respond_to_error_button(interaction, &ctx.http, description).await,
+    }
+}
+
+struct ChapterSeek {
+    description: String,
+    menu: CreateActionRow,
+}
+
+/// Seek the current track to the chapter `target` points at
+async fn seek_to_chapter(
+    ctx: &Context,
+    guild_id: GuildId,
+    target: &ChapterTarget,
+) -> Result<ChapterSeek, String> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    let Some(call) = manager.get(guild_id) else {
+        warn!(
+            "Attempted to change chapter but bot is not in voice channel (guild {})",
+            guild_id
+        );
+        return Err(String::from(
+            "Error changing chapter! Ensure Poor Jimmy is in a voice channel with **/join**",
+        ));
+    };
+
+    // Only hold the call long enough to grab the track, the seek below can
+    // take a while
+    let handler = call.lock().await;
+    let current = handler.queue().current();
+    drop(handler);
+
+    let Some(track) = current else {
+        return Err(String::from("No song is currently playing!"));
+    };
+
+    let metadata = track_metadata(&track);
+    let chapters = &metadata.chapters;
+
+    if metadata.is_live {
+        return Err(String::from("Live streams can't be seeked!"));
+    }
+
+    if chapters.is_empty() {
+        return Err(String::from("This song has no chapters!"));
+    }
+
+    let position = match track.get_info().await {
+        Ok(info) => info.position,
+        Err(err) => {
+            error!("Failed to get track info in guild {}: {}", guild_id, err);
+            return Err(String::from("Error getting track information!"));
+        }
+    };
+
+    let Some(index) = resolve_target(chapters, current_chapter(chapters, position), target) else {
+        return Err(String::from("There is no such chapter!"));
+    };
+
+    if let Err(err) = track.seek_async(chapters[index].start).await {
+        error!("Failed to seek to chapter in guild {}: {}", guild_id, err);
+        return Err(String::from("Error changing chapter!"));
+    }
+
+    info!(
+        "Jumped to chapter {} of '{}' in guild {}",
+        index + 1,
+        metadata.title,
+        guild_id
+    );
+
+    Ok(ChapterSeek {
+        description: format!("Jumped to **{}**", describe_chapter(chapters, index)),
+        menu: create_chapter_menu(chapters, Some(index)),
+    })
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("chapter")
+        .description("Jump between the chapters of the current song")
+        .add_option(
+            serenity::builder::CreateCommandOption::new(
+                CommandOptionType::String,
+                "chapter",
+                "next, prev or a chapter number",
+            )
+            .required(true),
+        )
+}
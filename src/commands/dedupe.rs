@@ -0,0 +1,62 @@
+use serenity::{
+    all::{Color, CommandInteraction, CreateEmbed},
+    client::Context,
+};
+use tracing::{error, info};
+
+use crate::utils::{duplicates::remove_duplicates, response::respond_to_followup};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer dedupe command: {}", err);
+        return;
+    }
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    let guild_id = command.guild_id.unwrap();
+
+    let Some(call) = manager.get(guild_id) else {
+        let embed = CreateEmbed::new()
+            .description(
+                "Error removing duplicates! Ensure Poor Jimmy is in a voice channel with **/join**",
+            )
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    };
+
+    let handler = call.lock().await;
+
+    let removed = remove_duplicates(handler.queue());
+
+    let description = if removed.is_empty() {
+        String::from("There are no duplicates in the queue!")
+    } else {
+        info!(
+            "Removed {} duplicate tracks in guild {}",
+            removed.len(),
+            guild_id
+        );
+
+        let titles = removed
+            .iter()
+            .map(|title| format!("• {}", title))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        format!("**Removed {} duplicates!**\n{}", removed.len(), titles)
+    };
+
+    let embed = CreateEmbed::new()
+        .description(description)
+        .color(Color::DARK_GREEN);
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("dedupe")
+        .description("Remove songs that are queued more than once")
+}
@@ -0,0 +1,113 @@
+use serenity::{
+    all::{
+        Color, CommandDataOptionValue, CommandInteraction, CommandOptionType, ComponentInteraction,
+        CreateEmbed, EditInteractionResponse,
+    },
+    client::Context,
+    model::Permissions,
+};
+use tracing::{error, info};
+
+use crate::{
+    components::duplicate_buttons::QUEUE_ANYWAY_PREFIX,
+    utils::{
+        duplicates::DuplicatePolicy,
+        guild_settings::update_guild_settings,
+        metadata_cache::{cached_source, get_metadata_cache},
+        response::{respond_to_error_button, respond_to_followup},
+        track_utils::enqueue_track_component,
+        type_map::get_http_client,
+        youtube::watch_url,
+    },
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer duplicate-policy command: {}", err);
+        return;
+    }
+
+    let policy = match command.data.options.first().map(|data| &data.value) {
+        Some(CommandDataOptionValue::String(value)) => DuplicatePolicy::parse(value),
+        _ => None,
+    };
+
+    let Some(policy) = policy else {
+        let embed = CreateEmbed::new()
+            .description("Please choose **allow**, **warn** or **reject**!")
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    };
+
+    let guild_id = command.guild_id.unwrap();
+
+    update_guild_settings(&ctx.data, guild_id, |settings| {
+        settings.duplicate_policy = policy;
+    })
+    .await;
+
+    info!(
+        "Duplicate policy set to {} in guild {}",
+        policy.name(),
+        guild_id
+    );
+
+    let description = match policy {
+        DuplicatePolicy::Allow => "Songs already in the queue can be **queued again**",
+        DuplicatePolicy::Warn => {
+            "Requesting a song already in the queue will **ask first** before queueing it again"
+        }
+        DuplicatePolicy::Reject => "Songs already in the queue **can't be queued again**",
+    };
+
+    let embed = CreateEmbed::new()
+        .description(description)
+        .color(Color::DARK_GREEN);
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+/// Queue the track a duplicate warning was shown for
+pub async fn handle_queue_anyway(ctx: &Context, interaction: &ComponentInteraction) {
+    if let Err(err) = interaction.defer(&ctx.http).await {
+        error!("Failed to defer queue anyway interaction: {}", err);
+        return;
+    }
+
+    let Some(video_id) = interaction.data.custom_id.strip_prefix(QUEUE_ANYWAY_PREFIX) else {
+        error!("Invalid custom_id format: {}", interaction.data.custom_id);
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    // Remove the button so the track isn't queued a third time
+    if let Err(err) = interaction
+        .edit_response(&ctx.http, EditInteractionResponse::new().components(vec![]))
+        .await
+    {
+        error!("Failed to remove queue anyway button: {}", err);
+    }
+
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+    let source = cached_source(http_client, &cache, watch_url(video_id));
+
+    enqueue_track_component(ctx, interaction, source, true).await;
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("duplicate-policy")
+        .description("Choose what happens when a song already in the queue is requested")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            serenity::builder::CreateCommandOption::new(
+                CommandOptionType::String,
+                "policy",
+                "What to do with duplicate requests",
+            )
+            .add_string_choice("Allow", "allow")
+            .add_string_choice("Warn and ask", "warn")
+            .add_string_choice("Reject", "reject")
+            .required(true),
+        )
+}
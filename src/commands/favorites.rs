@@ -0,0 +1,363 @@
+use rand::seq::SliceRandom;
+use serenity::{
+    all::{
+        CommandDataOption, CommandInteraction, CommandOptionType, ComponentInteraction,
+        CreateInteractionResponse, UserId,
+    },
+    builder::{
+        CreateActionRow, CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+        CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    },
+    client::Context,
+    model::colour::Color,
+};
+use tracing::{error, info};
+
+use crate::{
+    components::favorite_buttons::{create_favorites_page_buttons, parse_favorites_page},
+    utils::{
+        favorites::{FAVORITES_PER_PAGE, FavoriteTrack, FavoritesKey, page_count, save_favorites},
+        format::format_duration,
+        guild_settings::get_guild_settings,
+        limits::playlist_size_allowed,
+        metadata_cache::{cached_source, get_metadata_cache},
+        options::subcommand,
+        response::respond_to_followup,
+        track_utils::{enqueue_track_list, track_metadata},
+        type_map::get_http_client,
+    },
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    // Favorites are personal, so the list is only shown to its owner
+    let defer = match subcommand(command) {
+        Some(("list", _)) => command.defer_ephemeral(&ctx.http).await,
+        _ => command.defer(&ctx.http).await,
+    };
+
+    if let Err(err) = defer {
+        error!("Failed to defer favorites command: {}", err);
+        return;
+    }
+
+    let result = match subcommand(command) {
+        Some(("list", options)) => {
+            let page = integer_option(options, "page").map_or(0, |page| page.saturating_sub(1));
+            list(ctx, command, page).await;
+            return;
+        }
+        Some(("play", options)) => play(ctx, command, options).await,
+        Some(("remove", options)) => remove(ctx, command.user.id, options).await,
+        _ => Err(String::from("Unknown favorites command!")),
+    };
+
+    let embed = match result {
+        Ok(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_GREEN),
+        Err(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_RED),
+    };
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+fn integer_option(options: &[CommandDataOption], name: &str) -> Option<usize> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_i64())
+        .and_then(|value| usize::try_from(value).ok())
+}
+
+async fn list(ctx: &Context, command: &CommandInteraction, page: usize) {
+    let favorites = favorites_of(ctx, command.user.id).await;
+    let (embed, buttons) = favorites_page(&favorites, page);
+
+    let mut message = CreateInteractionResponseFollowup::new()
+        .embed(embed)
+        .ephemeral(true);
+
+    if let Some(buttons) = buttons {
+        message = message.components(vec![buttons]);
+    }
+
+    if let Err(err) = command.create_followup(&ctx.http, message).await {
+        error!("Failed to send favorites: {}", err);
+    }
+}
+
+async fn favorites_of(ctx: &Context, user_id: UserId) -> Vec<FavoriteTrack> {
+    let data = ctx.data.read().await;
+    data.get::<FavoritesKey>()
+        .expect("Guaranteed to exist in the typemap.")
+        .list(user_id)
+        .to_vec()
+}
+
+/// One page of favorites, with page buttons when there is more than one
+fn favorites_page(
+    favorites: &[FavoriteTrack],
+    page: usize,
+) -> (CreateEmbed, Option<CreateActionRow>) {
+    if favorites.is_empty() {
+        let embed = CreateEmbed::new()
+            .description("You have no favorites yet! Press ❤️ on a song you like")
+            .color(Color::DARK_RED);
+        return (embed, None);
+    }
+
+    let pages = page_count(favorites.len());
+    let page = page.min(pages - 1);
+    let first = page * FAVORITES_PER_PAGE;
+
+    let lines = favorites
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(FAVORITES_PER_PAGE)
+        .map(|(index, favorite)| match favorite.track.duration {
+            Some(duration) => format!(
+                "{}. [{}]({}) ({})",
+                index + 1,
+                favorite.track.title,
+                favorite.track.url,
+                format_duration(duration)
+            ),
+            None => format!(
+                "{}. [{}]({})",
+                index + 1,
+                favorite.track.title,
+                favorite.track.url
+            ),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let embed = CreateEmbed::new()
+        .title("❤️ Your favorites")
+        .description(lines)
+        .footer(CreateEmbedFooter::new(format!(
+            "Page {} of {} • {} tracks",
+            page + 1,
+            pages,
+            favorites.len()
+        )))
+        .color(Color::BLUE);
+
+    let buttons = (pages > 1).then(|| create_favorites_page_buttons(page, pages));
+
+    (embed, buttons)
+}
+
+/// Handle the page buttons of `/favorites list`
+pub async fn handle_page_button(ctx: &Context, interaction: &ComponentInteraction) {
+    let page = parse_favorites_page(&interaction.data.custom_id).unwrap_or_default();
+
+    let favorites = favorites_of(ctx, interaction.user.id).await;
+    let (embed, buttons) = favorites_page(&favorites, page);
+
+    let message = CreateInteractionResponseMessage::new()
+        .embed(embed)
+        .components(buttons.into_iter().collect());
+
+    if let Err(err) = interaction
+        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(message))
+        .await
+    {
+        error!("Failed to update favorites page: {}", err);
+    }
+}
+
+/// Queue every favorite, in order or shuffled
+async fn play(
+    ctx: &Context,
+    command: &CommandInteraction,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let mut favorites = favorites_of(ctx, command.user.id).await;
+
+    if favorites.is_empty() {
+        return Err(String::from(
+            "You have no favorites yet! Press ❤️ on a song you like",
+        ));
+    }
+
+    let shuffle = options
+        .iter()
+        .find(|option| option.name == "shuffle")
+        .and_then(|option| option.value.as_bool())
+        .unwrap_or(false);
+
+    if shuffle {
+        favorites.shuffle(&mut rand::rng());
+    }
+
+    let limits = get_guild_settings(&ctx.data, command.guild_id.unwrap())
+        .await
+        .limits;
+    // Shuffled first so a capped shuffle still picks from every favorite
+    let allowed = playlist_size_allowed(&limits, favorites.len());
+    let left_out = favorites.len() - allowed;
+    favorites.truncate(allowed);
+
+    info!(
+        "Queueing {} favorites of user {} in guild {}",
+        favorites.len(),
+        command.user.id,
+        command.guild_id.unwrap()
+    );
+
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+    let sources = favorites
+        .iter()
+        .map(|favorite| cached_source(http_client.clone(), &cache, favorite.track.url.clone()))
+        .collect();
+    let mut summary = enqueue_track_list(ctx, command, sources).await;
+    summary.left_out = left_out;
+
+    let description = summary.describe(&format!(
+        "**Queued** {} of your favorites{}!",
+        summary.queued,
+        if shuffle { " shuffled" } else { "" }
+    ));
+
+    match summary.stopped_by {
+        Some(rejection) if summary.queued == 0 => Err(rejection.description()),
+        _ => Ok(description),
+    }
+}
+
+async fn remove(
+    ctx: &Context,
+    user_id: UserId,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let Some(position) = integer_option(options, "position") else {
+        return Err(String::from(
+            "Please provide the position of the favorite to remove!",
+        ));
+    };
+
+    let mut data = ctx.data.write().await;
+    let favorites = data
+        .get_mut::<FavoritesKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let removed = favorites
+        .remove(user_id, position)
+        .ok_or_else(|| String::from("You have no favorite at that position!"))?;
+    save_favorites(favorites);
+
+    Ok(format!(
+        "**Removed** {} from your favorites!",
+        removed.track.title
+    ))
+}
+
+/// Handle the ❤️ button of the "Now playing" message by adding the current
+/// track to the favorites of whoever pressed it
+pub async fn handle_favorite_button(ctx: &Context, interaction: &ComponentInteraction) {
+    let guild_id = interaction.guild_id.unwrap();
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    let metadata = match manager.get(guild_id) {
+        Some(call) => call
+            .lock()
+            .await
+            .queue()
+            .current()
+            .map(|track| track_metadata(&track)),
+        None => None,
+    };
+
+    let result = match metadata {
+        None => Err(String::from("No song is currently playing!")),
+        Some(metadata) => match FavoriteTrack::from_metadata(&metadata) {
+            None => Err(String::from("This track can't be added to favorites!")),
+            Some(favorite) => {
+                let title = favorite.track.title.clone();
+
+                let mut data = ctx.data.write().await;
+                let favorites = data
+                    .get_mut::<FavoritesKey>()
+                    .expect("Guaranteed to exist in the typemap.");
+
+                match favorites.add(interaction.user.id, favorite) {
+                    Ok(()) => {
+                        save_favorites(favorites);
+                        info!("User {} favorited '{}'", interaction.user.id, title);
+                        Ok(format!("❤️ **Added** {} to your favorites!", title))
+                    }
+                    Err(err) => Err(err.description()),
+                }
+            }
+        },
+    };
+
+    let embed = match result {
+        Ok(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_GREEN),
+        Err(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_RED),
+    };
+
+    // Only the user who pressed the button needs to see the result
+    let message = CreateInteractionResponseMessage::new()
+        .embed(embed)
+        .ephemeral(true);
+
+    if let Err(err) = interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(message))
+        .await
+    {
+        error!("Failed to send favorite response: {}", err);
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("favorites")
+        .description("Songs you liked with the ❤️ button")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show your favorites")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "page", "Page to show")
+                        .min_int_value(1),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "play",
+                "Queue all of your favorites",
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "shuffle",
+                "Queue them in a random order",
+            )),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "Remove a song from your favorites",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "position",
+                    "Position of the song, as shown by /favorites list",
+                )
+                .required(true)
+                .min_int_value(1),
+            ),
+        )
+}
@@ -38,22 +38,63 @@ First, join a voice channel, then use `/join` to bring Poor Jimmy into your chan
 
 • `/play-url <url>` - Play a specific YouTube video or share link
   Example: `/play-url https://youtube.com/watch?v=...`
+  Both also take `position:` with `next`, `now` or a queue number to skip the line (not for albums and playlists)
+  Both also take `position:` with `next`, `now` or a queue number to skip the line
+
+• `/play-next <url or title>` - Queue a song straight after the current one
+
+• `/play-now <url or title>` - Interrupt the current song, which carries on afterwards
+
+• `/play-file <file>` - Play an uploaded audio file
+  You can also right-click a message with an audio file and pick **Apps > Play in voice**
+
+• `/library search <query>` - Search the bot's local music library, which picks up new files every few minutes
+• `/library play album:<album>` or `artist:<artist>` - Queue an album or everything by an artist from the library
+
+• `/podcast <feed>` - Browse a podcast's episodes and queue them with the buttons
+  DJs can subscribe the server to a feed, subscribed feeds are suggested as you type
+
+• `/myplaylist create <name>` - Start your own playlist, kept by the bot
+  Fill it with `/myplaylist add` or the **➕ Save** button, then queue it with `/myplaylist play`
+  See them with `/myplaylist list`, tidy up with `/myplaylist remove` and `/myplaylist delete`
+
+• `/serverplaylist` - The same for playlists shared by the whole server, which anyone can play and DJs can edit
+
+• `/favorites list` - See the songs you liked with the **❤️ Favorite** button
+  Queue them with `/favorites play`, add `shuffle:True` to mix them up
+
+• `/playlist <url>` - Queue a YouTube playlist, with a progress bar you can cancel
+  Add `shuffle:True` to mix it up, `start:` to begin further in and `limit:` to queue fewer videos
+
+• `/playlist-export <format>` - Download the queue or a saved playlist as a JSON, M3U or XSPF file
+• `/playlist-import <file>` - Queue the YouTube videos in a playlist file, or keep them with `save_as:`
 
 • `/search <query>` - Search YouTube and select from results
   Example: `/search lofi hip hop`
 
+• `/radio play <station>` - Play an internet radio station by preset name or stream URL
+  DJs save stations for the server with `/radio save`, anyone can see them with `/radio list`
+
 **Playback Controls**
 • `/pause` - Pause the current song
 • `/resume` - Resume playback
 • `/skip` - Skip to the next song in queue
+• `/previous` - Queue the last played song to play next
+• `/history [count]` - List recently played songs and pick any of them to queue again
 • `/loop` - Toggle looping for the current song
 • `/now-playing` - Show current song with progress bar
+• `/chapter <next|prev|number>` - Jump between the chapters of the current song
 
 **Queue Management**
-• `/list` - View all songs in the queue
+• `/list` - View all songs in the queue and its total length
 • `/clear` - Stop playback and clear the entire queue
+• `/dedupe` - Remove songs that are queued more than once
+• `/duplicate-policy <allow|warn|reject>` - Choose what happens when a queued song is requested again
+• `/autoplay` - Toggle queueing related songs when the queue ends
+• `/limits` - Show or change the server's queue limits (requires Manage Server)
 
 **Other Commands**
+• `/stats` - See the server's top tracks, top requesters and most skipped songs over a day, week, month or all time
 • `/join` - Summon Poor Jimmy to your voice channel
 • `/leave` - Remove Poor Jimmy from the voice channel
 • `/ping` - Check if the bot is responsive
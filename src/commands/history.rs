@@ -0,0 +1,191 @@
+use serenity::{
+    all::{
+        ChannelId, CommandDataOption, CommandInteraction, CommandOptionType, ComponentInteraction,
+        ComponentInteractionDataKind, GuildId, UserId,
+    },
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponseFollowup},
+    client::Context,
+    model::colour::Color,
+};
+use songbird::input::YoutubeDl;
+use tracing::{error, info};
+
+use crate::{
+    components::history_menu::{MAX_OPTIONS, create_history_menu},
+    utils::{
+        enqueue_summary::EnqueueSummary,
+        format::format_duration,
+        history::{HistoryEntry, history_capacity, recent_history, unique_tracks},
+        response::{respond_to_button, respond_to_error_button, respond_to_followup},
+        track_utils::enqueue_resolved_track_for,
+        type_map::get_http_client,
+    },
+};
+
+/// Tracks listed when no count is given
+const DEFAULT_COUNT: usize = 10;
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer history command: {}", err);
+        return;
+    }
+
+    let count = count_option(&command.data.options)
+        .unwrap_or(DEFAULT_COUNT)
+        .clamp(1, MAX_OPTIONS);
+
+    let entries = recently_played(ctx, command.guild_id.unwrap()).await;
+    let entries: Vec<HistoryEntry> = entries.into_iter().take(count).collect();
+
+    if entries.is_empty() {
+        let embed = CreateEmbed::new()
+            .description("Nothing has been played yet!")
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    }
+
+    let embed = CreateEmbed::new()
+        .title("🕘 Recently played")
+        .description(format_history(&entries))
+        .color(Color::DARK_GREEN);
+
+    let mut message = CreateInteractionResponseFollowup::new().embed(embed);
+
+    if let Some(menu) = create_history_menu(&entries) {
+        message = message.components(vec![menu]);
+    }
+
+    if let Err(err) = command.create_followup(&ctx.http, message).await {
+        error!("Failed to send history response: {}", err);
+    }
+}
+
+/// Queue the tracks picked from the `/history` menu at the end of the queue
+pub async fn handle_select(ctx: &Context, interaction: &ComponentInteraction) {
+    let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind else {
+        error!("Invalid history selection: {:?}", interaction.data.kind);
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    let guild_id = interaction.guild_id.unwrap();
+
+    match requeue(
+        ctx,
+        guild_id,
+        interaction.channel_id,
+        interaction.user.id,
+        values,
+    )
+    .await
+    {
+        Ok(description) => respond_to_button(interaction, &ctx.http, description, false).await,
+        Err(description) => respond_to_error_button(interaction, &ctx.http, description).await,
+    }
+}
+
+/// The guild's finished tracks, most recent first, each listed once
+async fn recently_played(ctx: &Context, guild_id: GuildId) -> Vec<HistoryEntry> {
+    unique_tracks(recent_history(&ctx.data, guild_id, history_capacity()).await)
+}
+
+/// Queue the history entries with the given URLs again, in the order they
+/// were listed, subject to the guild's limits and duplicate policy. Returns
+/// the message to show the user.
+async fn requeue(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+    source_urls: &[String],
+) -> Result<String, String> {
+    let entries: Vec<HistoryEntry> = recently_played(ctx, guild_id)
+        .await
+        .into_iter()
+        .filter(|entry| source_urls.contains(&entry.source_url))
+        .collect();
+
+    if entries.is_empty() {
+        return Err(String::from(
+            "Those songs aren't in the history anymore! Try **/history** again",
+        ));
+    }
+
+    let http_client = get_http_client(ctx).await;
+    let mut summary = EnqueueSummary::default();
+    let mut queued_title = None;
+
+    for entry in entries {
+        let source = YoutubeDl::new(http_client.clone(), entry.source_url);
+        let title = entry.metadata.title.clone();
+
+        info!("Re-queueing '{}' from history in guild {}", title, guild_id);
+
+        let result = enqueue_resolved_track_for(
+            ctx,
+            guild_id,
+            user_id,
+            channel_id,
+            source.into(),
+            entry.metadata,
+        )
+        .await;
+
+        if result.is_ok() {
+            queued_title = Some(title);
+        }
+
+        if !summary.record(result) {
+            break;
+        }
+    }
+
+    let headline = match queued_title {
+        Some(title) if summary.queued == 1 => format!("**Queued** {}!", title),
+        _ => format!("**Queued** {} songs from the history!", summary.queued),
+    };
+    let description = summary.describe(&headline);
+
+    match summary.stopped_by {
+        Some(rejection) if summary.queued == 0 => Err(rejection.description()),
+        _ if summary.queued == 0 => Err(description),
+        _ => Ok(description),
+    }
+}
+
+fn format_history(entries: &[HistoryEntry]) -> String {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| match entry.metadata.duration {
+            Some(duration) if !entry.metadata.is_live => format!(
+                "{}. {} ({})",
+                index + 1,
+                entry.metadata.title,
+                format_duration(duration)
+            ),
+            _ => format!("{}. {}", index + 1, entry.metadata.title),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn count_option(options: &[CommandDataOption]) -> Option<usize> {
+    options
+        .iter()
+        .find(|option| option.name == "count")
+        .and_then(|option| option.value.as_i64())
+        .and_then(|value| usize::try_from(value).ok())
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("history")
+        .description("List recently played songs and queue them again")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "count", "Songs to list")
+                .min_int_value(1)
+                .max_int_value(MAX_OPTIONS as u64),
+        )
+}
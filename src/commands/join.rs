@@ -79,6 +79,7 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
                     call: call.clone(),
                     guild_id,
                     manager: manager.clone(),
+                    data: ctx.data.clone(),
                 },
             );
 
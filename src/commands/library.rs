@@ -0,0 +1,230 @@
+use serenity::{
+    all::{
+        CommandDataOption, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+        CreateInteractionResponse,
+    },
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed},
+    client::Context,
+    model::colour::Color,
+};
+use songbird::input::File;
+use tracing::{error, info};
+
+use crate::utils::{
+    enqueue_summary::EnqueueSummary,
+    format::format_duration,
+    guild_settings::get_guild_settings,
+    library::{LibraryGroup, LibraryKey, LibraryTrack, library_dir},
+    limits::playlist_size_allowed,
+    options::{string_option, subcommand},
+    response::respond_to_followup,
+    track_utils::enqueue_resolved_track_list,
+};
+
+/// Most results `/library search` lists
+const MAX_SEARCH_RESULTS: usize = 10;
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer library command: {}", err);
+        return;
+    }
+
+    let result = if library_dir().is_none() {
+        Err(String::from("The music library isn't set up on this bot!"))
+    } else {
+        match subcommand(command) {
+            Some(("search", options)) => search(ctx, options).await,
+            Some(("play", options)) => play(ctx, command, options).await,
+            _ => Err(String::from("Unknown library command!")),
+        }
+    };
+
+    let embed = match result {
+        Ok(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_GREEN),
+        Err(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_RED),
+    };
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+async fn search(ctx: &Context, options: &[CommandDataOption]) -> Result<String, String> {
+    let Some(query) = string_option(options, "query") else {
+        return Err(String::from("Please provide something to search for!"));
+    };
+
+    let data = ctx.data.read().await;
+    let library = data
+        .get::<LibraryKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let results = library.search(query);
+    if results.is_empty() {
+        return Err(format!("Nothing in the library matches **{}**!", query));
+    }
+
+    let lines = results
+        .iter()
+        .take(MAX_SEARCH_RESULTS)
+        .map(|track| describe_track(track))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut description = format!("**Library results for {}:**\n{}", query, lines);
+
+    if results.len() > MAX_SEARCH_RESULTS {
+        description.push_str(&format!(
+            "\n...and {} more",
+            results.len() - MAX_SEARCH_RESULTS
+        ));
+    }
+
+    Ok(description)
+}
+
+fn describe_track(track: &LibraryTrack) -> String {
+    let mut line = format!("🎵 **{}**", track.display_title());
+
+    if let Some(album) = &track.album {
+        line.push_str(&format!(" - *{}*", album));
+    }
+
+    if let Some(duration) = track.duration {
+        line.push_str(&format!(" ({})", format_duration(duration)));
+    }
+
+    line
+}
+
+/// Queue every track of an album or artist
+async fn play(
+    ctx: &Context,
+    command: &CommandInteraction,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let (group, name) = match (
+        string_option(options, "album"),
+        string_option(options, "artist"),
+    ) {
+        (Some(album), _) => (LibraryGroup::Album, album),
+        (None, Some(artist)) => (LibraryGroup::Artist, artist),
+        (None, None) => return Err(String::from("Please choose an album or an artist!")),
+    };
+
+    let tracks: Vec<LibraryTrack> = {
+        let data = ctx.data.read().await;
+        data.get::<LibraryKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .tracks_in(group, name)
+            .into_iter()
+            .cloned()
+            .collect()
+    };
+
+    if tracks.is_empty() {
+        return Err(format!("Nothing in the library by **{}**!", name));
+    }
+
+    let limits = get_guild_settings(&ctx.data, command.guild_id.unwrap())
+        .await
+        .limits;
+    let allowed = playlist_size_allowed(&limits, tracks.len());
+
+    info!(
+        "Queueing {} library tracks for '{}' in guild {}",
+        allowed,
+        name,
+        command.guild_id.unwrap()
+    );
+
+    let mut summary = EnqueueSummary {
+        left_out: tracks.len() - allowed,
+        ..Default::default()
+    };
+
+    for track in tracks[..allowed].iter() {
+        let source = File::new(track.path.clone());
+        let result =
+            enqueue_resolved_track_list(ctx, command, source.into(), track.metadata()).await;
+        if !summary.record(result) {
+            break;
+        }
+    }
+
+    let description = summary.describe(&format!(
+        "**Queued** {} tracks from **{}**!",
+        summary.queued, name
+    ));
+
+    match summary.stopped_by {
+        Some(rejection) if summary.queued == 0 => Err(rejection.description()),
+        _ => Ok(description),
+    }
+}
+
+/// Suggest album and artist names while the user types
+pub async fn autocomplete(ctx: &Context, command: &CommandInteraction) {
+    let Some(focused) = command.data.autocomplete() else {
+        return;
+    };
+
+    let names = match LibraryGroup::parse(focused.name) {
+        Some(group) => {
+            let data = ctx.data.read().await;
+            data.get::<LibraryKey>()
+                .expect("Guaranteed to exist in the typemap.")
+                .names(group, focused.value)
+        }
+        None => Vec::new(),
+    };
+
+    let response = names
+        .into_iter()
+        // Choice names and values are capped at 100 characters
+        .filter(|name| name.chars().count() <= 100)
+        .fold(CreateAutocompleteResponse::new(), |response, name| {
+            response.add_string_choice(name.clone(), name)
+        });
+
+    if let Err(err) = command
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await
+    {
+        error!("Failed to send library autocomplete: {}", err);
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("library")
+        .description("Play music from the bot's local library")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "search",
+                "Search the library by title, artist or album",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "query", "What to look for")
+                    .required(true),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "play",
+                "Queue a whole album or everything by an artist",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "album", "Album name")
+                    .set_autocomplete(true),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "artist", "Artist name")
+                    .set_autocomplete(true),
+            ),
+        )
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use serenity::{
+    all::{Color, CommandDataOptionValue, CommandInteraction, CommandOptionType, CreateEmbed},
+    client::Context,
+    model::Permissions,
+};
+use tracing::{error, info};
+
+use crate::utils::{
+    guild_settings::update_guild_settings, limits::describe_limits, response::respond_to_followup,
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer limits command: {}", err);
+        return;
+    }
+
+    let guild_id = command.guild_id.unwrap();
+
+    // Every option is a new value for one limit, 0 removes the limit
+    let changes: Vec<(&str, Option<usize>)> = command
+        .data
+        .options
+        .iter()
+        .filter_map(|option| match option.value {
+            CommandDataOptionValue::Integer(value) => Some((
+                option.name.as_str(),
+                usize::try_from(value).ok().filter(|value| *value > 0),
+            )),
+            _ => None,
+        })
+        .collect();
+
+    let limits = update_guild_settings(&ctx.data, guild_id, |settings| {
+        for (name, value) in changes.iter() {
+            match *name {
+                "track_minutes" => {
+                    settings.limits.max_track_duration =
+                        value.map(|minutes| Duration::from_secs(minutes as u64 * 60))
+                }
+                "queue_length" => settings.limits.max_queue_length = *value,
+                "tracks_per_user" => settings.limits.max_tracks_per_user = *value,
+                "playlist_size" => settings.limits.max_playlist_size = *value,
+                _ => {}
+            }
+        }
+
+        settings.limits.clone()
+    })
+    .await;
+
+    let title = if changes.is_empty() {
+        "Queue limits"
+    } else {
+        info!("Updated queue limits in guild {}: {:?}", guild_id, limits);
+        "Updated queue limits"
+    };
+
+    let embed = CreateEmbed::new()
+        .title(title)
+        .description(describe_limits(&limits))
+        .color(Color::DARK_GREEN);
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    let option = |name: &str, description: &str| {
+        serenity::builder::CreateCommandOption::new(CommandOptionType::Integer, name, description)
+            .min_int_value(0)
+            .required(false)
+    };
+
+    serenity::builder::CreateCommand::new("limits")
+        .description("Show or change this server's queue limits (0 removes a limit)")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(option(
+            "track_minutes",
+            "Longest track that can be queued, in minutes",
+        ))
+        .add_option(option("queue_length", "Most tracks the queue can hold"))
+        .add_option(option(
+            "tracks_per_user",
+            "Most tracks one user can have queued",
+        ))
+        .add_option(option(
+            "playlist_size",
+            "Most tracks a playlist import can add",
+        ))
+}
@@ -1,10 +1,26 @@
 use serenity::{
-    all::{Color, CommandInteraction, CreateEmbed},
+    all::{ChannelId, Color, CommandInteraction, CreateEmbed, GuildId, Http, MessageId},
+    builder::{CreateInteractionResponseFollowup, EditMessage},
     client::Context,
+    prelude::{RwLock, TypeMap, TypeMapKey},
 };
-use tracing::error;
+use songbird::{serenity::SongbirdKey, tracks::TrackHandle};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tracing::{error, warn};
+
+use crate::utils::{
+    format::{format_duration, total_duration},
+    response::respond_to_followup,
+    track_utils::track_metadata,
+};
+
+/// The latest `/list` message of each guild, kept up to date while tracks
+/// queued with placeholder metadata are filled in
+pub struct QueueMessagesKey;
 
-use crate::utils::{response::respond_to_followup, track_utils::TrackMetadata};
+impl TypeMapKey for QueueMessagesKey {
+    type Value = HashMap<GuildId, (ChannelId, MessageId)>;
+}
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
     if let Err(err) = command.defer(&ctx.http).await {
@@ -32,19 +48,16 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
             return;
         }
 
-        // Transform the Vec of TrackHandles into a Vec of titles
-        let queue_titles: Vec<String> = current_queue
-            .iter()
-            .map(|track| track.data::<TrackMetadata>().title.clone())
-            .collect();
-
-        // Build the response description string.
-        let response_description = format_queue_description(queue_titles);
-
-        let embed = CreateEmbed::new()
-            .description(response_description)
-            .color(Color::DARK_GREEN);
-        respond_to_followup(command, &ctx.http, embed, false).await;
+        let message = CreateInteractionResponseFollowup::new().embed(queue_embed(&current_queue));
+        match command.create_followup(&ctx.http, message).await {
+            Ok(message) => {
+                let mut data = ctx.data.write().await;
+                data.get_mut::<QueueMessagesKey>()
+                    .expect("Guaranteed to exist in the typemap.")
+                    .insert(guild_id, (message.channel_id, message.id));
+            }
+            Err(err) => error!("Failed to send followup response: {}", err),
+        }
     } else {
         let embed = CreateEmbed::new()
             .description(
@@ -55,6 +68,83 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
     }
 }
 
+/// Every queued track with its latest title, and the length of the queue
+fn queue_embed(queue: &[TrackHandle]) -> CreateEmbed {
+    let queue_metadata: Vec<_> = queue.iter().map(track_metadata).collect();
+
+    // Transform the Vec of TrackHandles into a Vec of titles
+    let queue_titles: Vec<String> = queue_metadata
+        .iter()
+        .map(|metadata| {
+            if metadata.is_live {
+                format!("🔴 {}", metadata.title)
+            } else {
+                metadata.title.clone()
+            }
+        })
+        .collect();
+
+    // Build the response description string.
+    let mut response_description = format_queue_description(queue_titles);
+
+    let (total, excluded) = total_duration(
+        queue_metadata
+            .iter()
+            .map(|metadata| (metadata.duration, metadata.is_live)),
+    );
+    response_description.push_str(&format_total_length(total, excluded));
+
+    CreateEmbed::new()
+        .description(response_description)
+        .color(Color::DARK_GREEN)
+}
+
+/// Show the latest metadata of `track` in the guild's `/list` message, if
+/// one was sent and the track is still queued
+pub async fn refresh_queue_message(
+    http: &Http,
+    data: &Arc<RwLock<TypeMap>>,
+    guild_id: GuildId,
+    track: &TrackHandle,
+) {
+    let (shown, manager) = {
+        let data = data.read().await;
+        let shown = data
+            .get::<QueueMessagesKey>()
+            .and_then(|messages| messages.get(&guild_id).copied());
+        (shown, data.get::<SongbirdKey>().cloned())
+    };
+
+    let (Some((channel_id, message_id)), Some(manager)) = (shown, manager) else {
+        return;
+    };
+    let Some(call) = manager.get(guild_id) else {
+        return;
+    };
+
+    let queue = call.lock().await.queue().current_queue();
+    if !queue.iter().any(|queued| queued.uuid() == track.uuid()) {
+        return;
+    }
+
+    if let Err(err) = channel_id
+        .edit_message(
+            http,
+            message_id,
+            EditMessage::new().embed(queue_embed(&queue)),
+        )
+        .await
+    {
+        warn!("Failed to update queue message: {}", err);
+
+        // Most likely deleted, stop trying to update it
+        let mut data = data.write().await;
+        if let Some(messages) = data.get_mut::<QueueMessagesKey>() {
+            messages.remove(&guild_id);
+        }
+    }
+}
+
 pub fn register() -> serenity::builder::CreateCommand {
     serenity::builder::CreateCommand::new("list").description("Display the current queue of songs")
 }
@@ -69,6 +159,22 @@ fn format_queue_description(list_of_titles: Vec<String>) -> String {
     description
 }
 
+/// Footer line with the length of the queue, noting tracks like live
+/// streams that have no length to count
+fn format_total_length(total: Duration, excluded: usize) -> String {
+    let mut line = format!("\n**Total length:** {}", format_duration(total));
+
+    if excluded > 0 {
+        line.push_str(&format!(
+            " (+{} live or unknown length {})",
+            excluded,
+            if excluded == 1 { "track" } else { "tracks" }
+        ));
+    }
+
+    line
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,6 +207,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_total_length() {
+        assert_eq!(
+            format_total_length(Duration::from_secs(330), 0),
+            "\n**Total length:** 05:30"
+        );
+        assert_eq!(
+            format_total_length(Duration::from_secs(60), 1),
+            "\n**Total length:** 01:00 (+1 live or unknown length track)"
+        );
+    }
+
     #[test]
     fn test_format_queue_description_with_special_characters() {
         let titles = vec![
@@ -5,7 +5,10 @@ use serenity::{
 use songbird::tracks::LoopState;
 use tracing::error;
 
-use crate::utils::response::{respond_to_button, respond_to_error_button, respond_to_followup};
+use crate::utils::{
+    response::{respond_to_button, respond_to_error_button, respond_to_followup},
+    track_utils::TrackMetadata,
+};
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
     if let Err(err) = command.defer(&ctx.http).await {
@@ -25,6 +28,18 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         // Grab the currrently playing song
         let current_song = handler.queue().current();
 
+        // Live streams never end, so there is nothing to loop
+        if let Some(track) = &current_song {
+            if track.data::<TrackMetadata>().is_live {
+                let embed = CreateEmbed::new()
+                    .description("Live streams can't be looped!")
+                    .color(Color::DARK_RED);
+                respond_to_followup(command, &ctx.http, embed, false).await;
+
+                return;
+            }
+        }
+
         // Grab the state of the current song
         let is_looping = match &current_song {
             Some(track) => {
@@ -114,6 +129,20 @@ pub async fn handle_button(ctx: &Context, command: &ComponentInteraction) {
         // Grab the currrently playing song
         let current_song = handler.queue().current();
 
+        // Live streams never end, so there is nothing to loop
+        if let Some(track) = &current_song {
+            if track.data::<TrackMetadata>().is_live {
+                respond_to_error_button(
+                    command,
+                    &ctx.http,
+                    format!("Live streams can't be looped!"),
+                )
+                .await;
+
+                return;
+            }
+        }
+
         // Grab the state of the current song
         let is_looping = match &current_song {
             Some(track) => {
@@ -1,15 +1,33 @@
+pub mod autoplay;
+pub mod chapter;
 pub mod clear;
+pub mod dedupe;
+pub mod duplicate_policy;
+pub mod favorites;
 pub mod help;
+pub mod history;
 pub mod join;
 pub mod leave;
+pub mod library;
+pub mod limits;
 pub mod list;
 pub mod r#loop;
+pub mod myplaylist;
 pub mod now_playing;
 pub mod pause;
 pub mod ping;
+pub mod play_file;
+pub mod play_next;
+pub mod play_now;
 pub mod play_title;
 pub mod play_url;
 pub mod playlist;
+pub mod playlist_export;
+pub mod playlist_import;
+pub mod podcast;
+pub mod previous;
+pub mod radio;
 pub mod resume;
 pub mod search;
 pub mod skip;
+pub mod stats;
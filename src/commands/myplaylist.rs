@@ -0,0 +1,649 @@
+use serenity::{
+    all::{
+        CommandDataOption, CommandInteraction, CommandOptionType, ComponentInteraction,
+        ComponentInteractionDataKind, CreateAutocompleteResponse, CreateInteractionResponse,
+    },
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponseMessage},
+    client::Context,
+    model::colour::Color,
+};
+use tracing::{error, info};
+
+use crate::{
+    components::save_track_menu::{SAVE_TRACK_PREFIX, create_save_track_menu},
+    utils::{
+        dj::{DJ_ONLY, is_dj},
+        format::format_duration,
+        guild_settings::get_guild_settings,
+        limits::playlist_size_allowed,
+        metadata_cache::{cached_search_source, cached_source, get_metadata_cache},
+        options::{string_option, subcommand},
+        response::{respond_to_button, respond_to_error_button, respond_to_followup},
+        saved_playlists::{
+            MAX_NAME_LENGTH, PlaylistOwner, PlaylistStoreKey, SavedPlaylist, SavedTrack,
+            save_playlists,
+        },
+        track_utils::{enqueue_track_list, resolve_metadata, track_metadata},
+        type_map::get_http_client,
+    },
+};
+
+/// Most tracks `/myplaylist list` shows for one playlist
+const MAX_LISTED_TRACKS: usize = 20;
+/// Subcommands that change server playlists and so need a DJ
+const EDIT_SUBCOMMANDS: [&str; 4] = ["create", "add", "remove", "delete"];
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer myplaylist command: {}", err);
+        return;
+    }
+
+    let guild_id = command.guild_id.unwrap();
+    let owner = playlist_owner(command);
+
+    let result = match subcommand(command) {
+        Some((name, _))
+            if EDIT_SUBCOMMANDS.contains(&name)
+                && matches!(owner, PlaylistOwner::Guild(_))
+                && !is_dj(ctx, guild_id, command.member.as_deref()) =>
+        {
+            Err(String::from(DJ_ONLY))
+        }
+        Some(("create", options)) => create(ctx, owner, options).await,
+        Some(("add", options)) => add(ctx, command, owner, options).await,
+        Some(("remove", options)) => remove(ctx, owner, options).await,
+        Some(("list", options)) => list(ctx, owner, options).await,
+        Some(("play", options)) => play(ctx, command, owner, options).await,
+        Some(("delete", options)) => delete(ctx, owner, options).await,
+        _ => Err(String::from("Unknown playlist command!")),
+    };
+
+    let embed = match result {
+        Ok(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_GREEN),
+        Err(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_RED),
+    };
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+/// Whose playlists a command works on: the server's for `/serverplaylist`
+/// or when the command's `server` option is set, otherwise the user's own
+pub fn playlist_owner(command: &CommandInteraction) -> PlaylistOwner {
+    let server = command.data.name == "serverplaylist"
+        || command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "server")
+            .and_then(|option| option.value.as_bool())
+            .unwrap_or(false);
+
+    match command.guild_id {
+        Some(guild_id) if server => PlaylistOwner::Guild(guild_id),
+        _ => PlaylistOwner::User(command.user.id),
+    }
+}
+
+fn name_option(options: &[CommandDataOption]) -> Result<&str, String> {
+    string_option(options, "name").ok_or_else(|| String::from("Please provide a playlist name!"))
+}
+
+async fn create(
+    ctx: &Context,
+    owner: PlaylistOwner,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let name = name_option(options)?;
+
+    let mut data = ctx.data.write().await;
+    let store = data
+        .get_mut::<PlaylistStoreKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    store
+        .playlists_mut(owner)
+        .create(name)
+        .map_err(|err| err.description(name))?;
+    save_playlists(store);
+
+    info!("Created playlist '{}' for {:?}", name, owner);
+
+    Ok(format!("**Created** playlist **{}**!", name))
+}
+
+/// Add a track by URL or title, or the one playing now when no query is
+/// given
+async fn add(
+    ctx: &Context,
+    command: &CommandInteraction,
+    owner: PlaylistOwner,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let name = name_option(options)?;
+    let guild_id = command.guild_id.unwrap();
+
+    let metadata = match string_option(options, "query") {
+        Some(query) => {
+            let http_client = get_http_client(ctx).await;
+            let cache = get_metadata_cache(&ctx.data).await;
+            let mut source = if query.starts_with("http://") || query.starts_with("https://") {
+                cached_source(http_client.clone(), &cache, query.to_string())
+            } else {
+                cached_search_source(http_client.clone(), &cache, query.to_string())
+            };
+
+            resolve_metadata(&http_client, &mut source, guild_id).await
+        }
+        None => {
+            let manager = songbird::get(ctx)
+                .await
+                .expect("Songbird Voice client placed in at initialization.");
+
+            let current = match manager.get(guild_id) {
+                Some(call) => call
+                    .lock()
+                    .await
+                    .queue()
+                    .current()
+                    .map(|track| track_metadata(&track)),
+                None => None,
+            };
+
+            let Some(metadata) = current else {
+                return Err(String::from(
+                    "No song is currently playing! Give a URL or title to add instead",
+                ));
+            };
+
+            metadata
+        }
+    };
+
+    let track = SavedTrack::from_metadata(&metadata)
+        .ok_or_else(|| String::from("That track can't be saved to a playlist!"))?;
+
+    save_track(ctx, owner, name, track).await
+}
+
+/// Append one track to a playlist and describe the result
+async fn save_track(
+    ctx: &Context,
+    owner: PlaylistOwner,
+    name: &str,
+    track: SavedTrack,
+) -> Result<String, String> {
+    let title = track.title.clone();
+
+    let mut data = ctx.data.write().await;
+    let store = data
+        .get_mut::<PlaylistStoreKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    store
+        .playlists_mut(owner)
+        .add(name, vec![track])
+        .map_err(|err| err.description(name))?;
+    save_playlists(store);
+
+    info!("Saved '{}' to playlist '{}' of {:?}", title, name, owner);
+
+    Ok(format!("**Saved** {} to **{}**!", title, name))
+}
+
+async fn remove(
+    ctx: &Context,
+    owner: PlaylistOwner,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let name = name_option(options)?;
+
+    let position = options
+        .iter()
+        .find(|option| option.name == "position")
+        .and_then(|option| option.value.as_i64())
+        .and_then(|position| usize::try_from(position).ok())
+        .ok_or_else(|| String::from("Please provide the position of the track to remove!"))?;
+
+    let mut data = ctx.data.write().await;
+    let store = data
+        .get_mut::<PlaylistStoreKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let removed = store
+        .playlists_mut(owner)
+        .remove(name, position)
+        .map_err(|err| err.description(name))?;
+    save_playlists(store);
+
+    Ok(format!("**Removed** {} from **{}**!", removed.title, name))
+}
+
+/// List a playlist's tracks, or every playlist when no name is given
+async fn list(
+    ctx: &Context,
+    owner: PlaylistOwner,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let data = ctx.data.read().await;
+    let playlists = data
+        .get::<PlaylistStoreKey>()
+        .expect("Guaranteed to exist in the typemap.")
+        .playlists(owner)
+        .map(|playlists| playlists.list())
+        .unwrap_or_default();
+
+    let Some(name) = string_option(options, "name") else {
+        if playlists.is_empty() {
+            return Err(String::from("There are no playlists yet!"));
+        }
+
+        let lines = playlists
+            .iter()
+            .map(|playlist| {
+                format!(
+                    "📁 **{}** - {} tracks",
+                    playlist.name,
+                    playlist.tracks.len()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        return Ok(format!("**Playlists:**\n{}", lines));
+    };
+
+    let playlist = playlists
+        .iter()
+        .find(|playlist| playlist.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("There is no playlist called **{}**!", name))?;
+
+    Ok(describe_playlist(playlist))
+}
+
+fn describe_playlist(playlist: &SavedPlaylist) -> String {
+    if playlist.tracks.is_empty() {
+        return format!("**{}** is empty!", playlist.name);
+    }
+
+    let lines = playlist
+        .tracks
+        .iter()
+        .enumerate()
+        .take(MAX_LISTED_TRACKS)
+        .map(|(index, track)| match track.duration {
+            Some(duration) => format!(
+                "{}. {} ({})",
+                index + 1,
+                track.title,
+                format_duration(duration)
+            ),
+            None => format!("{}. {}", index + 1, track.title),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut description = format!("**{}:**\n{}", playlist.name, lines);
+
+    if playlist.tracks.len() > MAX_LISTED_TRACKS {
+        description.push_str(&format!(
+            "\n...and {} more",
+            playlist.tracks.len() - MAX_LISTED_TRACKS
+        ));
+    }
+
+    description
+}
+
+/// Queue every track of a playlist
+async fn play(
+    ctx: &Context,
+    command: &CommandInteraction,
+    owner: PlaylistOwner,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let name = name_option(options)?;
+
+    let playlist = {
+        let data = ctx.data.read().await;
+        data.get::<PlaylistStoreKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .playlists(owner)
+            .and_then(|playlists| playlists.find(name))
+            .cloned()
+            .ok_or_else(|| format!("There is no playlist called **{}**!", name))?
+    };
+
+    if playlist.tracks.is_empty() {
+        return Err(format!("**{}** is empty!", playlist.name));
+    }
+
+    let limits = get_guild_settings(&ctx.data, command.guild_id.unwrap())
+        .await
+        .limits;
+    let allowed = playlist_size_allowed(&limits, playlist.tracks.len());
+
+    info!(
+        "Queueing {} tracks from saved playlist '{}' in guild {}",
+        allowed,
+        playlist.name,
+        command.guild_id.unwrap()
+    );
+
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+    let sources = playlist.tracks[..allowed]
+        .iter()
+        .map(|track| cached_source(http_client.clone(), &cache, track.url.clone()))
+        .collect();
+    let mut summary = enqueue_track_list(ctx, command, sources).await;
+    summary.left_out = playlist.tracks.len() - allowed;
+
+    let description = summary.describe(&format!(
+        "**Queued** {} tracks from **{}**!",
+        summary.queued, playlist.name
+    ));
+
+    match summary.stopped_by {
+        Some(rejection) if summary.queued == 0 => Err(rejection.description()),
+        _ => Ok(description),
+    }
+}
+
+async fn delete(
+    ctx: &Context,
+    owner: PlaylistOwner,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let name = name_option(options)?;
+
+    let mut data = ctx.data.write().await;
+    let store = data
+        .get_mut::<PlaylistStoreKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let deleted = store
+        .playlists_mut(owner)
+        .delete(name)
+        .map_err(|err| err.description(name))?;
+    save_playlists(store);
+
+    info!("Deleted playlist '{}' of {:?}", deleted.name, owner);
+
+    Ok(format!("**Deleted** playlist **{}**!", deleted.name))
+}
+
+/// Handle the save button of the "Now playing" message by asking which
+/// playlist the current track goes into
+pub async fn handle_save_button(ctx: &Context, interaction: &ComponentInteraction) {
+    let guild_id = interaction.guild_id.unwrap();
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    let current = match manager.get(guild_id) {
+        Some(call) => call.lock().await.queue().current(),
+        None => None,
+    };
+
+    let Some(track) = current else {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("No song is currently playing!"),
+        )
+        .await;
+        return;
+    };
+
+    if SavedTrack::from_metadata(&track_metadata(&track)).is_none() {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("This track can't be saved to a playlist!"),
+        )
+        .await;
+        return;
+    }
+
+    let playlists: Vec<SavedPlaylist> = {
+        let data = ctx.data.read().await;
+        data.get::<PlaylistStoreKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .playlists(PlaylistOwner::User(interaction.user.id))
+            .map(|playlists| playlists.list().to_vec())
+            .unwrap_or_default()
+    };
+
+    if playlists.is_empty() {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("You have no playlists yet! Make one with `/myplaylist create`"),
+        )
+        .await;
+        return;
+    }
+
+    // Remember the track by its uuid so the pick saves it even if the
+    // next song has started by then
+    let message = CreateInteractionResponseMessage::new()
+        .content("Which playlist should this track go into?")
+        .components(vec![create_save_track_menu(
+            &track.uuid().to_string(),
+            &playlists,
+        )])
+        .ephemeral(true);
+
+    if let Err(err) = interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(message))
+        .await
+    {
+        error!("Failed to send playlist picker: {}", err);
+    }
+}
+
+/// Handle a pick from the playlist menu of the save button
+pub async fn handle_save_select(ctx: &Context, interaction: &ComponentInteraction) {
+    let track_uuid = interaction
+        .data
+        .custom_id
+        .strip_prefix(SAVE_TRACK_PREFIX)
+        .unwrap_or_default();
+
+    let index = match &interaction.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => {
+            values.first().and_then(|value| value.parse::<usize>().ok())
+        }
+        _ => None,
+    };
+
+    let Some(index) = index else {
+        error!("Invalid playlist selection: {:?}", interaction.data.kind);
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    let guild_id = interaction.guild_id.unwrap();
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    // The track may have moved on from the head of the queue, but as long
+    // as it's still queued it can be found by its uuid
+    let metadata = match manager.get(guild_id) {
+        Some(call) => call
+            .lock()
+            .await
+            .queue()
+            .current_queue()
+            .into_iter()
+            .find(|track| track.uuid().to_string() == track_uuid)
+            .map(|track| track_metadata(&track)),
+        None => None,
+    };
+
+    let Some(track) = metadata.and_then(|metadata| SavedTrack::from_metadata(&metadata)) else {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("That track isn't in the queue anymore!"),
+        )
+        .await;
+        return;
+    };
+
+    let name = {
+        let data = ctx.data.read().await;
+        data.get::<PlaylistStoreKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .playlists(PlaylistOwner::User(interaction.user.id))
+            .and_then(|playlists| playlists.list().get(index))
+            .map(|playlist| playlist.name.clone())
+    };
+
+    let Some(name) = name else {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("That playlist doesn't exist anymore!"),
+        )
+        .await;
+        return;
+    };
+
+    let owner = PlaylistOwner::User(interaction.user.id);
+
+    match save_track(ctx, owner, &name, track).await {
+        Ok(description) => respond_to_button(interaction, &ctx.http, description, false).await,
+        Err(description) => respond_to_error_button(interaction, &ctx.http, description).await,
+    }
+}
+
+/// Suggest playlist names while the user types
+pub async fn autocomplete(ctx: &Context, command: &CommandInteraction) {
+    let Some(focused) = command.data.autocomplete() else {
+        return;
+    };
+
+    let query = focused.value.to_lowercase();
+
+    let names: Vec<String> = {
+        let data = ctx.data.read().await;
+        data.get::<PlaylistStoreKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .playlists(playlist_owner(command))
+            .map(|playlists| {
+                playlists
+                    .list()
+                    .iter()
+                    .filter(|playlist| playlist.name.to_lowercase().contains(&query))
+                    .map(|playlist| playlist.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let response = names
+        .into_iter()
+        .fold(CreateAutocompleteResponse::new(), |response, name| {
+            response.add_string_choice(name.clone(), name)
+        });
+
+    if let Err(err) = command
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await
+    {
+        error!("Failed to send playlist autocomplete: {}", err);
+    }
+}
+
+fn playlist_name_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "name", "Playlist name")
+        .required(true)
+        .set_autocomplete(true)
+}
+
+pub fn register() -> CreateCommand {
+    register_subcommands(
+        CreateCommand::new("myplaylist").description("Keep your own playlists on the bot"),
+    )
+}
+
+pub fn register_server() -> CreateCommand {
+    register_subcommands(
+        CreateCommand::new("serverplaylist")
+            .description("Playlists shared by the whole server, edited by DJs"),
+    )
+}
+
+fn register_subcommands(command: CreateCommand) -> CreateCommand {
+    command
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "create",
+                "Start a new playlist",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "name", "Playlist name")
+                    .required(true)
+                    .max_length(MAX_NAME_LENGTH),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "add",
+                "Add a track, or the one playing now",
+            )
+            .add_sub_option(playlist_name_option())
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "query",
+                "A Youtube URL or title, leave out to add the current track",
+            )),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "Remove a track from a playlist",
+            )
+            .add_sub_option(playlist_name_option())
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "position",
+                    "Position of the track, as shown by /myplaylist list",
+                )
+                .required(true)
+                .min_int_value(1),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "Show your playlists, or the tracks of one",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "name", "Playlist name")
+                    .set_autocomplete(true),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "play", "Queue a playlist")
+                .add_sub_option(playlist_name_option()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "delete", "Delete a playlist")
+                .add_sub_option(playlist_name_option()),
+        )
+}
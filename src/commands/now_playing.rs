@@ -1,11 +1,18 @@
 use serenity::{
-    all::{Color, CommandInteraction, CreateEmbed},
+    all::{Color, CommandInteraction, CreateEmbed, CreateInteractionResponseFollowup},
     client::Context,
 };
 use tracing::{error, warn};
 
-use crate::utils::{
-    format::create_progress_bar, response::respond_to_followup, track_utils::TrackMetadata,
+use crate::{
+    components::{chapter_menu::create_chapter_menu, music_buttons::create_music_buttons},
+    utils::{
+        chapters::{current_chapter, describe_chapter},
+        format::{create_live_badge, create_progress_bar},
+        response::respond_to_followup,
+        sponsorblock::describe_segments,
+        track_utils::track_metadata,
+    },
 };
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
@@ -36,7 +43,7 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         };
 
         // Get track metadata
-        let metadata = current_track.data::<TrackMetadata>();
+        let metadata = track_metadata(&current_track);
         let title = &metadata.title;
 
         // Get playback info
@@ -52,18 +59,65 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
             }
         };
 
-        // Format response with progress bar
-        let progress_bar = create_progress_bar(track_info.position, metadata.duration, 20);
+        // Format response with progress bar. Live streams have no length, so
+        // show how long the stream has been playing instead
+        let progress_bar = if metadata.is_live {
+            create_live_badge(track_info.play_time)
+        } else {
+            create_progress_bar(track_info.position, metadata.duration, 20)
+        };
+
+        let song = metadata
+            .station
+            .as_ref()
+            .and_then(|station| station.song())
+            .map(|song| format!("\n🎵 {}", song))
+            .unwrap_or_default();
+
+        let mut description = format!("**Now Playing:**\n{}{}\n\n{}", title, song, progress_bar);
+
+        let chapter = current_chapter(&metadata.chapters, track_info.position);
+        if let Some(index) = chapter {
+            description.push_str(&format!(
+                "\n\n📖 **{}**",
+                describe_chapter(&metadata.chapters, index)
+            ));
+        }
+
+        // Segments already behind the playhead were skipped by SponsorBlock
+        let skipped_segments: Vec<_> = metadata
+            .sponsor_segments
+            .iter()
+            .filter(|segment| segment.end <= track_info.position)
+            .collect();
+
+        if !skipped_segments.is_empty() {
+            description.push_str(&format!(
+                "\n\n**Skipped by SponsorBlock:**\n{}",
+                describe_segments(skipped_segments)
+            ));
+        }
 
         let mut embed = CreateEmbed::new()
-            .description(format!("**Now Playing:**\n{}\n\n{}", title, progress_bar))
+            .description(description)
             .color(Color::DARK_GREEN);
 
         if let Some(url) = &metadata.thumbnail_url {
             embed = embed.thumbnail(url);
         }
 
-        respond_to_followup(command, &ctx.http, embed, true).await;
+        let mut components = create_music_buttons();
+        if !metadata.chapters.is_empty() {
+            components.push(create_chapter_menu(&metadata.chapters, chapter));
+        }
+
+        let message = CreateInteractionResponseFollowup::new()
+            .embed(embed)
+            .components(components);
+
+        if let Err(err) = command.create_followup(&ctx.http, message).await {
+            error!("Failed to send now playing response: {}", err);
+        }
     } else {
         warn!(
             "Attempted to get now playing but bot is not in voice channel (guild {})",
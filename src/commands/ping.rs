@@ -1,7 +1,7 @@
 use serenity::{all::CommandInteraction, client::Context};
 use tracing::info;
 
-use crate::utils::response::respond_to_command;
+use crate::utils::{metadata_cache::get_metadata_cache, response::respond_to_command};
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
     let guild_id = command
@@ -9,11 +9,23 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         .expect("No Guild ID found on interaction")
         .to_string();
 
-    info!("Ping! From guild id: {guild_id}");
+    let counters = get_metadata_cache(&ctx.data).await.counters();
 
-    respond_to_command(command, &ctx.http, format!("Pong!"), false).await;
+    info!(
+        "Ping! From guild id: {guild_id}. Metadata cache: {}",
+        counters.describe()
+    );
+
+    respond_to_command(
+        command,
+        &ctx.http,
+        format!("Pong! Metadata cache: {}", counters.describe()),
+        false,
+    )
+    .await;
 }
 
 pub fn register() -> serenity::builder::CreateCommand {
-    serenity::builder::CreateCommand::new("ping").description("Respond with Pong!")
+    serenity::builder::CreateCommand::new("ping")
+        .description("Respond with Pong! and the metadata cache's hit rate")
 }
@@ -0,0 +1,179 @@
+use std::io::Cursor;
+
+use serenity::{
+    all::{Attachment, CommandInteraction, CommandOptionType, CommandType, ResolvedTarget},
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed},
+    client::Context,
+    model::colour::Color,
+};
+use tracing::{error, info, warn};
+
+use crate::utils::{
+    audio_file::{file_extension, is_audio_file, max_attachment_bytes, probe_audio},
+    queue_position::QueuePosition,
+    response::respond_to_followup,
+    track_utils::{TrackMetadata, enqueue_resolved_track},
+    type_map::get_http_client,
+};
+
+/// `/play-file` with an uploaded attachment
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer play-file command: {}", err);
+        return;
+    }
+
+    let attachment = command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == "file")
+        .and_then(|option| option.value.as_attachment_id())
+        .and_then(|id| command.data.resolved.attachments.get(&id));
+
+    let Some(attachment) = attachment else {
+        respond_with_error(ctx, command, "Please attach an audio file to play!").await;
+        return;
+    };
+
+    play_attachment(ctx, command, attachment).await;
+}
+
+/// "Play in voice" on a message plays its first audio attachment
+pub async fn run_context_menu(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer play in voice command: {}", err);
+        return;
+    }
+
+    let Some(ResolvedTarget::Message(message)) = command.data.target() else {
+        respond_with_error(ctx, command, "Couldn't find that message!").await;
+        return;
+    };
+
+    let attachment = message
+        .attachments
+        .iter()
+        .find(|attachment| is_audio_file(&attachment.filename, attachment.content_type.as_deref()));
+
+    let Some(attachment) = attachment else {
+        respond_with_error(ctx, command, "That message has no audio file to play!").await;
+        return;
+    };
+
+    play_attachment(ctx, command, attachment).await;
+}
+
+async fn play_attachment(ctx: &Context, command: &CommandInteraction, attachment: &Attachment) {
+    let guild_id = command.guild_id.unwrap();
+
+    if !is_audio_file(&attachment.filename, attachment.content_type.as_deref()) {
+        respond_with_error(
+            ctx,
+            command,
+            &format!("**{}** isn't an audio file!", attachment.filename),
+        )
+        .await;
+        return;
+    }
+
+    let max_bytes = max_attachment_bytes();
+    if u64::from(attachment.size) > max_bytes {
+        respond_with_error(
+            ctx,
+            command,
+            &format!(
+                "**{}** is too large! Files can be up to **{}MB**",
+                attachment.filename,
+                max_bytes / 1024 / 1024
+            ),
+        )
+        .await;
+        return;
+    }
+
+    let http_client = get_http_client(ctx).await;
+
+    let bytes = match http_client
+        .get(&attachment.url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        Ok(response) => response.bytes().await,
+        Err(err) => Err(err),
+    };
+
+    let bytes = match bytes {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            warn!("Failed to download attachment {}: {}", attachment.url, err);
+            respond_with_error(
+                ctx,
+                command,
+                &format!("Couldn't download **{}**!", attachment.filename),
+            )
+            .await;
+            return;
+        }
+    };
+
+    // Probe the file before queueing it so anything that merely claims to
+    // be audio is turned away here instead of failing mid-playback
+    let extension = file_extension(&attachment.filename);
+    let probed = bytes.clone();
+    let tags = tokio::task::spawn_blocking(move || {
+        probe_audio(Box::new(Cursor::new(probed)), extension.as_deref())
+    })
+    .await
+    .ok()
+    .flatten();
+
+    let Some(tags) = tags else {
+        respond_with_error(
+            ctx,
+            command,
+            &format!("**{}** isn't a playable audio file!", attachment.filename),
+        )
+        .await;
+        return;
+    };
+
+    info!(
+        "Playing attachment '{}' in guild {}",
+        attachment.filename, guild_id
+    );
+
+    let metadata = TrackMetadata {
+        title: tags.display_title(&attachment.filename),
+        duration: tags.duration,
+        // Attachment links expire, so there is nothing to save or replay later
+        source_url: None,
+        ..Default::default()
+    };
+
+    // Play the bytes that were just probed rather than downloading the file
+    // a second time
+    enqueue_resolved_track(ctx, command, bytes.into(), metadata, QueuePosition::End).await;
+}
+
+async fn respond_with_error(ctx: &Context, command: &CommandInteraction, description: &str) {
+    let embed = CreateEmbed::new()
+        .description(description)
+        .color(Color::DARK_RED);
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("play-file")
+        .description("Play an uploaded audio file")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::Attachment, "file", "The audio file")
+                .required(true),
+        )
+}
+
+pub fn register_context_menu() -> CreateCommand {
+    CreateCommand::new("Play in voice").kind(CommandType::Message)
+}
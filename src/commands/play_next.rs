@@ -0,0 +1,73 @@
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::CreateEmbed,
+    client::Context,
+    model::colour::Color,
+};
+use tracing::error;
+
+use crate::{
+    commands::play_url::{is_valid_youtube_url, play_music_link},
+    utils::{
+        metadata_cache::{cached_search_source, cached_source, get_metadata_cache},
+        music_links::parse_music_link,
+        queue_position::QueuePosition,
+        response::respond_to_followup,
+        track_utils::enqueue_track,
+        type_map::get_http_client,
+    },
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    play_at(ctx, command, QueuePosition::Next).await;
+}
+
+/// Queue a YouTube URL or the first search result for a title at `position`
+pub async fn play_at(ctx: &Context, command: &CommandInteraction, position: QueuePosition) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer {} command: {}", command.data.name, err);
+        return;
+    }
+
+    let query = match command.data.options.first().map(|data| &data.value) {
+        Some(CommandDataOptionValue::String(value)) => value.clone(),
+        _ => {
+            let response_embed = CreateEmbed::default()
+                .description("Please provide a URL or title to play!")
+                .color(Color::DARK_RED);
+
+            respond_to_followup(command, &ctx.http, response_embed, false).await;
+
+            return;
+        }
+    };
+
+    if let Some(link) = parse_music_link(&query) {
+        play_music_link(ctx, command, link, position).await;
+        return;
+    }
+
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+
+    let source = if is_valid_youtube_url(&query) {
+        cached_source(http_client, &cache, query)
+    } else {
+        cached_search_source(http_client, &cache, query)
+    };
+
+    enqueue_track(ctx, command, source, position).await;
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("play-next")
+        .description("Queue a song to play straight after the current one")
+        .add_option(
+            serenity::builder::CreateCommandOption::new(
+                CommandOptionType::String,
+                "query",
+                "A Youtube video URL or title",
+            )
+            .required(true),
+        )
+}
@@ -0,0 +1,23 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType},
+    client::Context,
+};
+
+use crate::{commands::play_next::play_at, utils::queue_position::QueuePosition};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    play_at(ctx, command, QueuePosition::Now).await;
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("play-now")
+        .description("Interrupt the current song, which carries on once this one ends")
+        .add_option(
+            serenity::builder::CreateCommandOption::new(
+                CommandOptionType::String,
+                "query",
+                "A Youtube video URL or title",
+            )
+            .required(true),
+        )
+}
@@ -4,11 +4,14 @@ use serenity::{
     client::Context,
     model::colour::Color,
 };
-use songbird::input::YoutubeDl;
 use tracing::error;
 
 use crate::utils::{
-    response::respond_to_followup, track_utils::enqueue_track, type_map::get_http_client,
+    metadata_cache::{cached_search_source, get_metadata_cache},
+    queue_position::{position_option, register_position_option},
+    response::respond_to_followup,
+    track_utils::enqueue_track,
+    type_map::get_http_client,
 };
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
@@ -19,7 +22,11 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
 
     let mut response_embed = CreateEmbed::default();
 
-    let command_value = command.data.options.first();
+    let command_value = command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == "title");
 
     let resolved_value = match command_value {
         Some(data) => &data.value,
@@ -47,12 +54,23 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         }
     };
 
+    let Some(position) = position_option(command) else {
+        response_embed = response_embed
+            .description("Please provide **next**, **now**, **end** or a queue position!")
+            .color(Color::DARK_RED);
+
+        respond_to_followup(command, &ctx.http, response_embed, false).await;
+
+        return;
+    };
+
     let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
 
     // Get the audio source for the URL
-    let source = YoutubeDl::new_search(http_client, title);
+    let source = cached_search_source(http_client, &cache, title);
 
-    enqueue_track(ctx, command, source.into()).await;
+    enqueue_track(ctx, command, source, position).await;
 }
 
 pub fn register() -> serenity::builder::CreateCommand {
@@ -66,4 +84,5 @@ pub fn register() -> serenity::builder::CreateCommand {
             )
             .required(true),
         )
+        .add_option(register_position_option())
 }
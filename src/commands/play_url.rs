@@ -4,12 +4,24 @@ use serenity::{
     client::Context,
     model::colour::Color,
 };
-use tracing::error;
-
-use songbird::input::YoutubeDl;
+use tracing::{error, info, warn};
 
 use crate::utils::{
-    response::respond_to_followup, track_utils::enqueue_track, type_map::get_http_client,
+    enqueue_summary::EnqueueSummary,
+    guild_settings::get_guild_settings,
+    limits::playlist_size_allowed,
+    metadata_cache::{cached_search_source, cached_source, get_metadata_cache},
+    metadata_resolver::{MetadataResolver, ResolvedSource},
+    music_links::{
+        LinkError, MusicLink, MusicLinkKind, fetch_linked_tracks, metadata_endpoint,
+        parse_music_link,
+    },
+    queue_position::{QueuePosition, position_option, register_position_option},
+    response::respond_to_followup,
+    track_utils::{
+        enqueue_resolved_track, enqueue_resolved_track_list, enqueue_track, resolve_metadata,
+    },
+    type_map::get_http_client,
 };
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
@@ -20,7 +32,11 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
 
     let mut response_embed = CreateEmbed::default();
 
-    let command_value = command.data.options.first();
+    let command_value = command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == "url");
 
     let resolved_value = match command_value {
         Some(data) => &data.value,
@@ -48,6 +64,21 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         }
     };
 
+    let Some(position) = position_option(command) else {
+        response_embed = response_embed
+            .description("Please provide **next**, **now**, **end** or a queue position!")
+            .color(Color::DARK_RED);
+
+        respond_to_followup(command, &ctx.http, response_embed, false).await;
+
+        return;
+    };
+
+    if let Some(link) = parse_music_link(&url) {
+        play_music_link(ctx, command, link, position).await;
+        return;
+    }
+
     // Validate its a valid Youtube URL
     if !is_valid_youtube_url(&url) {
         response_embed = response_embed
@@ -60,26 +91,179 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
     }
 
     let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
 
     // Get the audio source for the URL
-    let source = YoutubeDl::new(http_client, url);
+    let source = cached_source(http_client, &cache, url);
+
+    enqueue_track(ctx, command, source, position).await;
+}
+
+/// Most tracks listed when reporting the ones that couldn't be found
+const MAX_UNMATCHED_LISTED: usize = 10;
+
+/// Queue a Spotify or Apple Music link by searching YouTube for each of its
+/// tracks, the same way `/play-title` does
+pub async fn play_music_link(
+    ctx: &Context,
+    command: &CommandInteraction,
+    link: MusicLink,
+    position: QueuePosition,
+) {
+    let guild_id = command.guild_id.unwrap();
+
+    // Albums and playlists are always added to the end of the queue
+    if link.kind != MusicLinkKind::Track && position != QueuePosition::End {
+        let response_embed = CreateEmbed::default()
+            .description(format!(
+                "**position** only works for single tracks! Leave it out to queue the whole {} at the end",
+                link.kind.name()
+            ))
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, response_embed, false).await;
+        return;
+    }
+
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+
+    let linked = match metadata_endpoint() {
+        Some(endpoint) => fetch_linked_tracks(&http_client, &endpoint, &link).await,
+        None => Err(LinkError::NotConfigured),
+    };
+
+    let linked = match linked {
+        Ok(linked) => linked,
+        Err(err) => {
+            warn!(
+                "Failed to translate {} link {}: {}",
+                link.service.name(),
+                link.url,
+                err
+            );
+            let response_embed = CreateEmbed::default()
+                .description(format!("**Couldn't play that link!** {}", err))
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, response_embed, false).await;
+            return;
+        }
+    };
+
+    info!(
+        "Translating {} {} with {} tracks in guild {}",
+        link.service.name(),
+        link.kind.name(),
+        linked.tracks.len(),
+        guild_id
+    );
+
+    // A single track is queued like any other, wherever it was asked for
+    if link.kind == MusicLinkKind::Track {
+        let track = &linked.tracks[0];
+        let mut source = cached_search_source(http_client.clone(), &cache, track.search_query());
+        let metadata = resolve_metadata(&http_client, &mut source, guild_id).await;
+
+        if metadata.source_url.is_none() {
+            let response_embed = CreateEmbed::default()
+                .description(format!(
+                    "Couldn't find **{}** on YouTube!",
+                    track.search_query()
+                ))
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, response_embed, false).await;
+            return;
+        }
+
+        enqueue_resolved_track(ctx, command, source, metadata, position).await;
+        return;
+    }
+
+    let limits = get_guild_settings(&ctx.data, guild_id).await.limits;
+    let total = linked.tracks.len();
+    let allowed = playlist_size_allowed(&limits, total);
+
+    let mut summary = EnqueueSummary {
+        left_out: total - allowed,
+        ..Default::default()
+    };
+    let mut unmatched = Vec::new();
+
+    let sources = linked.tracks[..allowed]
+        .iter()
+        .map(|track| cached_search_source(http_client.clone(), &cache, track.search_query()))
+        .collect();
+    let mut resolver = MetadataResolver::new(http_client.clone(), guild_id, sources);
+
+    while let Some(ResolvedSource {
+        index,
+        source,
+        metadata,
+    }) = resolver.next().await
+    {
+        let track = &linked.tracks[index];
+
+        let Some(metadata) = metadata
+            .ok()
+            .filter(|metadata| metadata.source_url.is_some())
+        else {
+            unmatched.push(track.search_query());
+            continue;
+        };
+
+        let result = enqueue_resolved_track_list(ctx, command, source, metadata).await;
+        if !summary.record(result) {
+            break;
+        }
+    }
+
+    let name = linked
+        .name
+        .map(|name| format!(" **{}**", name))
+        .unwrap_or_default();
+
+    let mut description = summary.describe(&format!(
+        "**Queued** {} of {} tracks from the {} {}{}!",
+        summary.queued,
+        total,
+        link.service.name(),
+        link.kind.name(),
+        name
+    ));
+
+    if !unmatched.is_empty() {
+        description.push_str("\n**Couldn't find on YouTube:**");
+        for query in unmatched.iter().take(MAX_UNMATCHED_LISTED) {
+            description.push_str(&format!("\n• {}", query));
+        }
+        if unmatched.len() > MAX_UNMATCHED_LISTED {
+            description.push_str(&format!(
+                "\n...and {} more",
+                unmatched.len() - MAX_UNMATCHED_LISTED
+            ));
+        }
+    }
+
+    let response_embed = CreateEmbed::default()
+        .description(description)
+        .color(summary.color());
 
-    enqueue_track(ctx, command, source.into()).await;
+    respond_to_followup(command, &ctx.http, response_embed, false).await;
 }
 
 pub fn register() -> serenity::builder::CreateCommand {
     serenity::builder::CreateCommand::new("play-url")
-        .description("Play the audio from a Youtube video URL")
+        .description("Play the audio from a Youtube video URL or a Spotify or Apple Music link")
         .add_option(
             serenity::builder::CreateCommandOption::new(
                 CommandOptionType::String,
                 "url",
-                "A Youtube video URL",
+                "A Youtube video URL, or a Spotify or Apple Music link",
             )
             .required(true),
         )
+        .add_option(register_position_option())
 }
-fn is_valid_youtube_url(url: &String) -> bool {
+pub fn is_valid_youtube_url(url: &String) -> bool {
     (url.contains("youtube.com") && (url.contains("/watch"))) || url.contains("youtu.be")
 }
 
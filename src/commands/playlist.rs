@@ -1,127 +1,420 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
 use serenity::{
-    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
-    builder::CreateEmbed,
+    all::{CommandDataOption, CommandInteraction, CommandOptionType, ComponentInteraction},
+    builder::{
+        CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+        CreateInteractionResponseFollowup,
+    },
     client::Context,
-    model::colour::Color,
+    model::{channel::Message, colour::Color},
 };
-use songbird::input::YoutubeDl;
-use tracing::error;
-use url::Url;
-
-use crate::utils::{
-    response::respond_to_followup,
-    track_utils::{enqueue_track, enqueue_track_list},
-    type_map::get_http_client,
+use tracing::{error, info, warn};
+
+use crate::{
+    components::playlist_buttons::{create_playlist_cancel_button, parse_playlist_cancel},
+    utils::{
+        dj::is_dj,
+        enqueue_summary::EnqueueSummary,
+        guild_settings::get_guild_settings,
+        limits::playlist_size_allowed,
+        metadata_cache::{cached_source, get_metadata_cache},
+        metadata_resolver::{MetadataResolver, ResolvedSource},
+        options::string_option,
+        response::{respond_to_error_button, respond_to_followup},
+        track_utils::enqueue_resolved_track_list,
+        type_map::get_http_client,
+        youtube::watch_url,
+        youtube_playlist::{
+            PlaylistImportsKey, PlaylistUrl, describe_progress, describe_unavailable,
+            playlist_fetch_limit, select_videos,
+        },
+    },
 };
 
-use rustypipe::{client::RustyPipe, model::VideoCodec};
+use rustypipe::client::RustyPipe;
 
-pub async fn run(ctx: &Context, command: &CommandInteraction) {
-    let rp = RustyPipe::new();
+/// How often the progress embed is refreshed while videos are queued
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
 
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
     if let Err(err) = command.defer(&ctx.http).await {
-        error!("Failed to defer play-url command: {}", err);
+        error!("Failed to defer playlist command: {}", err);
         return;
     }
 
-    let mut response_embed = CreateEmbed::default();
+    let options = &command.data.options;
 
-    let command_value = command.data.options.first();
+    let Some(url) = string_option(options, "url") else {
+        respond_with_error(ctx, command, "Please provide a URL to play!").await;
+        return;
+    };
 
-    let resolved_value = match command_value {
-        Some(data) => &data.value,
-        _ => {
-            response_embed = response_embed
-                .description("Please provide a URL to play!")
-                .color(Color::DARK_RED);
+    let Some(playlist_url) = PlaylistUrl::parse(url) else {
+        respond_with_error(
+            ctx,
+            command,
+            "Please provide a YouTube link with a playlist in it, such as **/playlist?list=...**",
+        )
+        .await;
+        return;
+    };
 
-            respond_to_followup(command, &ctx.http, response_embed, false).await;
+    let guild_id = command.guild_id.unwrap();
 
-            return;
-        }
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    if manager.get(guild_id).is_none() {
+        respond_with_error(
+            ctx,
+            command,
+            "Error queueing playlist! Ensure Poor Jimmy is in a voice channel with **/join**",
+        )
+        .await;
+        return;
+    }
+
+    let limits = get_guild_settings(&ctx.data, guild_id).await.limits;
+
+    // The server's limit caps the import, even when a bigger one is asked for
+    let limit = match integer_option(options, "limit") {
+        Some(limit) => Some(playlist_size_allowed(&limits, limit)),
+        None => limits.max_playlist_size,
     };
 
-    let url = match resolved_value {
-        CommandDataOptionValue::String(value) => value.clone(),
-        _ => {
-            response_embed = response_embed
-                .description("Please provide a valid URL!")
-                .color(Color::DARK_RED);
+    let start = integer_option(options, "start")
+        .or(playlist_url.index)
+        .unwrap_or(1);
+    let shuffle = options
+        .iter()
+        .find(|option| option.name == "shuffle")
+        .and_then(|option| option.value.as_bool())
+        .unwrap_or(false);
 
-            respond_to_followup(command, &ctx.http, response_embed, false).await;
+    let rp = RustyPipe::new();
 
+    let mut playlist = match rp.query().playlist(&playlist_url.id).await {
+        Ok(playlist) => playlist,
+        Err(err) => {
+            warn!("Failed to open playlist {}: {}", playlist_url.id, err);
+            respond_with_error(
+                ctx,
+                command,
+                "**Couldn't open that playlist!** It may be private or deleted",
+            )
+            .await;
             return;
         }
     };
 
-    let http_client = get_http_client(&ctx).await;
-
-    let id_playlist = get_id_playlist(url.clone());
+    // A shuffle picks from the whole playlist, otherwise only the videos up
+    // to the limit are needed
+    let fetch_limit = playlist_fetch_limit();
+    let wanted = match limit {
+        Some(limit) if !shuffle => (start - 1).saturating_add(limit).min(fetch_limit),
+        _ => fetch_limit,
+    };
 
-    let mut urls: Vec<String> = Vec::new();
+    let fully_read = match playlist.videos.extend_limit(rp.query(), wanted).await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(
+                "Failed to page through playlist {}: {}",
+                playlist_url.id, err
+            );
+            false
+        }
+    };
 
-    if !is_valid_youtube_url(url.clone()) || id_playlist.is_err() {
-        response_embed = response_embed
-            .description("Please provide a valid **/watch** Youtube URL")
-            .color(Color::DARK_RED);
+    // YouTube counts private and deleted videos but leaves them out of the list
+    let hidden = if playlist.videos.is_exhausted() {
+        (playlist.video_count as usize).saturating_sub(playlist.videos.items.len())
+    } else {
+        0
+    };
 
-        respond_to_followup(command, &ctx.http, response_embed, false).await;
+    let mut videos = std::mem::take(&mut playlist.videos.items);
+    videos.truncate(wanted);
+    let videos = select_videos(videos, start, limit, shuffle);
 
+    if videos.is_empty() {
+        respond_with_error(
+            ctx,
+            command,
+            &format!(
+                "**{}** has no videos from position {}!",
+                playlist.name, start
+            ),
+        )
+        .await;
         return;
     }
 
-    let mut playlist = rp
-        .query()
-        .playlist(id_playlist.unwrap())
-        .await
-        .expect("Nao foi possivel acessar playlist");
+    let import_id = command.id.get();
+    let cancelled = {
+        let mut data = ctx.data.write().await;
+        data.get_mut::<PlaylistImportsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .start(import_id, command.user.id)
+    };
+
+    let total = videos.len();
+    let progress = send_progress(ctx, command, &playlist.name, import_id, total).await;
 
-    playlist.videos.extend_limit(rp.query(), 50).await.unwrap();
+    info!(
+        "Importing {} videos from playlist '{}' in guild {}",
+        total, playlist.name, guild_id
+    );
 
-    playlist
-        .videos
-        .items
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+    let sources = videos
         .iter()
-        .for_each(|v| urls.push(create_link_youtbe(v.id.to_string())));
+        .map(|video| cached_source(http_client.clone(), &cache, watch_url(&video.id)))
+        .collect();
+    let mut resolver = MetadataResolver::new(http_client, guild_id, sources);
+
+    let mut summary = EnqueueSummary::default();
+    let mut unavailable = Vec::new();
+    let mut done = 0;
+    let mut last_update = Instant::now();
+
+    while !cancelled.load(Ordering::Relaxed) {
+        let Some(ResolvedSource {
+            index,
+            source,
+            metadata,
+        }) = resolver.next().await
+        else {
+            break;
+        };
+
+        match metadata {
+            Ok(metadata) => {
+                let result = enqueue_resolved_track_list(ctx, command, source, metadata).await;
+                if !summary.record(result) {
+                    break;
+                }
+            }
+            Err(err) => {
+                let name = videos[index].name.clone();
+                info!("Skipping unavailable video '{}': {}", name, err);
+                unavailable.push(name);
+            }
+        }
 
-    for url in urls.iter() {
-        let source = YoutubeDl::new(http_client.clone(), url.clone());
-        enqueue_track_list(&ctx, command, source.into()).await;
+        done += 1;
+
+        if let Some(message) = &progress
+            && last_update.elapsed() >= PROGRESS_INTERVAL
+        {
+            last_update = Instant::now();
+
+            let embed = progress_embed(&playlist.name, done, total);
+            let update = CreateInteractionResponseFollowup::new()
+                .embed(embed)
+                .components(vec![create_playlist_cancel_button(import_id)]);
+
+            if let Err(err) = command.edit_followup(&ctx.http, message.id, update).await {
+                warn!("Failed to update playlist progress: {}", err);
+            }
+        }
+    }
+
+    {
+        let mut data = ctx.data.write().await;
+        data.get_mut::<PlaylistImportsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .finish(import_id);
+    }
+
+    let was_cancelled = cancelled.load(Ordering::Relaxed) && done < total;
+
+    let headline = if was_cancelled {
+        format!(
+            "**Cancelled!** Queued {} of {} tracks from **{}**",
+            summary.queued, total, playlist.name
+        )
+    } else {
+        format!(
+            "**Queued** {} tracks from **{}**!",
+            summary.queued, playlist.name
+        )
+    };
+
+    let mut description = summary.describe(&headline);
+
+    if let Some(unavailable) = describe_unavailable(&unavailable, hidden) {
+        description.push_str(&format!("\n\n{}", unavailable));
+    }
+
+    // Say how to carry on when the cap left videos out
+    let listed = playlist.video_count as usize;
+    let next_start = start + total;
+    if !shuffle && !was_cancelled && summary.stopped_by.is_none() && next_start <= listed {
+        description.push_str(&format!(
+            "\n\nThe playlist has more videos, queue them with `start:{}`",
+            next_start
+        ));
+    }
+
+    if !fully_read {
+        description
+            .push_str("\n\nYouTube stopped listing the playlist early, some videos may be missing");
+    }
+
+    let embed = CreateEmbed::new()
+        .description(description)
+        .color(summary.color());
+
+    match progress {
+        Some(message) => {
+            let update = CreateInteractionResponseFollowup::new()
+                .embed(embed)
+                .components(vec![]);
+
+            if let Err(err) = command.edit_followup(&ctx.http, message.id, update).await {
+                error!("Failed to send playlist summary: {}", err);
+            }
+        }
+        None => respond_to_followup(command, &ctx.http, embed, false).await,
     }
 }
 
-pub fn register() -> serenity::builder::CreateCommand {
-    serenity::builder::CreateCommand::new("playlist")
-        .description("Play the audio from a Youtube Playlist URL")
-        .add_option(
-            serenity::builder::CreateCommandOption::new(
-                CommandOptionType::String,
-                "url",
-                "A Youtube Playlist URL",
-            )
-            .required(true),
+/// Stop an import in progress, for its requester or a DJ
+pub async fn handle_cancel_button(ctx: &Context, interaction: &ComponentInteraction) {
+    let Some(import_id) = parse_playlist_cancel(&interaction.data.custom_id) else {
+        error!(
+            "Invalid playlist cancel button: {}",
+            interaction.data.custom_id
+        );
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    let requested_by = {
+        let data = ctx.data.read().await;
+        data.get::<PlaylistImportsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .requested_by(import_id)
+    };
+
+    let Some(requested_by) = requested_by else {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("That playlist has already finished queueing!"),
+        )
+        .await;
+        return;
+    };
+
+    let guild_id = interaction.guild_id.unwrap();
+
+    if requested_by != interaction.user.id && !is_dj(ctx, guild_id, interaction.member.as_ref()) {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("Only the member who queued this playlist or a DJ can cancel it!"),
         )
+        .await;
+        return;
+    }
+
+    {
+        let data = ctx.data.read().await;
+        data.get::<PlaylistImportsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .cancel(import_id);
+    }
+
+    info!(
+        "Playlist import {} cancelled by user {} in guild {}",
+        import_id, interaction.user.id, guild_id
+    );
+
+    // The import posts its summary in place of the progress once it stops
+    if let Err(err) = interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await
+    {
+        error!("Failed to acknowledge playlist cancel: {}", err);
+    }
 }
 
-fn is_valid_youtube_url(url: String) -> bool {
-    (url.contains("youtube.com") && (url.contains("/watch"))) || url.contains("youtu.be")
+async fn respond_with_error(ctx: &Context, command: &CommandInteraction, description: &str) {
+    let embed = CreateEmbed::new()
+        .description(description)
+        .color(Color::DARK_RED);
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
 }
 
-fn get_id_playlist(url: String) -> Result<String, String> {
-    let url = Url::parse(url.as_str()).expect("Erro ao parsear link");
+fn progress_embed(name: &str, done: usize, total: usize) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(format!("📃 {}", name))
+        .description(describe_progress(done, total))
+        .color(Color::BLUE)
+}
 
-    let playlist_id = url
-        .query_pairs()
-        .find(|(k, _)| k == "list")
-        .map(|(_, v)| v.into_owned()); // transforma Cow<str> em String
+/// Post the progress embed with its cancel button. Without it the import
+/// still runs and reports at the end.
+async fn send_progress(
+    ctx: &Context,
+    command: &CommandInteraction,
+    name: &str,
+    import_id: u64,
+    total: usize,
+) -> Option<Message> {
+    let message = CreateInteractionResponseFollowup::new()
+        .embed(progress_embed(name, 0, total))
+        .components(vec![create_playlist_cancel_button(import_id)]);
 
-    if let Some(id) = playlist_id {
-        return Result::Ok(id);
+    match command.create_followup(&ctx.http, message).await {
+        Ok(message) => Some(message),
+        Err(err) => {
+            error!("Failed to send playlist progress: {}", err);
+            None
+        }
     }
+}
 
-    Result::Err("Não foi possivel resolver link".to_string())
+fn integer_option(options: &[CommandDataOption], name: &str) -> Option<usize> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_i64())
+        .and_then(|value| usize::try_from(value).ok())
+        .filter(|value| *value > 0)
 }
 
-fn create_link_youtbe(id: String) -> String {
-    return format!("https://www.youtube.com/watch?v={}", id);
+pub fn register() -> CreateCommand {
+    CreateCommand::new("playlist")
+        .description("Play the audio from a Youtube Playlist URL")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "url", "A Youtube Playlist URL")
+                .required(true),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "shuffle",
+            "Queue the videos in a random order",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "start",
+                "Position of the first video to queue, defaults to the link's index",
+            )
+            .min_int_value(1),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "limit", "Most videos to queue")
+                .min_int_value(1),
+        )
 }
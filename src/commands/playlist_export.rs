@@ -0,0 +1,178 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, GuildId},
+    builder::{
+        CreateAttachment, CreateCommand, CreateCommandOption, CreateEmbed,
+        CreateInteractionResponseFollowup,
+    },
+    client::Context,
+    model::colour::Color,
+};
+use tracing::{error, info};
+
+use crate::{
+    commands::myplaylist::playlist_owner,
+    utils::{
+        options::string_option,
+        playlist_file::{PlaylistFormat, export_playlist},
+        response::respond_to_followup,
+        saved_playlists::{MAX_PLAYLIST_TRACKS, PlaylistStoreKey, SavedPlaylist, SavedTrack},
+        track_utils::track_metadata,
+    },
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer playlist-export command: {}", err);
+        return;
+    }
+
+    let options = &command.data.options;
+
+    let Some(format) = string_option(options, "format").and_then(PlaylistFormat::parse) else {
+        let embed = CreateEmbed::new()
+            .description("Please choose **json**, **m3u** or **xspf**!")
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    };
+
+    let playlist = match string_option(options, "playlist") {
+        Some(name) => {
+            let data = ctx.data.read().await;
+            data.get::<PlaylistStoreKey>()
+                .expect("Guaranteed to exist in the typemap.")
+                .playlists(playlist_owner(command))
+                .and_then(|playlists| playlists.find(name))
+                .cloned()
+                .ok_or_else(|| format!("There is no playlist called **{}**!", name))
+        }
+        None => queue_playlist(ctx, command.guild_id.unwrap()).await,
+    };
+
+    let playlist = match playlist {
+        Ok(playlist) => playlist,
+        Err(description) => {
+            let embed = CreateEmbed::new()
+                .description(description)
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, embed, false).await;
+            return;
+        }
+    };
+
+    info!(
+        "Exporting playlist '{}' with {} tracks as {:?} in guild {}",
+        playlist.name,
+        playlist.tracks.len(),
+        format,
+        command.guild_id.unwrap()
+    );
+
+    let contents = export_playlist(&playlist, format);
+    let filename = format!("{}.{}", file_stem(&playlist.name), format.extension());
+
+    let embed = CreateEmbed::new()
+        .description(format!(
+            "**Exported** {} tracks from **{}**!",
+            playlist.tracks.len(),
+            playlist.name
+        ))
+        .color(Color::DARK_GREEN);
+
+    let message = CreateInteractionResponseFollowup::new()
+        .embed(embed)
+        .add_file(CreateAttachment::bytes(contents.into_bytes(), filename));
+
+    if let Err(err) = command.create_followup(&ctx.http, message).await {
+        error!("Failed to send exported playlist: {}", err);
+    }
+}
+
+/// The tracks of the queue that can be saved, as a playlist
+async fn queue_playlist(ctx: &Context, guild_id: GuildId) -> Result<SavedPlaylist, String> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    let tracks: Vec<SavedTrack> = match manager.get(guild_id) {
+        Some(call) => call
+            .lock()
+            .await
+            .queue()
+            .current_queue()
+            .iter()
+            .filter_map(|track| SavedTrack::from_metadata(&track_metadata(track)))
+            .take(MAX_PLAYLIST_TRACKS)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    if tracks.is_empty() {
+        return Err(String::from(
+            "The queue has nothing to export! Pick a saved playlist instead",
+        ));
+    }
+
+    Ok(SavedPlaylist {
+        name: String::from("Queue"),
+        tracks,
+    })
+}
+
+/// Playlist name made safe for a file name
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let stem = stem.trim_matches('_');
+    if stem.is_empty() {
+        String::from("playlist")
+    } else {
+        stem.to_string()
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("playlist-export")
+        .description("Download the queue or a saved playlist as a playlist file")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "format", "File format")
+                .required(true)
+                .add_string_choice("JSON", "json")
+                .add_string_choice("M3U", "m3u")
+                .add_string_choice("XSPF", "xspf"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "playlist",
+                "Saved playlist to export, leave out to export the queue",
+            )
+            .set_autocomplete(true),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "server",
+            "Look the playlist up in the server's playlists instead of yours",
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("Road Trip / 2024"), "Road_Trip___2024");
+        assert_eq!(file_stem("Ünïcode-mix"), "Ünïcode-mix");
+        assert_eq!(file_stem("../"), "playlist");
+    }
+}
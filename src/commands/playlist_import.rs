@@ -0,0 +1,213 @@
+use serenity::{
+    all::{Attachment, CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed},
+    client::Context,
+    model::colour::Color,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    commands::myplaylist::playlist_owner,
+    utils::{
+        dj::{DJ_ONLY, is_dj},
+        guild_settings::get_guild_settings,
+        limits::playlist_size_allowed,
+        metadata_cache::{cached_source, get_metadata_cache},
+        options::string_option,
+        playlist_file::{MAX_PLAYLIST_FILE_BYTES, PlaylistFormat, import_playlist},
+        response::respond_to_followup,
+        saved_playlists::{
+            MAX_NAME_LENGTH, PlaylistOwner, PlaylistStoreKey, SavedPlaylist, save_playlists,
+        },
+        track_utils::enqueue_track_list,
+        type_map::get_http_client,
+    },
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer playlist-import command: {}", err);
+        return;
+    }
+
+    let result = import(ctx, command).await;
+
+    let embed = match result {
+        Ok(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_GREEN),
+        Err(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_RED),
+    };
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+async fn import(ctx: &Context, command: &CommandInteraction) -> Result<String, String> {
+    let attachment = command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == "file")
+        .and_then(|option| option.value.as_attachment_id())
+        .and_then(|id| command.data.resolved.attachments.get(&id))
+        .ok_or_else(|| String::from("Please attach a playlist file!"))?;
+
+    let playlist = download_playlist(ctx, attachment).await?;
+
+    match string_option(&command.data.options, "save_as") {
+        Some(name) => save(ctx, command, playlist, name).await,
+        None => enqueue(ctx, command, playlist).await,
+    }
+}
+
+async fn download_playlist(
+    ctx: &Context,
+    attachment: &Attachment,
+) -> Result<SavedPlaylist, String> {
+    if attachment.size > MAX_PLAYLIST_FILE_BYTES {
+        return Err(format!(
+            "**{}** is too big for a playlist file!",
+            attachment.filename
+        ));
+    }
+
+    let http_client = get_http_client(ctx).await;
+
+    let response = http_client
+        .get(&attachment.url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    let contents = match response {
+        Ok(response) => response.text().await,
+        Err(err) => Err(err),
+    };
+
+    let contents = contents.map_err(|err| {
+        warn!("Failed to download playlist {}: {}", attachment.url, err);
+        format!("Couldn't download **{}**!", attachment.filename)
+    })?;
+
+    let format = PlaylistFormat::from_filename(&attachment.filename)
+        .unwrap_or_else(|| PlaylistFormat::sniff(&contents));
+
+    // Files without a name of their own are named after the file
+    let fallback_name = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .unwrap_or(&attachment.filename);
+
+    import_playlist(&contents, format, fallback_name)
+        .map_err(|err| format!("**Couldn't import {}!** {}", attachment.filename, err))
+}
+
+/// Keep the imported tracks as a new saved playlist
+async fn save(
+    ctx: &Context,
+    command: &CommandInteraction,
+    playlist: SavedPlaylist,
+    name: &str,
+) -> Result<String, String> {
+    let owner = playlist_owner(command);
+
+    if matches!(owner, PlaylistOwner::Guild(_))
+        && !is_dj(ctx, command.guild_id.unwrap(), command.member.as_deref())
+    {
+        return Err(String::from(DJ_ONLY));
+    }
+
+    let mut data = ctx.data.write().await;
+    let store = data
+        .get_mut::<PlaylistStoreKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let playlists = store.playlists_mut(owner);
+    playlists
+        .create(name)
+        .map_err(|err| err.description(name))?;
+
+    let total = playlist.tracks.len();
+    let added = playlists
+        .add(name, playlist.tracks)
+        .map_err(|err| err.description(name))?;
+    save_playlists(store);
+
+    info!(
+        "Imported {} tracks into playlist '{}' of {:?}",
+        added, name, owner
+    );
+
+    let mut description = format!("**Saved** {} tracks as **{}**!", added, name);
+    if added < total {
+        description.push_str(&format!(
+            "\nLeft out {} tracks that didn't fit",
+            total - added
+        ));
+    }
+
+    Ok(description)
+}
+
+/// Queue the imported tracks straight away
+async fn enqueue(
+    ctx: &Context,
+    command: &CommandInteraction,
+    playlist: SavedPlaylist,
+) -> Result<String, String> {
+    let limits = get_guild_settings(&ctx.data, command.guild_id.unwrap())
+        .await
+        .limits;
+    let allowed = playlist_size_allowed(&limits, playlist.tracks.len());
+
+    info!(
+        "Queueing {} imported tracks from '{}' in guild {}",
+        allowed,
+        playlist.name,
+        command.guild_id.unwrap()
+    );
+
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
+    let sources = playlist.tracks[..allowed]
+        .iter()
+        .map(|track| cached_source(http_client.clone(), &cache, track.url.clone()))
+        .collect();
+    let mut summary = enqueue_track_list(ctx, command, sources).await;
+    summary.left_out = playlist.tracks.len() - allowed;
+
+    let description = summary.describe(&format!(
+        "**Queued** {} tracks from **{}**!",
+        summary.queued, playlist.name
+    ));
+
+    match summary.stopped_by {
+        Some(rejection) if summary.queued == 0 => Err(rejection.description()),
+        _ => Ok(description),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("playlist-import")
+        .description("Queue or save a JSON, M3U or XSPF playlist file")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::Attachment, "file", "Playlist file")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "save_as",
+                "Save it as a playlist with this name instead of queueing it",
+            )
+            .max_length(MAX_NAME_LENGTH),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "server",
+            "Save it to the server's playlists instead of yours (DJs only)",
+        ))
+}
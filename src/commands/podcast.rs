@@ -0,0 +1,440 @@
+use serenity::{
+    all::{
+        CommandInteraction, CommandOptionType, ComponentInteraction, CreateAutocompleteResponse,
+        CreateInteractionResponse, EditInteractionResponse,
+    },
+    builder::{
+        CreateActionRow, CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+        CreateInteractionResponseFollowup,
+    },
+    client::Context,
+    model::colour::Color,
+};
+use songbird::input::HttpRequest;
+use tracing::{error, info, warn};
+
+use crate::{
+    components::podcast_buttons::{PodcastButton, create_episode_buttons},
+    utils::{
+        dj::is_dj,
+        format::format_duration,
+        podcast::{
+            Episode, Feed, OpenedFeedsKey, PodcastSubscription, PodcastSubscriptionsKey,
+            fetch_feed, save_subscriptions, url_key,
+        },
+        response::{respond_to_error_button, respond_to_followup},
+        track_utils::enqueue_resolved_track_component,
+        type_map::get_http_client,
+    },
+};
+
+/// Discord caps autocomplete choice names and values at 100 characters
+const MAX_CHOICE_LENGTH: usize = 100;
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer podcast command: {}", err);
+        return;
+    }
+
+    let guild_id = command.guild_id.unwrap();
+
+    let Some(feed_option) = command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == "feed")
+        .and_then(|option| option.value.as_str())
+        .map(str::trim)
+    else {
+        let embed = CreateEmbed::new()
+            .description("Please provide a podcast feed URL!")
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    };
+
+    // Subscribed feeds can also be picked by their title
+    let url = {
+        let data = ctx.data.read().await;
+        data.get::<PodcastSubscriptionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .list(guild_id)
+            .iter()
+            .find(|subscription| subscription.title.eq_ignore_ascii_case(feed_option))
+            .map(|subscription| subscription.url.clone())
+            .unwrap_or_else(|| feed_option.to_string())
+    };
+
+    let http_client = get_http_client(ctx).await;
+
+    let feed = match fetch_feed(&http_client, &url).await {
+        Ok(feed) => feed,
+        Err(err) => {
+            warn!("Failed to load podcast feed {}: {}", url, err);
+            let embed = CreateEmbed::new()
+                .description(format!("**Couldn't open that podcast!** {}", err))
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, embed, false).await;
+            return;
+        }
+    };
+
+    let feed_key = url_key(&url);
+
+    // Remember the feed so its buttons work, DJs subscribe the server to it
+    // with the Subscribe button
+    let subscribed = {
+        let mut data = ctx.data.write().await;
+        data.get_mut::<OpenedFeedsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .insert(
+                feed_key,
+                PodcastSubscription {
+                    title: feed.title.clone(),
+                    url: url.clone(),
+                },
+            );
+
+        data.get::<PodcastSubscriptionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .get(guild_id, feed_key)
+            .is_some()
+    };
+
+    info!(
+        "Showing podcast '{}' with {} episodes in guild {}",
+        feed.title,
+        feed.episodes.len(),
+        guild_id
+    );
+
+    let message = CreateInteractionResponseFollowup::new()
+        .embed(episode_page_embed(&feed, 0))
+        .components(episode_page_buttons(&feed, feed_key, 0, subscribed));
+
+    if let Err(err) = command.create_followup(&ctx.http, message).await {
+        error!("Failed to send podcast episodes: {}", err);
+    }
+}
+
+fn episode_page_embed(feed: &Feed, page: usize) -> CreateEmbed {
+    let (first_episode, episodes) = feed.page(page);
+
+    let lines = episodes
+        .iter()
+        .enumerate()
+        .map(|(offset, episode)| {
+            let mut details = Vec::new();
+            if let Some(published) = &episode.published {
+                details.push(published.clone());
+            }
+            if let Some(duration) = episode.duration {
+                details.push(format_duration(duration));
+            }
+
+            let mut line = format!("**{}.** {}", first_episode + offset + 1, episode.title);
+            if !details.is_empty() {
+                line.push_str(&format!("\n    {}", details.join(" • ")));
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("🎙️ {}", feed.title))
+        .description(lines)
+        .footer(CreateEmbedFooter::new(format!(
+            "Page {} of {}",
+            page + 1,
+            feed.page_count()
+        )))
+        .color(Color::BLUE);
+
+    if let Some(image) = &feed.image {
+        embed = embed.thumbnail(image);
+    }
+
+    embed
+}
+
+fn episode_page_buttons(
+    feed: &Feed,
+    feed_key: u64,
+    page: usize,
+    subscribed: bool,
+) -> Vec<CreateActionRow> {
+    let (first_episode, episodes) = feed.page(page);
+    let episode_keys: Vec<u64> = episodes.iter().map(Episode::key).collect();
+
+    create_episode_buttons(
+        feed_key,
+        page,
+        feed.page_count(),
+        first_episode,
+        &episode_keys,
+        subscribed,
+    )
+}
+
+pub async fn handle_component(ctx: &Context, interaction: &ComponentInteraction) {
+    let (Some(button), Some(guild_id)) = (
+        PodcastButton::parse(&interaction.data.custom_id),
+        interaction.guild_id,
+    ) else {
+        error!("Invalid custom_id format: {}", interaction.data.custom_id);
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    let feed_key = match button {
+        PodcastButton::Page { feed, .. }
+        | PodcastButton::Play { feed, .. }
+        | PodcastButton::Subscribe { feed }
+        | PodcastButton::Unsubscribe { feed } => feed,
+    };
+
+    // Subscribed feeds are kept across restarts, other feeds only while
+    // the bot runs
+    let (subscription, subscribed) = {
+        let data = ctx.data.read().await;
+        let subscribed = data
+            .get::<PodcastSubscriptionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .get(guild_id, feed_key)
+            .cloned();
+
+        match subscribed {
+            Some(subscription) => (Some(subscription), true),
+            None => (
+                data.get::<OpenedFeedsKey>()
+                    .expect("Guaranteed to exist in the typemap.")
+                    .get(&feed_key)
+                    .cloned(),
+                false,
+            ),
+        }
+    };
+
+    let Some(subscription) = subscription else {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            "That podcast isn't open anymore! Open it again with **/podcast**".to_string(),
+        )
+        .await;
+        return;
+    };
+
+    // Subscriptions are shared by the whole server
+    if matches!(
+        button,
+        PodcastButton::Subscribe { .. } | PodcastButton::Unsubscribe { .. }
+    ) && !is_dj(ctx, guild_id, interaction.member.as_ref())
+    {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("Only DJs can change the server's podcast subscriptions!"),
+        )
+        .await;
+        return;
+    }
+
+    if let Err(err) = interaction.defer(&ctx.http).await {
+        error!("Failed to defer podcast component interaction: {}", err);
+        return;
+    }
+
+    if let PodcastButton::Unsubscribe { feed } = button {
+        let subscriptions = {
+            let mut data = ctx.data.write().await;
+            let subscriptions = data
+                .get_mut::<PodcastSubscriptionsKey>()
+                .expect("Guaranteed to exist in the typemap.");
+            subscriptions.unsubscribe(guild_id, feed);
+            subscriptions.clone()
+        };
+        save_subscriptions(&subscriptions);
+
+        info!(
+            "Unsubscribed from podcast '{}' in guild {}",
+            subscription.title, guild_id
+        );
+
+        let embed = CreateEmbed::new()
+            .description(format!("**Unsubscribed** from {}!", subscription.title))
+            .color(Color::DARK_GREEN);
+
+        if let Err(err) = interaction
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .embeds(vec![embed])
+                    .components(vec![]),
+            )
+            .await
+        {
+            error!("Failed to update podcast message: {}", err);
+        }
+        return;
+    }
+
+    let subscribed = match button {
+        PodcastButton::Subscribe { .. } => {
+            let subscriptions = {
+                let mut data = ctx.data.write().await;
+                let subscriptions = data
+                    .get_mut::<PodcastSubscriptionsKey>()
+                    .expect("Guaranteed to exist in the typemap.");
+                subscriptions.subscribe(guild_id, subscription.clone());
+                subscriptions.clone()
+            };
+            save_subscriptions(&subscriptions);
+
+            info!(
+                "Subscribed to podcast '{}' in guild {}",
+                subscription.title, guild_id
+            );
+            true
+        }
+        _ => subscribed,
+    };
+
+    // Feeds are fetched again so new episodes show up while browsing
+    let http_client = get_http_client(ctx).await;
+    let feed = match fetch_feed(&http_client, &subscription.url).await {
+        Ok(feed) => feed,
+        Err(err) => {
+            warn!("Failed to load podcast feed {}: {}", subscription.url, err);
+            let embed = CreateEmbed::new()
+                .description(format!("**Couldn't open that podcast!** {}", err))
+                .color(Color::DARK_RED);
+            let message = CreateInteractionResponseFollowup::new().embed(embed);
+            if let Err(err) = interaction.create_followup(&ctx.http, message).await {
+                error!("Failed to send followup response: {}", err);
+            }
+            return;
+        }
+    };
+
+    match button {
+        PodcastButton::Page { page, .. } => {
+            let page = page.min(feed.page_count() - 1);
+
+            if let Err(err) = interaction
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new()
+                        .embeds(vec![episode_page_embed(&feed, page)])
+                        .components(episode_page_buttons(&feed, feed_key, page, subscribed)),
+                )
+                .await
+            {
+                error!("Failed to update podcast episodes: {}", err);
+            }
+        }
+        PodcastButton::Play { episode, .. } => {
+            let Some(episode) = feed.episodes.iter().find(|listed| listed.key() == episode) else {
+                let embed = CreateEmbed::new()
+                    .description("That episode isn't in the feed anymore!")
+                    .color(Color::DARK_RED);
+                let message = CreateInteractionResponseFollowup::new().embed(embed);
+                if let Err(err) = interaction.create_followup(&ctx.http, message).await {
+                    error!("Failed to send followup response: {}", err);
+                }
+                return;
+            };
+
+            info!(
+                "Queueing podcast episode '{}' in guild {}",
+                episode.title, guild_id
+            );
+
+            let source = HttpRequest::new(http_client, episode.audio_url.clone());
+
+            enqueue_resolved_track_component(
+                ctx,
+                interaction,
+                source.into(),
+                episode.metadata(),
+                false,
+            )
+            .await;
+        }
+        PodcastButton::Subscribe { .. } => {
+            if let Err(err) = interaction
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new()
+                        .embeds(vec![episode_page_embed(&feed, 0)])
+                        .components(episode_page_buttons(&feed, feed_key, 0, subscribed)),
+                )
+                .await
+            {
+                error!("Failed to update podcast episodes: {}", err);
+            }
+
+            let embed = CreateEmbed::new()
+                .description(format!(
+                    "**Subscribed** to {}! Pick it from **/podcast** any time",
+                    feed.title
+                ))
+                .color(Color::DARK_GREEN);
+            let message = CreateInteractionResponseFollowup::new().embed(embed);
+            if let Err(err) = interaction.create_followup(&ctx.http, message).await {
+                error!("Failed to send followup response: {}", err);
+            }
+        }
+        PodcastButton::Unsubscribe { .. } => {}
+    }
+}
+
+/// Suggest the guild's subscribed feeds while the user types
+pub async fn autocomplete(ctx: &Context, command: &CommandInteraction) {
+    let (Some(focused), Some(guild_id)) = (command.data.autocomplete(), command.guild_id) else {
+        return;
+    };
+
+    let query = focused.value.to_lowercase();
+
+    let response = {
+        let data = ctx.data.read().await;
+        data.get::<PodcastSubscriptionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .list(guild_id)
+            .iter()
+            .filter(|subscription| subscription.title.to_lowercase().contains(&query))
+            .filter(|subscription| subscription.url.len() <= MAX_CHOICE_LENGTH)
+            .take(25)
+            .fold(
+                CreateAutocompleteResponse::new(),
+                |response, subscription| {
+                    let name: String = subscription.title.chars().take(MAX_CHOICE_LENGTH).collect();
+                    response.add_string_choice(name, subscription.url.clone())
+                },
+            )
+    };
+
+    if let Err(err) = command
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await
+    {
+        error!("Failed to send podcast autocomplete: {}", err);
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("podcast")
+        .description("Browse a podcast feed and queue its episodes")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "feed",
+                "An RSS or Atom feed URL, or a podcast this server subscribed to",
+            )
+            .set_autocomplete(true)
+            .required(true),
+        )
+}
@@ -0,0 +1,111 @@
+use serenity::{
+    all::{
+        ChannelId, Color, CommandInteraction, ComponentInteraction, CreateEmbed, GuildId, UserId,
+    },
+    client::Context,
+};
+use songbird::input::YoutubeDl;
+use tracing::{error, info, warn};
+
+use crate::utils::{
+    history::{pop_previous, record_played},
+    queue_position::QueuePosition,
+    response::{respond_to_button, respond_to_error_button, respond_to_followup},
+    track_utils::enqueue_resolved_track_at,
+    type_map::get_http_client,
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer previous command: {}", err);
+        return;
+    }
+
+    let guild_id = command.guild_id.unwrap();
+
+    match queue_previous(ctx, guild_id, command.channel_id, command.user.id).await {
+        Ok(description) => {
+            let embed = CreateEmbed::new()
+                .description(description)
+                .color(Color::DARK_GREEN);
+            respond_to_followup(command, &ctx.http, embed, false).await;
+        }
+        Err(description) => {
+            let embed = CreateEmbed::new()
+                .description(description)
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, embed, false).await;
+        }
+    }
+}
+
+pub async fn handle_button(ctx: &Context, command: &ComponentInteraction) {
+    let guild_id = command.guild_id.unwrap();
+
+    match queue_previous(ctx, guild_id, command.channel_id, command.user.id).await {
+        Ok(description) => respond_to_button(command, &ctx.http, description, false).await,
+        Err(description) => respond_to_error_button(command, &ctx.http, description).await,
+    }
+}
+
+/// Take the last finished track from the guild's history and put it at the
+/// front of the queue, subject to the guild's limits and duplicate policy.
+/// Returns the message to show the user.
+async fn queue_previous(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> Result<String, String> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    if manager.get(guild_id).is_none() {
+        warn!(
+            "Attempted to play previous song but bot is not in voice channel (guild {})",
+            guild_id
+        );
+        return Err(String::from(
+            "Error playing previous song! Ensure Poor Jimmy is in a voice channel with **/join**",
+        ));
+    }
+
+    let Some(entry) = pop_previous(&ctx.data, guild_id).await else {
+        return Err(String::from("There is no previous song to play!"));
+    };
+
+    let http_client = get_http_client(ctx).await;
+    let source = YoutubeDl::new(http_client, entry.source_url.clone());
+    let title = entry.metadata.title.clone();
+
+    info!(
+        "Re-queueing previous track '{}' in guild {}",
+        title, guild_id
+    );
+
+    let result = enqueue_resolved_track_at(
+        ctx,
+        guild_id,
+        user_id,
+        channel_id,
+        source.into(),
+        entry.metadata.clone(),
+        QueuePosition::Next,
+    )
+    .await;
+
+    match result {
+        Ok(_) => Ok(format!("**Queued** {} to play next!", title)),
+        Err(rejection) => {
+            // Keep the track in the history so it can be tried again
+            record_played(&ctx.data, guild_id, entry).await;
+            Err(rejection.description())
+        }
+    }
+}
+
+pub fn register() -> serenity::builder::CreateCommand {
+    serenity::builder::CreateCommand::new("previous")
+        .description("Queue the last played song to play next")
+}
@@ -0,0 +1,312 @@
+use serenity::{
+    all::{CommandDataOption, CommandInteraction, CommandOptionType, GuildId},
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed},
+    client::Context,
+    model::colour::Color,
+};
+use songbird::Event;
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::utils::{
+    dj::is_dj,
+    options::{string_option, subcommand},
+    queue_position::QueuePosition,
+    radio::{
+        RadioPresetsKey, RadioStation, StationPreset, StationTitlePoller, StreamKind,
+        TITLE_POLL_INTERVAL, fetch_icy_info, resolve_stream, save_presets, stream_input,
+    },
+    response::respond_to_followup,
+    track_utils::{TrackMetadata, enqueue_resolved_track},
+    type_map::get_http_client,
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer radio command: {}", err);
+        return;
+    }
+
+    let guild_id = command.guild_id.unwrap();
+
+    let result = match subcommand(command) {
+        Some(("play", options)) => {
+            play(ctx, command, options).await;
+            return;
+        }
+        // Presets are shared by the whole server
+        Some(("save" | "remove", _)) if !is_dj(ctx, guild_id, command.member.as_deref()) => Err(
+            String::from("Only DJs can change the server's radio stations!"),
+        ),
+        Some(("save", options)) => save(ctx, guild_id, options).await,
+        Some(("remove", options)) => remove(ctx, guild_id, options).await,
+        Some(("list", _)) => Ok(list(ctx, guild_id).await),
+        _ => Err(String::from("Unknown radio command!")),
+    };
+
+    let embed = match result {
+        Ok(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_GREEN),
+        Err(description) => CreateEmbed::new()
+            .description(description)
+            .color(Color::DARK_RED),
+    };
+
+    respond_to_followup(command, &ctx.http, embed, false).await;
+}
+
+/// Queue a station given as a preset name or stream URL
+async fn play(ctx: &Context, command: &CommandInteraction, options: &[CommandDataOption]) {
+    let guild_id = command.guild_id.unwrap();
+
+    let Some(station) = string_option(options, "station") else {
+        let embed = CreateEmbed::new()
+            .description("Please provide a station preset or stream URL!")
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    };
+
+    let preset = {
+        let data = ctx.data.read().await;
+        data.get::<RadioPresetsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .find(guild_id, station)
+            .cloned()
+    };
+
+    let (preset_name, url) = match preset {
+        Some(preset) => (Some(preset.name), preset.url),
+        None => (None, station.to_string()),
+    };
+
+    let http_client = get_http_client(ctx).await;
+
+    let (stream_url, kind) = match resolve_stream(&http_client, &url).await {
+        Ok(stream) => stream,
+        Err(err) => {
+            warn!("Failed to resolve radio stream {}: {}", url, err);
+            let embed = CreateEmbed::new()
+                .description(format!(
+                    "**Couldn't play that station!** {}. Use a saved preset from **/radio list** or a stream URL",
+                    err
+                ))
+                .color(Color::DARK_RED);
+            respond_to_followup(command, &ctx.http, embed, false).await;
+            return;
+        }
+    };
+
+    // HLS streams don't carry ICY metadata
+    let icy = match kind {
+        StreamKind::Direct => fetch_icy_info(&http_client, &stream_url)
+            .await
+            .unwrap_or_else(|err| {
+                warn!("Failed to fetch ICY metadata for {}: {}", stream_url, err);
+                Default::default()
+            }),
+        StreamKind::Hls => Default::default(),
+    };
+
+    let name = preset_name
+        .or(icy.name)
+        .unwrap_or_else(|| station_name_from_url(&stream_url));
+
+    info!("Playing radio station '{}' in guild {}", name, guild_id);
+
+    let station = RadioStation::new(name.clone(), stream_url.clone(), icy.song);
+
+    let metadata = TrackMetadata {
+        title: format!("📻 {}", name),
+        source_url: Some(url),
+        is_live: true,
+        station: Some(station.clone()),
+        ..Default::default()
+    };
+
+    let source = stream_input(http_client.clone(), stream_url, kind);
+
+    let Some(track) =
+        enqueue_resolved_track(ctx, command, source, metadata, QueuePosition::End).await
+    else {
+        return;
+    };
+
+    if kind == StreamKind::Direct {
+        let _ = track.add_event(
+            Event::Periodic(TITLE_POLL_INTERVAL, None),
+            StationTitlePoller {
+                client: http_client,
+                station,
+                channel_id: command.channel_id,
+                http: ctx.http.clone(),
+            },
+        );
+    }
+}
+
+/// Stations without a name of their own are called by their host
+fn station_name_from_url(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_else(|| String::from("Radio"))
+}
+
+async fn save(
+    ctx: &Context,
+    guild_id: GuildId,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let (Some(name), Some(url)) = (
+        string_option(options, "name"),
+        string_option(options, "url"),
+    ) else {
+        return Err(String::from(
+            "Please provide a name and stream URL for the preset!",
+        ));
+    };
+
+    let http_client = get_http_client(ctx).await;
+    if let Err(err) = resolve_stream(&http_client, url).await {
+        return Err(format!("**Couldn't save that station!** {}", err));
+    }
+
+    let mut data = ctx.data.write().await;
+    let presets = data
+        .get_mut::<RadioPresetsKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let replaced = presets.upsert(
+        guild_id,
+        StationPreset {
+            name: name.to_string(),
+            url: url.to_string(),
+        },
+    );
+    let presets = presets.clone();
+    drop(data);
+    save_presets(&presets);
+
+    info!("Saved radio preset '{}' in guild {}", name, guild_id);
+
+    Ok(if replaced {
+        format!("**Updated** the **{}** station!", name)
+    } else {
+        format!(
+            "**Saved** the **{}** station! Play it with `/radio play {}`",
+            name, name
+        )
+    })
+}
+
+async fn remove(
+    ctx: &Context,
+    guild_id: GuildId,
+    options: &[CommandDataOption],
+) -> Result<String, String> {
+    let Some(name) = string_option(options, "name") else {
+        return Err(String::from("Please provide the preset to remove!"));
+    };
+
+    let mut data = ctx.data.write().await;
+    let presets = data
+        .get_mut::<RadioPresetsKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    if !presets.remove(guild_id, name) {
+        return Err(format!("There is no station called **{}**!", name));
+    }
+    let presets = presets.clone();
+    drop(data);
+    save_presets(&presets);
+
+    Ok(format!("**Removed** the **{}** station!", name))
+}
+
+async fn list(ctx: &Context, guild_id: GuildId) -> String {
+    let data = ctx.data.read().await;
+    let presets = data
+        .get::<RadioPresetsKey>()
+        .expect("Guaranteed to exist in the typemap.")
+        .list(guild_id);
+
+    if presets.is_empty() {
+        return String::from("No stations saved yet! Add one with **/radio save**");
+    }
+
+    let lines = presets
+        .iter()
+        .map(|preset| format!("📻 **{}** - {}", preset.name, preset.url))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!("**Saved stations:**\n{}", lines)
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("radio")
+        .description("Play internet radio stations")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "play",
+                "Play a saved station or an Icecast, Shoutcast or HLS stream URL",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "station",
+                    "Preset name or stream URL",
+                )
+                .required(true),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "save",
+                "Save a station for this server",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "name", "Preset name")
+                    .max_length(50)
+                    .required(true),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "url", "Stream URL")
+                    .required(true),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "Remove a saved station",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::String, "name", "Preset name")
+                    .required(true),
+            ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List this server's saved stations",
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_station_name_from_url() {
+        assert_eq!(
+            station_name_from_url("http://stream.example.com:8000/lofi"),
+            "stream.example.com"
+        );
+        assert_eq!(station_name_from_url("not a url"), "Radio");
+    }
+}
@@ -1,37 +1,50 @@
-use serde::Deserialize;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use reqwest::Client as HttpClient;
 use serenity::{
     all::{
-        ButtonStyle, CommandDataOptionValue, CommandInteraction, CommandOptionType,
-        ComponentInteraction,
+        CommandDataOption, CommandInteraction, CommandOptionType, ComponentInteraction,
+        ComponentInteractionDataKind, GuildId, Http,
+    },
+    builder::{
+        CreateActionRow, CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+        CreateInteractionResponse, CreateInteractionResponseMessage, EditInteractionResponse,
+        EditMessage,
     },
-    builder::{CreateActionRow, CreateButton, CreateEmbed},
     client::Context,
     model::colour::Color,
+    prelude::{RwLock, TypeMap},
 };
-use tracing::{debug, error};
+use tracing::{debug, error, info};
 
 use crate::{
+    components::search_menu::{SearchComponent, create_search_menu, describe_result},
     utils::{
-        response::{respond_to_error_button, respond_to_followup},
-        track_utils::enqueue_track_component,
+        dj::is_dj,
+        enqueue_summary::EnqueueSummary,
+        lazy_metadata::{LazyMetadata, placeholder_metadata},
+        metadata_cache::{MetadataCache, cached_source, get_metadata_cache},
+        options::string_option,
+        response::{respond_to_error_button, respond_to_followup, respond_to_followup_component},
+        search_session::{ActiveSession, SearchSession, SearchSessionsKey, search_expiry},
+        track_utils::{
+            TrackMetadata, enqueue_resolved_track_component, enqueue_resolved_track_for,
+            enqueue_track_component, resolve_metadata,
+        },
         type_map::get_http_client,
+        youtube::watch_url,
+        youtube_search::{SearchFilters, SearchResult, UploadedWithin, search_youtube},
     },
 };
-use songbird::input::YoutubeDl;
-
-#[derive(Debug, Deserialize)]
-struct Thumbnail {
-    url: String,
-}
+use songbird::input::Input;
 
-#[derive(Debug, Deserialize)]
-struct SearchResult {
-    id: String,
-    title: String,
-    duration: Option<f64>,
-    #[serde(default)]
-    thumbnails: Vec<Thumbnail>,
-}
+/// Results looked up for each search, paged 25 at a time
+const MAX_RESULTS: usize = 50;
+/// Longest title shown in the results list, so a full page fits in an embed
+const MAX_TITLE_LENGTH: usize = 80;
 
 pub async fn run(ctx: &Context, command: &CommandInteraction) {
     if let Err(err) = command.defer(&ctx.http).await {
@@ -39,53 +52,26 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         return;
     }
 
-    let mut response_embed = CreateEmbed::default();
+    let options = &command.data.options;
 
-    let command_value = command.data.options.first();
-
-    let resolved_value = match command_value {
-        Some(data) => &data.value,
-        _ => {
-            response_embed = response_embed
-                .description("Please provide a search query!")
-                .color(Color::DARK_RED);
+    let Some(query) = string_option(options, "query").map(String::from) else {
+        let response_embed = CreateEmbed::default()
+            .description("Please provide a search query!")
+            .color(Color::DARK_RED);
 
-            respond_to_followup(command, &ctx.http, response_embed, false).await;
-            return;
-        }
+        respond_to_followup(command, &ctx.http, response_embed, false).await;
+        return;
     };
 
-    let query = match resolved_value {
-        CommandDataOptionValue::String(value) => value.clone(),
-        _ => {
-            response_embed = response_embed
-                .description("Please provide a valid search query!")
-                .color(Color::DARK_RED);
+    let filters = search_filters(options);
 
-            respond_to_followup(command, &ctx.http, response_embed, false).await;
-            return;
-        }
-    };
+    debug!("Searching YouTube for: {} ({:?})", query, filters);
 
-    debug!("Searching YouTube for: {}", query);
-
-    // Run yt-dlp to search YouTube
-    let output = match tokio::process::Command::new("yt-dlp")
-        .args(&[
-            "--default-search",
-            "ytsearch5",
-            "--dump-json",
-            "--no-playlist",
-            "--flat-playlist",
-            &query,
-        ])
-        .output()
-        .await
-    {
-        Ok(output) => output,
+    let mut results = match search_youtube(&query, &filters, MAX_RESULTS).await {
+        Ok(results) => results,
         Err(err) => {
-            error!("Failed to execute yt-dlp: {}", err);
-            response_embed = response_embed
+            error!("Failed to search YouTube: {}", err);
+            let response_embed = CreateEmbed::default()
                 .description("Failed to search YouTube. Please try again later.")
                 .color(Color::DARK_RED);
 
@@ -94,173 +80,414 @@ pub async fn run(ctx: &Context, command: &CommandInteraction) {
         }
     };
 
-    if !output.status.success() {
-        error!(
-            "yt-dlp command failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        response_embed = response_embed
-            .description("Failed to search YouTube. Please try again later.")
+    debug!("Found {} results for query", results.len());
+
+    if results.is_empty() {
+        let mut description = format!("No results found for \"{}\"", query);
+        if let Some(filters) = filters.describe() {
+            description.push_str(&format!(" with {}", filters));
+        }
+
+        let response_embed = CreateEmbed::default()
+            .description(description)
             .color(Color::DARK_RED);
 
         respond_to_followup(command, &ctx.http, response_embed, false).await;
         return;
     }
 
-    // Parse the JSON output - yt-dlp returns one JSON object per line
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    debug!("Stdout from yt-dlp query: {}", stdout);
+    // Search results can leave out what the cache already knows
+    let cache = get_metadata_cache(&ctx.data).await;
+    for result in results.iter_mut() {
+        if let Some(cached) = cache.peek(&result.id) {
+            result.duration = result.duration.or(cached.duration);
+            result.thumbnail_url = result.thumbnail_url.take().or(cached.thumbnail);
+        }
+    }
 
-    let results: Vec<SearchResult> = stdout
-        .lines()
-        .filter_map(|line| {
-            if line.is_empty() {
-                return None;
-            }
-            match serde_json::from_str::<SearchResult>(line) {
-                Ok(result) => Some(result),
-                Err(err) => {
-                    error!("Failed to parse search result: {}", err);
-                    error!("Line that failed: {}", line);
-                    None
-                }
-            }
-        })
-        .take(5)
-        .collect();
+    let session_id = command.id.get();
+    let session = SearchSession {
+        query,
+        filters,
+        results,
+        owner: command.user.id,
+        created: Instant::now(),
+    };
+    let (embed, components) = search_page(&session, session_id, 0, false);
+    let response = EditInteractionResponse::new()
+        .embed(embed)
+        .components(components);
 
-    debug!("Parsed {} results from query", results.len());
+    let message = match command.edit_response(&ctx.http, response).await {
+        Ok(message) => message,
+        Err(err) => {
+            error!("Failed to send search results: {}", err);
+            return;
+        }
+    };
 
-    if results.is_empty() {
-        response_embed = response_embed
-            .description(format!("No results found for \"{}\"", query))
-            .color(Color::DARK_RED);
+    let forgotten = {
+        let mut data = ctx.data.write().await;
+        data.get_mut::<SearchSessionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .start(session_id, session, message.channel_id, message.id)
+    };
 
-        respond_to_followup(command, &ctx.http, response_embed, false).await;
-        return;
+    if let Some(forgotten) = forgotten {
+        disable_search(&ctx.http, forgotten).await;
     }
 
-    // Create embeds for each search result
-    let embeds: Vec<CreateEmbed> = results
-        .iter()
-        .enumerate()
-        .map(|(idx, result)| {
-            let duration_str = result
-                .duration
-                .map(|d| {
-                    let total_seconds = d as u64;
-                    let minutes = total_seconds / 60;
-                    let seconds = total_seconds % 60;
-                    format!("{}:{:02}", minutes, seconds)
-                })
-                .unwrap_or_else(|| "Unknown".to_string());
-
-            let mut embed = CreateEmbed::default()
-                .title(format!("{}. {}", idx + 1, result.title))
-                .description(format!("Duration: {}", duration_str))
-                .url(format!("https://www.youtube.com/watch?v={}", result.id))
-                .color(Color::BLUE);
-
-            // Add thumbnail if available (use the last one which is usually highest quality)
-            if let Some(thumbnail) = result.thumbnails.last() {
-                embed = embed.thumbnail(&thumbnail.url);
-            }
+    tokio::spawn(expire_search(
+        ctx.data.clone(),
+        ctx.http.clone(),
+        session_id,
+        search_expiry(),
+    ));
+}
 
-            embed
-        })
-        .collect();
+/// Forget the search once it expires and disable its message
+async fn expire_search(
+    data: Arc<RwLock<TypeMap>>,
+    http: Arc<Http>,
+    session_id: u64,
+    expiry: Duration,
+) {
+    tokio::time::sleep(expiry).await;
+
+    let ended = {
+        let mut data = data.write().await;
+        data.get_mut::<SearchSessionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .end(session_id)
+    };
 
-    // Create buttons for each result
-    let buttons: Vec<CreateButton> = results
-        .iter()
-        .enumerate()
-        .map(|(idx, result)| {
-            CreateButton::new(format!("search_play_{}", result.id))
-                .label(format!("Option {}", idx + 1))
-                .style(ButtonStyle::Primary)
-        })
-        .collect();
+    // Searches forgotten to make room for newer ones were disabled already
+    if let Some(ended) = ended {
+        debug!("Search {} expired", session_id);
+        disable_search(&http, ended).await;
+    }
+}
 
-    // Discord allows up to 5 buttons per action row, we have max 5 results
-    let action_rows: Vec<CreateActionRow> = buttons
-        .chunks(5)
-        .map(|chunk| CreateActionRow::Buttons(chunk.to_vec()))
-        .collect();
+/// Leave the results of a forgotten search on the page they were left on,
+/// with nothing left to pick
+async fn disable_search(http: &Http, active: ActiveSession) {
+    let (embed, components) = search_page(&active.session, active.id, active.page, true);
+    let message = EditMessage::new().embed(embed).components(components);
 
-    if let Err(err) = command
-        .edit_response(
-            &ctx.http,
-            serenity::builder::EditInteractionResponse::new()
-                .embeds(embeds)
-                .components(action_rows),
-        )
+    if let Err(err) = active
+        .channel_id
+        .edit_message(http, active.message_id, message)
         .await
     {
-        error!("Failed to send search results: {}", err);
+        error!("Failed to disable search {}: {}", active.id, err);
     }
 }
 
+/// Page through the results or queue the ones picked from the menu
 pub async fn handle_component(ctx: &Context, interaction: &ComponentInteraction) {
-    if let Err(err) = interaction.defer(&ctx.http).await {
-        error!("Failed to defer search component interaction: {}", err);
+    let Some(component) = SearchComponent::parse(&interaction.data.custom_id) else {
+        error!("Invalid custom_id format: {}", interaction.data.custom_id);
+        respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
+        return;
+    };
+
+    let session_id = match component {
+        SearchComponent::Select { session } | SearchComponent::Page { session, .. } => session,
+    };
+
+    let session = {
+        let data = ctx.data.read().await;
+        data.get::<SearchSessionsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .get(session_id)
+    };
+
+    // Picks that race the expiry are turned away as well
+    let expiry = search_expiry();
+    let Some(session) = session.filter(|session| !session.is_expired(expiry)) else {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            "This search has expired! Search again with **/search**".to_string(),
+        )
+        .await;
+        return;
+    };
+
+    if session.owner != interaction.user.id
+        && !interaction
+            .guild_id
+            .is_some_and(|guild_id| is_dj(ctx, guild_id, interaction.member.as_ref()))
+    {
+        respond_to_error_button(
+            interaction,
+            &ctx.http,
+            String::from("Only the member who searched or a DJ can pick from these results!"),
+        )
+        .await;
         return;
     }
 
-    // Extract video ID from button custom_id (format: "search_play_{video_id}")
-    let video_id = match interaction.data.custom_id.strip_prefix("search_play_") {
-        Some(id) => id.to_string(),
-        None => {
-            error!("Invalid custom_id format: {}", interaction.data.custom_id);
-            // Delete the search results message
-            if let Err(err) = interaction.delete_response(&ctx.http).await {
-                error!("Failed to delete search results message: {}", err);
+    match component {
+        SearchComponent::Page { page, .. } => {
+            let page = page.min(session.page_count() - 1);
+            let (embed, components) = search_page(&session, session_id, page, false);
+
+            {
+                let mut data = ctx.data.write().await;
+                data.get_mut::<SearchSessionsKey>()
+                    .expect("Guaranteed to exist in the typemap.")
+                    .show_page(session_id, page);
+            }
+
+            let message = CreateInteractionResponseMessage::new()
+                .embed(embed)
+                .components(components);
+
+            if let Err(err) = interaction
+                .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(message))
+                .await
+            {
+                error!("Failed to update search results page: {}", err);
             }
-            respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string()).await;
-            return;
         }
+        SearchComponent::Select { .. } => {
+            let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+            else {
+                error!("Invalid search selection: {:?}", interaction.data.kind);
+                respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string())
+                    .await;
+                return;
+            };
+
+            let picked: Vec<SearchResult> = values
+                .iter()
+                .filter_map(|video_id| session.find(video_id).cloned())
+                .collect();
+
+            if picked.is_empty() {
+                respond_to_error_button(interaction, &ctx.http, "Invalid selection!".to_string())
+                    .await;
+                return;
+            }
+
+            if let Err(err) = interaction.defer(&ctx.http).await {
+                error!("Failed to defer search component interaction: {}", err);
+                return;
+            }
+
+            queue_picked(ctx, interaction, picked).await;
+        }
+    }
+}
+
+/// Queue the picked results in the order they were listed
+async fn queue_picked(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    picked: Vec<SearchResult>,
+) {
+    let Some(guild_id) = interaction.guild_id else {
+        return;
     };
 
-    let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
-    debug!("Playing selected video: {}", video_url);
+    let http_client = get_http_client(ctx).await;
+    let cache = get_metadata_cache(&ctx.data).await;
 
-    // Update the message to show we're processing the selection
-    let loading_embed = CreateEmbed::default()
-        .description("Adding track to queue...")
-        .color(Color::BLUE);
+    // A single pick is answered like any other song, with its duplicate
+    // warning and queue position
+    if let [result] = picked.as_slice() {
+        debug!("Playing selected video: {}", result.id);
 
-    if let Err(err) = interaction
-        .edit_response(
-            &ctx.http,
-            serenity::builder::EditInteractionResponse::new()
-                .embeds(vec![loading_embed])
-                .components(vec![]), // Remove buttons
+        let source = cached_source(http_client.clone(), &cache, watch_url(&result.id));
+
+        match placeholder(&ctx.data, &cache, &http_client, guild_id, result) {
+            Some(metadata) => {
+                enqueue_resolved_track_component(ctx, interaction, source, metadata, false).await
+            }
+            None => enqueue_track_component(ctx, interaction, source, false).await,
+        }
+        return;
+    }
+
+    info!(
+        "Queueing {} search results in guild {}",
+        picked.len(),
+        guild_id
+    );
+
+    let mut summary = EnqueueSummary::default();
+
+    for result in &picked {
+        let mut source: Input = cached_source(http_client.clone(), &cache, watch_url(&result.id));
+        let metadata = match placeholder(&ctx.data, &cache, &http_client, guild_id, result) {
+            Some(metadata) => metadata,
+            None => resolve_metadata(&http_client, &mut source, guild_id).await,
+        };
+
+        let result = enqueue_resolved_track_for(
+            ctx,
+            guild_id,
+            interaction.user.id,
+            interaction.channel_id,
+            source,
+            metadata,
         )
-        .await
-    {
-        error!("Failed to update search results message: {}", err);
+        .await;
+
+        if !summary.record(result) {
+            break;
+        }
     }
 
-    let http_client = get_http_client(ctx).await;
-    let source = YoutubeDl::new(http_client, video_url);
+    let headline = format!("**Queued** {} songs from the search!", summary.queued);
+    let embed = CreateEmbed::new()
+        .description(summary.describe(&headline))
+        .color(summary.color());
+
+    respond_to_followup_component(interaction, &ctx.http, embed, false).await;
+}
+
+/// Placeholder metadata to queue `result` with straight away, the rest is
+/// looked up in the background. Videos already cached are quick to look up
+/// in full, so they get none. Neither do results without a length, which
+/// have to be looked up first to check them against the guild's limits.
+fn placeholder(
+    data: &Arc<RwLock<TypeMap>>,
+    cache: &MetadataCache,
+    http_client: &HttpClient,
+    guild_id: GuildId,
+    result: &SearchResult,
+) -> Option<TrackMetadata> {
+    if result.duration.is_none() || cache.peek(&result.id).is_some() {
+        return None;
+    }
+
+    let lazy = LazyMetadata::new(data.clone(), http_client.clone(), cache.clone(), guild_id);
+
+    Some(placeholder_metadata(
+        lazy,
+        &result.id,
+        result.title.clone(),
+        result.duration,
+        result.thumbnail_url.clone(),
+    ))
+}
+
+/// The results list of `page` and the menu to pick from it
+fn search_page(
+    session: &SearchSession,
+    session_id: u64,
+    page: usize,
+    expired: bool,
+) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let (first_result, results) = session.page(page);
+
+    let description = results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let title: String = result.title.chars().take(MAX_TITLE_LENGTH).collect();
+
+            format!(
+                "**{}.** [{}]({}) · {}",
+                first_result + index + 1,
+                title.replace(['[', ']'], ""),
+                watch_url(&result.id),
+                describe_result(result)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut footer = format!(
+        "Page {} of {} · {} results",
+        page + 1,
+        session.page_count(),
+        session.results.len()
+    );
+    if let Some(filters) = session.filters.describe() {
+        footer.push_str(&format!(" · {}", filters));
+    }
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("🔎 Results for \"{}\"", session.query))
+        .description(description)
+        .footer(CreateEmbedFooter::new(footer))
+        .color(Color::BLUE);
 
-    // Delete the loading message before enqueueing
-    if let Err(err) = interaction.delete_response(&ctx.http).await {
-        error!("Failed to delete loading message: {}", err);
+    if let Some(thumbnail) = results
+        .first()
+        .and_then(|result| result.thumbnail_url.as_ref())
+    {
+        embed = embed.thumbnail(thumbnail);
     }
 
-    // Use the helper function to enqueue the track
-    enqueue_track_component(ctx, interaction, source.into()).await;
+    let components = create_search_menu(
+        session_id,
+        page,
+        session.page_count(),
+        first_result,
+        results,
+        expired,
+    );
+
+    (embed, components)
 }
 
-pub fn register() -> serenity::builder::CreateCommand {
-    serenity::builder::CreateCommand::new("search")
-        .description("Search YouTube and choose a video's audio to play")
+fn search_filters(options: &[CommandDataOption]) -> SearchFilters {
+    SearchFilters {
+        max_duration: options
+            .iter()
+            .find(|option| option.name == "max_minutes")
+            .and_then(|option| option.value.as_i64())
+            .and_then(|minutes| u64::try_from(minutes).ok())
+            .map(|minutes| Duration::from_secs(minutes * 60)),
+        channel: string_option(options, "channel").map(String::from),
+        uploaded_within: string_option(options, "uploaded").and_then(UploadedWithin::parse),
+        music_only: options
+            .iter()
+            .find(|option| option.name == "music_only")
+            .and_then(|option| option.value.as_bool())
+            .unwrap_or(false),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("search")
+        .description("Search YouTube and choose videos' audio to play")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "query", "Search query")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "max_minutes",
+                "Only videos up to this many minutes long",
+            )
+            .min_int_value(1),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "channel",
+            "Only videos from a channel whose name contains this",
+        ))
         .add_option(
-            serenity::builder::CreateCommandOption::new(
+            CreateCommandOption::new(
                 CommandOptionType::String,
-                "query",
-                "Search query",
+                "uploaded",
+                "Only videos uploaded recently",
             )
-            .required(true),
+            .add_string_choice("In the last hour", "hour")
+            .add_string_choice("Today", "day")
+            .add_string_choice("This week", "week")
+            .add_string_choice("This month", "month")
+            .add_string_choice("This year", "year"),
         )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "music_only",
+            "Only songs from YouTube Music",
+        ))
 }
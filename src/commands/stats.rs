@@ -0,0 +1,110 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter},
+    client::Context,
+    model::colour::Color,
+};
+use tracing::error;
+
+use crate::utils::{
+    options::string_option,
+    response::respond_to_followup,
+    stats::{ListeningStatsKey, StatsSummary, StatsWindow, unix_now},
+};
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    if let Err(err) = command.defer(&ctx.http).await {
+        error!("Failed to defer stats command: {}", err);
+        return;
+    }
+
+    let window = string_option(&command.data.options, "window")
+        .and_then(StatsWindow::parse)
+        .unwrap_or(StatsWindow::Week);
+
+    let summary = {
+        let data = ctx.data.read().await;
+        data.get::<ListeningStatsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .summary(command.guild_id.unwrap(), window.since(unix_now()))
+    };
+
+    if summary.plays == 0 {
+        let embed = CreateEmbed::new()
+            .description(format!("Nothing was played in {}!", window.describe()))
+            .color(Color::DARK_RED);
+        respond_to_followup(command, &ctx.http, embed, false).await;
+        return;
+    }
+
+    respond_to_followup(command, &ctx.http, stats_embed(&summary, window), false).await;
+}
+
+fn stats_embed(summary: &StatsSummary, window: StatsWindow) -> CreateEmbed {
+    let hours = summary.listened.as_secs_f64() / 3600.0;
+
+    let top_tracks = numbered(
+        summary
+            .top_tracks
+            .iter()
+            .map(|(title, plays)| format!("{} - {} plays", title, plays)),
+    );
+
+    let top_requesters = numbered(
+        summary
+            .top_requesters
+            .iter()
+            .map(|(user_id, tracks)| format!("<@{}> - {} tracks", user_id, tracks)),
+    );
+
+    let most_skipped = numbered(
+        summary
+            .most_skipped
+            .iter()
+            .map(|(title, skips)| format!("{} - {} skips", title, skips)),
+    );
+
+    CreateEmbed::new()
+        .title(format!("📊 Listening stats for {}", window.describe()))
+        .description(format!(
+            "**{:.1} hours** listened over **{}** tracks",
+            hours, summary.plays
+        ))
+        .field("Top tracks", top_tracks, false)
+        .field("Top requesters", top_requesters, false)
+        .field("Most skipped", most_skipped, false)
+        .footer(CreateEmbedFooter::new(
+            "Skips include tracks stopped by /clear",
+        ))
+        .color(Color::BLUE)
+}
+
+/// Number the lines of a ranking, or say there is nothing to rank
+fn numbered(lines: impl Iterator<Item = String>) -> String {
+    let lines: Vec<String> = lines
+        .enumerate()
+        .map(|(index, line)| format!("{}. {}", index + 1, line))
+        .collect();
+
+    if lines.is_empty() {
+        String::from("Nothing yet")
+    } else {
+        lines.join("\n")
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("stats")
+        .description("Show what this server listens to")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "window",
+                "Time to look back over",
+            )
+            .add_string_choice("Last 24 hours", "day")
+            .add_string_choice("Last 7 days", "week")
+            .add_string_choice("Last 30 days", "month")
+            .add_string_choice("All time", "all"),
+        )
+}
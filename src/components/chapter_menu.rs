@@ -0,0 +1,79 @@
+use serenity::builder::{
+    CreateActionRow, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+
+use crate::utils::{chapters::Chapter, format::format_duration};
+
+/// Discord allows at most 25 options in a select menu
+const MAX_OPTIONS: usize = 25;
+/// Discord limits option labels to 100 characters
+const MAX_LABEL_LENGTH: usize = 100;
+
+/// Select menu listing the chapters of the current track. Tracks with more
+/// than 25 chapters show the ones around `current`.
+pub fn create_chapter_menu(chapters: &[Chapter], current: Option<usize>) -> CreateActionRow {
+    let start = current
+        .unwrap_or(0)
+        .saturating_sub(MAX_OPTIONS / 2)
+        .min(chapters.len().saturating_sub(MAX_OPTIONS));
+
+    let options: Vec<CreateSelectMenuOption> = chapters
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(MAX_OPTIONS)
+        .map(|(index, chapter)| {
+            let label: String = format!("{}. {}", index + 1, chapter.title)
+                .chars()
+                .take(MAX_LABEL_LENGTH)
+                .collect();
+
+            CreateSelectMenuOption::new(label, index.to_string())
+                .description(format_duration(chapter.start))
+                .default_selection(Some(index) == current)
+        })
+        .collect();
+
+    let menu = CreateSelectMenu::new("chapter_select", CreateSelectMenuKind::String { options })
+        .placeholder("Jump to chapter");
+
+    CreateActionRow::SelectMenu(menu)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn chapters(count: usize) -> Vec<Chapter> {
+        (0..count)
+            .map(|i| Chapter {
+                title: format!("Part {}", i + 1),
+                start: Duration::from_secs(i as u64 * 30),
+            })
+            .collect()
+    }
+
+    fn option_count(row: &CreateActionRow) -> usize {
+        let CreateActionRow::SelectMenu(menu) = row else {
+            panic!("Expected CreateActionRow::SelectMenu variant");
+        };
+
+        let json = serde_json::to_value(menu).unwrap();
+        json["options"].as_array().unwrap().len()
+    }
+
+    #[test]
+    fn test_chapter_menu_lists_every_chapter() {
+        assert_eq!(option_count(&create_chapter_menu(&chapters(3), Some(1))), 3);
+    }
+
+    #[test]
+    fn test_chapter_menu_is_capped() {
+        assert_eq!(
+            option_count(&create_chapter_menu(&chapters(40), Some(39))),
+            25
+        );
+    }
+}
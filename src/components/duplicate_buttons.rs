@@ -0,0 +1,30 @@
+use serenity::{
+    all::ButtonStyle,
+    builder::{CreateActionRow, CreateButton},
+};
+
+/// Custom id prefix of the "queue anyway" button, followed by the video id
+pub const QUEUE_ANYWAY_PREFIX: &str = "queue_anyway_";
+
+pub fn create_queue_anyway_button(video_id: &str) -> CreateActionRow {
+    let queue_anyway_button = CreateButton::new(format!("{}{}", QUEUE_ANYWAY_PREFIX, video_id))
+        .label("➕ Queue anyway")
+        .style(ButtonStyle::Secondary);
+
+    CreateActionRow::Buttons(vec![queue_anyway_button])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_anyway_button_carries_video_id() {
+        let CreateActionRow::Buttons(buttons) = create_queue_anyway_button("dQw4w9WgXcQ") else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        };
+
+        let json = serde_json::to_value(&buttons[0]).unwrap();
+        assert_eq!(json["custom_id"], "queue_anyway_dQw4w9WgXcQ");
+    }
+}
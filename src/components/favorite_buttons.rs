@@ -0,0 +1,54 @@
+use serenity::{
+    all::ButtonStyle,
+    builder::{CreateActionRow, CreateButton},
+};
+
+/// Custom id prefix of the favorites page buttons, followed by the page
+pub const FAVORITES_PAGE_PREFIX: &str = "favorites_page_";
+
+/// Previous and next buttons under a page of `/favorites list`
+pub fn create_favorites_page_buttons(page: usize, page_count: usize) -> CreateActionRow {
+    let previous_button = CreateButton::new(format!(
+        "{}{}",
+        FAVORITES_PAGE_PREFIX,
+        page.saturating_sub(1)
+    ))
+    .label("◀ Previous")
+    .style(ButtonStyle::Secondary)
+    .disabled(page == 0);
+
+    let next_button = CreateButton::new(format!("{}{}", FAVORITES_PAGE_PREFIX, page + 1))
+        .label("Next ▶")
+        .style(ButtonStyle::Secondary)
+        .disabled(page + 1 >= page_count);
+
+    CreateActionRow::Buttons(vec![previous_button, next_button])
+}
+
+/// Page a favorites page button leads to
+pub fn parse_favorites_page(custom_id: &str) -> Option<usize> {
+    custom_id.strip_prefix(FAVORITES_PAGE_PREFIX)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_buttons_round_trip() {
+        let CreateActionRow::Buttons(buttons) = create_favorites_page_buttons(0, 3) else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        };
+
+        let previous = serde_json::to_value(&buttons[0]).unwrap();
+        let next = serde_json::to_value(&buttons[1]).unwrap();
+
+        assert_eq!(previous["disabled"], true);
+        assert_eq!(next["disabled"], false);
+        assert_eq!(
+            parse_favorites_page(next["custom_id"].as_str().unwrap()),
+            Some(1)
+        );
+        assert_eq!(parse_favorites_page("favorites_page_x"), None);
+    }
+}
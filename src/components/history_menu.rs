@@ -0,0 +1,100 @@
+use serenity::builder::{
+    CreateActionRow, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+
+use crate::utils::{format::format_duration, history::HistoryEntry};
+
+/// Custom id of the `/history` requeue menu
+pub const HISTORY_SELECT_ID: &str = "history_select";
+
+/// Discord allows at most 25 options in a select menu
+pub const MAX_OPTIONS: usize = 25;
+/// Discord limits option labels and values to 100 characters
+const MAX_LABEL_LENGTH: usize = 100;
+const MAX_VALUE_LENGTH: usize = 100;
+
+/// Select menu to queue one or several recently played tracks again.
+/// Options are the tracks' URLs, so the choice still holds after more
+/// tracks finish. Returns `None` when no track can be offered.
+pub fn create_history_menu(entries: &[HistoryEntry]) -> Option<CreateActionRow> {
+    let options: Vec<CreateSelectMenuOption> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.source_url.len() <= MAX_VALUE_LENGTH)
+        .take(MAX_OPTIONS)
+        .map(|(index, entry)| {
+            let label: String = format!("{}. {}", index + 1, entry.metadata.title)
+                .chars()
+                .take(MAX_LABEL_LENGTH)
+                .collect();
+
+            let option = CreateSelectMenuOption::new(label, entry.source_url.clone());
+
+            match entry.metadata.duration {
+                Some(duration) if !entry.metadata.is_live => {
+                    option.description(format_duration(duration))
+                }
+                _ => option,
+            }
+        })
+        .collect();
+
+    if options.is_empty() {
+        return None;
+    }
+
+    let max_values = options.len() as u8;
+    let menu = CreateSelectMenu::new(HISTORY_SELECT_ID, CreateSelectMenuKind::String { options })
+        .placeholder("Queue again")
+        .min_values(1)
+        .max_values(max_values);
+
+    Some(CreateActionRow::SelectMenu(menu))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::utils::track_utils::TrackMetadata;
+
+    fn entry(url: &str) -> HistoryEntry {
+        HistoryEntry {
+            source_url: url.to_string(),
+            metadata: TrackMetadata {
+                title: String::from("Song"),
+                duration: Some(Duration::from_secs(90)),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_history_menu_allows_picking_every_track() {
+        let entries = vec![
+            entry("https://www.youtube.com/watch?v=a"),
+            entry(&format!("https://example.com/{}", "x".repeat(100))),
+            entry("https://www.youtube.com/watch?v=b"),
+        ];
+
+        let Some(CreateActionRow::SelectMenu(menu)) = create_history_menu(&entries) else {
+            panic!("Expected CreateActionRow::SelectMenu variant");
+        };
+
+        let json = serde_json::to_value(menu).unwrap();
+        assert_eq!(json["custom_id"], HISTORY_SELECT_ID);
+        assert_eq!(json["max_values"], 2);
+        // Tracks keep the position they're listed at, even when one is left out
+        assert_eq!(json["options"][1]["label"], "3. Song");
+        assert_eq!(
+            json["options"][1]["value"],
+            "https://www.youtube.com/watch?v=b"
+        );
+    }
+
+    #[test]
+    fn test_history_menu_needs_a_track() {
+        assert!(create_history_menu(&[]).is_none());
+    }
+}
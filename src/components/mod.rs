@@ -1 +1,9 @@
+pub mod chapter_menu;
+pub mod duplicate_buttons;
+pub mod favorite_buttons;
+pub mod history_menu;
 pub mod music_buttons;
+pub mod playlist_buttons;
+pub mod podcast_buttons;
+pub mod save_track_menu;
+pub mod search_menu;
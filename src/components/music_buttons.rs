@@ -4,10 +4,22 @@ use serenity::{
 };
 
 pub fn create_music_buttons() -> Vec<CreateActionRow> {
+    let previous_button = CreateButton::new("previous")
+        .label("⏮️ Previous")
+        .style(ButtonStyle::Secondary);
+
     let clear_button = CreateButton::new("clear")
         .label("📋 Clear")
         .style(ButtonStyle::Danger);
 
+    let save_button = CreateButton::new("save_track")
+        .label("➕ Save")
+        .style(ButtonStyle::Secondary);
+
+    let favorite_button = CreateButton::new("favorite")
+        .label("❤️ Favorite")
+        .style(ButtonStyle::Secondary);
+
     let resume_button = CreateButton::new("resume")
         .label("▶️ Resume")
         .style(ButtonStyle::Success);
@@ -24,15 +36,18 @@ pub fn create_music_buttons() -> Vec<CreateActionRow> {
         .label("🔄 Loop")
         .style(ButtonStyle::Primary);
 
-    let row = CreateActionRow::Buttons(vec![
-        clear_button,
+    // Discord allows at most 5 buttons per action row
+    let playback_row = CreateActionRow::Buttons(vec![
+        previous_button,
         resume_button,
         pause_button,
         skip_button,
         loop_button,
     ]);
 
-    vec![row]
+    let queue_row = CreateActionRow::Buttons(vec![save_button, favorite_button, clear_button]);
+
+    vec![playback_row, queue_row]
 }
 
 #[cfg(test)]
@@ -40,16 +55,30 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_create_music_buttons_returns_single_row() {
+    fn test_create_music_buttons_returns_two_rows() {
         let buttons = create_music_buttons();
-        assert_eq!(buttons.len(), 1);
+        assert_eq!(buttons.len(), 2);
     }
 
     #[test]
     fn test_music_buttons_has_correct_count() {
         let buttons = create_music_buttons();
         if let CreateActionRow::Buttons(ref button_vec) = buttons[0] {
-            assert_eq!(button_vec.len(), 5, "Should have 5 buttons: clear, resume, pause, skip, loop");
+            assert_eq!(
+                button_vec.len(),
+                5,
+                "Should have 5 buttons: previous, resume, pause, skip, loop"
+            );
+        } else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        }
+
+        if let CreateActionRow::Buttons(ref button_vec) = buttons[1] {
+            assert_eq!(
+                button_vec.len(),
+                3,
+                "Should have 3 buttons: save, favorite, clear"
+            );
         } else {
             panic!("Expected CreateActionRow::Buttons variant");
         }
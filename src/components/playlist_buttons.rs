@@ -0,0 +1,40 @@
+use serenity::{
+    all::ButtonStyle,
+    builder::{CreateActionRow, CreateButton},
+};
+
+/// Custom id prefix of the button cancelling a playlist import, followed by
+/// the import's id
+pub const PLAYLIST_CANCEL_PREFIX: &str = "playlist_cancel_";
+
+pub fn create_playlist_cancel_button(import_id: u64) -> CreateActionRow {
+    let cancel_button = CreateButton::new(format!("{}{}", PLAYLIST_CANCEL_PREFIX, import_id))
+        .label("✖ Cancel")
+        .style(ButtonStyle::Danger);
+
+    CreateActionRow::Buttons(vec![cancel_button])
+}
+
+/// Import a cancel button belongs to
+pub fn parse_playlist_cancel(custom_id: &str) -> Option<u64> {
+    custom_id.strip_prefix(PLAYLIST_CANCEL_PREFIX)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_button_round_trip() {
+        let CreateActionRow::Buttons(buttons) = create_playlist_cancel_button(42) else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        };
+
+        let json = serde_json::to_value(&buttons[0]).unwrap();
+        assert_eq!(
+            parse_playlist_cancel(json["custom_id"].as_str().unwrap()),
+            Some(42)
+        );
+        assert_eq!(parse_playlist_cancel("playlist_cancel_x"), None);
+    }
+}
@@ -0,0 +1,181 @@
+use serenity::{
+    all::ButtonStyle,
+    builder::{CreateActionRow, CreateButton},
+};
+
+/// Custom id prefix shared by every podcast button
+pub const PODCAST_PREFIX: &str = "podcast_";
+
+/// What a podcast button does. Feeds and episodes are referred to by the
+/// `url_key` of their URL since custom ids are too short for the URLs, and
+/// keys keep pointing at the same feed and episode when the guild's
+/// subscriptions or the feed change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PodcastButton {
+    Page { feed: u64, page: usize },
+    Play { feed: u64, episode: u64 },
+    Subscribe { feed: u64 },
+    Unsubscribe { feed: u64 },
+}
+
+impl PodcastButton {
+    pub fn custom_id(&self) -> String {
+        match self {
+            Self::Page { feed, page } => format!("{}page_{:016x}_{}", PODCAST_PREFIX, feed, page),
+            Self::Play { feed, episode } => {
+                format!("{}play_{:016x}_{:016x}", PODCAST_PREFIX, feed, episode)
+            }
+            Self::Subscribe { feed } => format!("{}subscribe_{:016x}", PODCAST_PREFIX, feed),
+            Self::Unsubscribe { feed } => format!("{}unsubscribe_{:016x}", PODCAST_PREFIX, feed),
+        }
+    }
+
+    pub fn parse(custom_id: &str) -> Option<Self> {
+        let rest = custom_id.strip_prefix(PODCAST_PREFIX)?;
+        let (action, args) = rest.split_once('_')?;
+        let mut args = args.split('_');
+        let mut key = || u64::from_str_radix(args.next()?, 16).ok();
+
+        let button = match action {
+            "page" => Self::Page {
+                feed: key()?,
+                page: args.next()?.parse().ok()?,
+            },
+            "play" => Self::Play {
+                feed: key()?,
+                episode: key()?,
+            },
+            "subscribe" => Self::Subscribe { feed: key()? },
+            "unsubscribe" => Self::Unsubscribe { feed: key()? },
+            _ => return None,
+        };
+
+        args.next().is_none().then_some(button)
+    }
+}
+
+/// A play button for each episode on the page, then the page controls and
+/// a button to subscribe to the feed, or unsubscribe once `subscribed`
+pub fn create_episode_buttons(
+    feed: u64,
+    page: usize,
+    page_count: usize,
+    first_episode: usize,
+    episodes: &[u64],
+    subscribed: bool,
+) -> Vec<CreateActionRow> {
+    let play_buttons = episodes
+        .iter()
+        .enumerate()
+        .map(|(offset, &episode)| {
+            CreateButton::new(PodcastButton::Play { feed, episode }.custom_id())
+                .label(format!("▶ {}", first_episode + offset + 1))
+                .style(ButtonStyle::Primary)
+        })
+        .collect();
+
+    let previous_button = CreateButton::new(
+        PodcastButton::Page {
+            feed,
+            page: page.saturating_sub(1),
+        }
+        .custom_id(),
+    )
+    .label("◀ Newer")
+    .style(ButtonStyle::Secondary)
+    .disabled(page == 0);
+
+    let next_button = CreateButton::new(
+        PodcastButton::Page {
+            feed,
+            page: page + 1,
+        }
+        .custom_id(),
+    )
+    .label("Older ▶")
+    .style(ButtonStyle::Secondary)
+    .disabled(page + 1 >= page_count);
+
+    let subscription_button = if subscribed {
+        CreateButton::new(PodcastButton::Unsubscribe { feed }.custom_id())
+            .label("Unsubscribe")
+            .style(ButtonStyle::Danger)
+    } else {
+        CreateButton::new(PodcastButton::Subscribe { feed }.custom_id())
+            .label("Subscribe")
+            .style(ButtonStyle::Success)
+    };
+
+    let mut rows = Vec::new();
+    if !episodes.is_empty() {
+        rows.push(CreateActionRow::Buttons(play_buttons));
+    }
+    rows.push(CreateActionRow::Buttons(vec![
+        previous_button,
+        next_button,
+        subscription_button,
+    ]));
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_ids_round_trip() {
+        let buttons = [
+            PodcastButton::Page {
+                feed: u64::MAX,
+                page: 7,
+            },
+            PodcastButton::Play {
+                feed: 0,
+                episode: 0x1234_5678_9abc_def0,
+            },
+            PodcastButton::Subscribe { feed: 3 },
+            PodcastButton::Unsubscribe { feed: 4 },
+        ];
+
+        for button in buttons {
+            let custom_id = button.custom_id();
+            assert!(custom_id.len() <= 100);
+            assert_eq!(PodcastButton::parse(&custom_id), Some(button));
+        }
+
+        assert_eq!(PodcastButton::parse("podcast_play_1"), None);
+        assert_eq!(PodcastButton::parse("podcast_page_1_2_3"), None);
+        assert_eq!(PodcastButton::parse("podcast_page_xyz_2"), None);
+        assert_eq!(PodcastButton::parse("search_play_abc"), None);
+    }
+
+    #[test]
+    fn test_first_page_has_no_newer_button() {
+        let rows = create_episode_buttons(0, 0, 3, 0, &[1, 2, 3, 4, 5], false);
+        assert_eq!(rows.len(), 2);
+
+        let CreateActionRow::Buttons(controls) = &rows[1] else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        };
+
+        let newer = serde_json::to_value(&controls[0]).unwrap();
+        let older = serde_json::to_value(&controls[1]).unwrap();
+        assert_eq!(newer["disabled"], true);
+        assert_eq!(older["disabled"], false);
+    }
+
+    #[test]
+    fn test_subscription_button_follows_state() {
+        for (subscribed, expected) in [(false, "Subscribe"), (true, "Unsubscribe")] {
+            let rows = create_episode_buttons(7, 0, 1, 0, &[], subscribed);
+
+            let CreateActionRow::Buttons(controls) = &rows[0] else {
+                panic!("Expected CreateActionRow::Buttons variant");
+            };
+
+            let button = serde_json::to_value(&controls[2]).unwrap();
+            assert_eq!(button["label"], expected);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serenity::builder::{
+    CreateActionRow, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+
+use crate::utils::saved_playlists::SavedPlaylist;
+
+/// Custom id prefix of the playlist picker, followed by the uuid of the
+/// track being saved
+pub const SAVE_TRACK_PREFIX: &str = "save_track_";
+
+/// Discord limits option labels to 100 characters
+const MAX_LABEL_LENGTH: usize = 100;
+
+/// Select menu asking which of the user's playlists a track goes into.
+/// Options are the playlist indexes, as names can be longer than a value.
+pub fn create_save_track_menu(track_uuid: &str, playlists: &[SavedPlaylist]) -> CreateActionRow {
+    let options: Vec<CreateSelectMenuOption> = playlists
+        .iter()
+        .enumerate()
+        .map(|(index, playlist)| {
+            let label: String = playlist.name.chars().take(MAX_LABEL_LENGTH).collect();
+
+            CreateSelectMenuOption::new(label, index.to_string())
+                .description(format!("{} tracks", playlist.tracks.len()))
+        })
+        .collect();
+
+    let menu = CreateSelectMenu::new(
+        format!("{}{}", SAVE_TRACK_PREFIX, track_uuid),
+        CreateSelectMenuKind::String { options },
+    )
+    .placeholder("Save to playlist");
+
+    CreateActionRow::SelectMenu(menu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_track_menu_lists_playlists() {
+        let playlists = vec![
+            SavedPlaylist {
+                name: String::from("Chill"),
+                tracks: Vec::new(),
+            },
+            SavedPlaylist {
+                name: String::from("Gym"),
+                tracks: Vec::new(),
+            },
+        ];
+
+        let CreateActionRow::SelectMenu(menu) = create_save_track_menu("abc", &playlists) else {
+            panic!("Expected CreateActionRow::SelectMenu variant");
+        };
+
+        let json = serde_json::to_value(menu).unwrap();
+        assert_eq!(json["custom_id"], "save_track_abc");
+        assert_eq!(json["options"][1]["label"], "Gym");
+        assert_eq!(json["options"][1]["value"], "1");
+    }
+}
@@ -0,0 +1,244 @@
+use serenity::{
+    all::ButtonStyle,
+    builder::{
+        CreateActionRow, CreateButton, CreateSelectMenu, CreateSelectMenuKind,
+        CreateSelectMenuOption,
+    },
+};
+
+use crate::utils::{format::format_duration, youtube_search::SearchResult};
+
+/// Custom id prefix shared by the `/search` menu and its page buttons
+pub const SEARCH_PREFIX: &str = "search_";
+
+/// Discord limits option labels and descriptions to 100 characters
+const MAX_LABEL_LENGTH: usize = 100;
+
+/// What a search component does. Searches are referred to by the id of the
+/// interaction that started them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchComponent {
+    Select { session: u64 },
+    Page { session: u64, page: usize },
+}
+
+impl SearchComponent {
+    pub fn custom_id(&self) -> String {
+        match self {
+            Self::Select { session } => format!("{}select_{}", SEARCH_PREFIX, session),
+            Self::Page { session, page } => format!("{}page_{}_{}", SEARCH_PREFIX, session, page),
+        }
+    }
+
+    pub fn parse(custom_id: &str) -> Option<Self> {
+        let rest = custom_id.strip_prefix(SEARCH_PREFIX)?;
+        let (action, args) = rest.split_once('_')?;
+        let mut numbers = args.split('_').map(|n| n.parse::<u64>().ok());
+
+        let component = match action {
+            "select" => Self::Select {
+                session: numbers.next()??,
+            },
+            "page" => Self::Page {
+                session: numbers.next()??,
+                page: usize::try_from(numbers.next()??).ok()?,
+            },
+            _ => return None,
+        };
+
+        numbers.next().is_none().then_some(component)
+    }
+}
+
+/// Short description of a result: its channel and length
+pub fn describe_result(result: &SearchResult) -> String {
+    let length = match result.duration {
+        _ if result.is_live => String::from("🔴 Live"),
+        Some(duration) => format_duration(duration),
+        None => String::from("Unknown length"),
+    };
+
+    match &result.channel {
+        Some(channel) => format!("{} · {}", channel, length),
+        None => length,
+    }
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    text.chars().take(length).collect()
+}
+
+/// A select menu of the results on the page, several can be picked at once,
+/// followed by the page controls when there is more than one page. Expired
+/// searches keep their menu with everything disabled.
+pub fn create_search_menu(
+    session: u64,
+    page: usize,
+    page_count: usize,
+    first_result: usize,
+    results: &[SearchResult],
+    expired: bool,
+) -> Vec<CreateActionRow> {
+    let options: Vec<CreateSelectMenuOption> = results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let label = truncate(
+                &format!("{}. {}", first_result + index + 1, result.title),
+                MAX_LABEL_LENGTH,
+            );
+
+            CreateSelectMenuOption::new(label, result.id.clone())
+                .description(truncate(&describe_result(result), MAX_LABEL_LENGTH))
+        })
+        .collect();
+
+    let mut rows = Vec::new();
+
+    if !options.is_empty() {
+        let max_values = options.len() as u8;
+        let menu = CreateSelectMenu::new(
+            SearchComponent::Select { session }.custom_id(),
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder(if expired {
+            "This search has expired"
+        } else {
+            "Pick songs to queue"
+        })
+        .min_values(1)
+        .max_values(max_values)
+        .disabled(expired);
+
+        rows.push(CreateActionRow::SelectMenu(menu));
+    }
+
+    if page_count > 1 {
+        let previous_button = CreateButton::new(
+            SearchComponent::Page {
+                session,
+                page: page.saturating_sub(1),
+            }
+            .custom_id(),
+        )
+        .label("◀ Previous")
+        .style(ButtonStyle::Secondary)
+        .disabled(expired || page == 0);
+
+        let next_button = CreateButton::new(
+            SearchComponent::Page {
+                session,
+                page: page + 1,
+            }
+            .custom_id(),
+        )
+        .label("Next ▶")
+        .style(ButtonStyle::Secondary)
+        .disabled(expired || page + 1 >= page_count);
+
+        rows.push(CreateActionRow::Buttons(vec![previous_button, next_button]));
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn result(id: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            title: "x".repeat(120),
+            duration: Some(Duration::from_secs(213)),
+            thumbnail_url: None,
+            channel: Some(String::from("Rick Astley")),
+            is_live: false,
+            uploaded_at: None,
+        }
+    }
+
+    #[test]
+    fn test_custom_ids_round_trip() {
+        let components = [
+            SearchComponent::Select {
+                session: 1234567890123456789,
+            },
+            SearchComponent::Page {
+                session: 42,
+                page: 3,
+            },
+        ];
+
+        for component in components {
+            assert_eq!(
+                SearchComponent::parse(&component.custom_id()),
+                Some(component)
+            );
+        }
+
+        assert_eq!(SearchComponent::parse("search_page_1"), None);
+        assert_eq!(SearchComponent::parse("search_play_dQw4w9WgXcQ"), None);
+        assert_eq!(SearchComponent::parse("podcast_page_1_2"), None);
+    }
+
+    #[test]
+    fn test_menu_allows_picking_every_result_on_the_page() {
+        let results = vec![result("a"), result("b")];
+        let rows = create_search_menu(7, 1, 2, 25, &results, false);
+        assert_eq!(rows.len(), 2);
+
+        let CreateActionRow::SelectMenu(menu) = &rows[0] else {
+            panic!("Expected CreateActionRow::SelectMenu variant");
+        };
+
+        let json = serde_json::to_value(menu).unwrap();
+        assert_eq!(json["custom_id"], "search_select_7");
+        assert_eq!(json["max_values"], 2);
+        assert_eq!(json["options"][1]["value"], "b");
+        assert_eq!(json["options"][1]["description"], "Rick Astley · 03:33");
+
+        let label = json["options"][0]["label"].as_str().unwrap();
+        assert!(label.starts_with("26. x"));
+        assert_eq!(label.chars().count(), MAX_LABEL_LENGTH);
+
+        let CreateActionRow::Buttons(buttons) = &rows[1] else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        };
+
+        let previous = serde_json::to_value(&buttons[0]).unwrap();
+        let next = serde_json::to_value(&buttons[1]).unwrap();
+        assert_eq!(previous["custom_id"], "search_page_7_0");
+        assert_eq!(previous["disabled"], false);
+        assert_eq!(next["disabled"], true);
+    }
+
+    #[test]
+    fn test_single_page_has_no_page_buttons() {
+        let rows = create_search_menu(7, 0, 1, 0, &[result("a")], false);
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn test_expired_menu_is_disabled() {
+        let rows = create_search_menu(7, 0, 2, 0, &[result("a")], true);
+
+        let CreateActionRow::SelectMenu(menu) = &rows[0] else {
+            panic!("Expected CreateActionRow::SelectMenu variant");
+        };
+
+        let json = serde_json::to_value(menu).unwrap();
+        assert_eq!(json["disabled"], true);
+        assert_eq!(json["placeholder"], "This search has expired");
+
+        let CreateActionRow::Buttons(buttons) = &rows[1] else {
+            panic!("Expected CreateActionRow::Buttons variant");
+        };
+
+        for button in buttons {
+            assert_eq!(serde_json::to_value(button).unwrap()["disabled"], true);
+        }
+    }
+}
@@ -1,12 +1,17 @@
-use serenity::all::{Command, GuildId, Interaction, Ready};
+use serenity::all::{GuildId, Interaction, Ready};
 use serenity::async_trait;
 use serenity::client::{Context, EventHandler};
-use serenity::futures::stream::Cycle;
 use serenity::gateway::ActivityData;
 use serenity::model::user::OnlineStatus;
 use tracing::{debug, error, info};
 
 use crate::commands;
+use crate::components::duplicate_buttons::QUEUE_ANYWAY_PREFIX;
+use crate::components::favorite_buttons::FAVORITES_PAGE_PREFIX;
+use crate::components::playlist_buttons::PLAYLIST_CANCEL_PREFIX;
+use crate::components::podcast_buttons::PODCAST_PREFIX;
+use crate::components::save_track_menu::SAVE_TRACK_PREFIX;
+use crate::components::search_menu::SEARCH_PREFIX;
 use crate::utils::response::{respond_to_error, respond_to_error_button};
 
 /// The primary handler for the bot that handles all
@@ -30,26 +35,55 @@ impl EventHandler for BotEventHandler {
             );
 
             match command_name {
+                "autoplay" => commands::autoplay::run(&ctx, &command).await,
+                "chapter" => commands::chapter::run(&ctx, &command).await,
                 "clear" => commands::clear::run(&ctx, &command).await,
+                "dedupe" => commands::dedupe::run(&ctx, &command).await,
+                "duplicate-policy" => commands::duplicate_policy::run(&ctx, &command).await,
+                "favorites" => commands::favorites::run(&ctx, &command).await,
                 "help" => commands::help::run(&ctx, &command).await,
+                "history" => commands::history::run(&ctx, &command).await,
                 "join" => commands::join::run(&ctx, &command).await,
                 "leave" => commands::leave::run(&ctx, &command).await,
+                "library" => commands::library::run(&ctx, &command).await,
+                "limits" => commands::limits::run(&ctx, &command).await,
                 "list" => commands::list::run(&ctx, &command).await,
                 "loop" => commands::r#loop::run(&ctx, &command).await,
+                "myplaylist" => commands::myplaylist::run(&ctx, &command).await,
                 "now-playing" => commands::now_playing::run(&ctx, &command).await,
                 "pause" => commands::pause::run(&ctx, &command).await,
                 "ping" => commands::ping::run(&ctx, &command).await,
+                "Play in voice" => commands::play_file::run_context_menu(&ctx, &command).await,
+                "play-file" => commands::play_file::run(&ctx, &command).await,
+                "play-next" => commands::play_next::run(&ctx, &command).await,
+                "play-now" => commands::play_now::run(&ctx, &command).await,
                 "play-title" => commands::play_title::run(&ctx, &command).await,
                 "play-url" => commands::play_url::run(&ctx, &command).await,
                 "search" => commands::search::run(&ctx, &command).await,
                 "skip" => commands::skip::run(&ctx, &command).await,
+                "stats" => commands::stats::run(&ctx, &command).await,
                 "resume" => commands::resume::run(&ctx, &command).await,
                 "playlist" => commands::playlist::run(&ctx, &command).await,
+                "playlist-export" => commands::playlist_export::run(&ctx, &command).await,
+                "playlist-import" => commands::playlist_import::run(&ctx, &command).await,
+                "podcast" => commands::podcast::run(&ctx, &command).await,
+                "previous" => commands::previous::run(&ctx, &command).await,
+                "radio" => commands::radio::run(&ctx, &command).await,
+                "serverplaylist" => commands::myplaylist::run(&ctx, &command).await,
                 _ => {
                     error!("Unknown command received: {}", command_name);
                     respond_to_error(&command, &ctx.http, format!("Unknown command!")).await;
                 }
             };
+        } else if let Interaction::Autocomplete(command) = interaction {
+            match command.data.name.as_str() {
+                "library" => commands::library::autocomplete(&ctx, &command).await,
+                "myplaylist" | "playlist-export" | "serverplaylist" => {
+                    commands::myplaylist::autocomplete(&ctx, &command).await
+                }
+                "podcast" => commands::podcast::autocomplete(&ctx, &command).await,
+                name => debug!("No autocomplete for command '{}'", name),
+            }
         } else if let Interaction::Component(command) = interaction {
             let button_id = command.data.custom_id.as_str();
             let user = &command.user;
@@ -59,14 +93,29 @@ impl EventHandler for BotEventHandler {
                 button_id, user.name
             );
 
-            if button_id.starts_with("search_play_") {
+            if button_id.starts_with(SEARCH_PREFIX) {
                 commands::search::handle_component(&ctx, &command).await;
+            } else if button_id.starts_with(PODCAST_PREFIX) {
+                commands::podcast::handle_component(&ctx, &command).await;
+            } else if button_id.starts_with(FAVORITES_PAGE_PREFIX) {
+                commands::favorites::handle_page_button(&ctx, &command).await;
+            } else if button_id.starts_with(SAVE_TRACK_PREFIX) {
+                commands::myplaylist::handle_save_select(&ctx, &command).await;
+            } else if button_id.starts_with(PLAYLIST_CANCEL_PREFIX) {
+                commands::playlist::handle_cancel_button(&ctx, &command).await;
+            } else if button_id.starts_with(QUEUE_ANYWAY_PREFIX) {
+                commands::duplicate_policy::handle_queue_anyway(&ctx, &command).await;
             } else {
                 match button_id {
+                    "chapter_select" => commands::chapter::handle_select(&ctx, &command).await,
                     "clear" => commands::clear::handle_button(&ctx, &command).await,
+                    "favorite" => commands::favorites::handle_favorite_button(&ctx, &command).await,
+                    "history_select" => commands::history::handle_select(&ctx, &command).await,
                     "loop" => commands::r#loop::handle_button(&ctx, &command).await,
                     "pause" => commands::pause::handle_button(&ctx, &command).await,
+                    "previous" => commands::previous::handle_button(&ctx, &command).await,
                     "resume" => commands::resume::handle_button(&ctx, &command).await,
+                    "save_track" => commands::myplaylist::handle_save_button(&ctx, &command).await,
                     "skip" => commands::skip::handle_button(&ctx, &command).await,
                     _ => {
                         error!("Unknown button interaction received: {}", button_id);
@@ -82,21 +131,41 @@ impl EventHandler for BotEventHandler {
         info!("{} is connected! (ID: {})", ready.user.name, ready.user.id);
 
         let commands = vec![
+            commands::autoplay::register(),
+            commands::chapter::register(),
             commands::clear::register(),
+            commands::dedupe::register(),
+            commands::duplicate_policy::register(),
+            commands::favorites::register(),
             commands::help::register(),
+            commands::history::register(),
             commands::join::register(),
             commands::leave::register(),
+            commands::library::register(),
+            commands::limits::register(),
             commands::list::register(),
             commands::r#loop::register(),
+            commands::myplaylist::register(),
             commands::now_playing::register(),
             commands::pause::register(),
             commands::ping::register(),
+            commands::play_file::register(),
+            commands::play_file::register_context_menu(),
+            commands::play_next::register(),
+            commands::play_now::register(),
             commands::play_title::register(),
             commands::play_url::register(),
             commands::resume::register(),
             commands::search::register(),
             commands::skip::register(),
+            commands::stats::register(),
             commands::playlist::register(),
+            commands::playlist_export::register(),
+            commands::playlist_import::register(),
+            commands::podcast::register(),
+            commands::previous::register(),
+            commands::radio::register(),
+            commands::myplaylist::register_server(),
         ];
 
         info!("Registering {} slash commands globally...", commands.len());
@@ -5,32 +5,93 @@ use serenity::{
     builder::{CreateEmbed, CreateMessage},
     http::Http,
     model::{colour::Color, prelude::ChannelId, prelude::GuildId},
-    prelude::Mutex,
+    prelude::{Mutex, RwLock, TypeMap},
+};
+use songbird::{
+    Call, Event, EventContext, EventHandler as VoiceEventHandler, Songbird, tracks::PlayMode,
 };
-use songbird::{Call, Event, EventContext, EventHandler as VoiceEventHandler, Songbird};
 use tracing::{debug, error, info};
 
+use crate::utils::{
+    autoplay::find_recommendation,
+    guild_settings::get_guild_settings,
+    history::{HistoryEntry, record_played},
+    metadata_cache::{cached_source, get_metadata_cache},
+    stats::{PlayRecord, record_play},
+    track_utils::{enqueue_with_metadata, resolve_metadata, track_metadata},
+    type_map::get_http_client_from_data,
+    youtube::watch_url,
+};
+
 pub struct TrackEndNotifier {
     pub channel_id: ChannelId,
     pub http: Arc<Http>,
     pub call: Arc<Mutex<Call>>,
     pub guild_id: GuildId,
     pub manager: Arc<Songbird>,
+    pub data: Arc<RwLock<TypeMap>>,
 }
 
 #[async_trait]
 impl VoiceEventHandler for TrackEndNotifier {
     async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
         // Continue only if this is a Track event
-        let EventContext::Track(_) = ctx else {
+        let EventContext::Track(track_list) = ctx else {
             return None;
         };
 
+        // Remember every track that actually played for the listening stats and
+        // so it can be queued again. Tracks removed by /clear before starting have no play time.
+        for (state, track) in track_list.iter() {
+            if state.play_time.is_zero() {
+                continue;
+            }
+
+            let metadata = track_metadata(track);
+
+            // Tracks stopped before their end were skipped or cleared
+            let skipped = matches!(state.playing, PlayMode::Stop);
+            let play = PlayRecord::new(&metadata, state.play_time, skipped);
+            record_play(&self.data, self.guild_id, play).await;
+
+            if let Some(entry) = HistoryEntry::from_metadata(&metadata) {
+                record_played(&self.data, self.guild_id, entry).await;
+            }
+        }
+
         let handler = self.call.lock().await;
         let queue = handler.queue().current_queue();
+        drop(handler);
 
         if queue.is_empty() {
             debug!("Queue ended in channel {}", self.channel_id);
+
+            // Only keep the music going when the last track finished on its
+            // own, so /clear and /skip still stop playback.
+            let finished_naturally = track_list
+                .iter()
+                .any(|(state, _)| matches!(state.playing, PlayMode::End));
+
+            if finished_naturally
+                && get_guild_settings(&self.data, self.guild_id).await.autoplay
+                && let Some(title) = self.autoplay().await
+            {
+                let embed = CreateEmbed::new()
+                    .description(format!("**Autoplay** queued {}!", title))
+                    .color(Color::DARK_GREEN);
+
+                let message = CreateMessage::new().embed(embed);
+
+                if let Err(err) = self.channel_id.send_message(&self.http, message).await {
+                    error!(
+                        "Failed to send autoplay notification to channel {}: {}",
+                        self.channel_id, err
+                    );
+                }
+
+                return None;
+            }
+
             // No songs left in the queue, notify the channel
             let embed = CreateEmbed::new()
                 .description("Queue has **ended!**")
@@ -108,3 +169,27 @@ impl VoiceEventHandler for TrackEndNotifier {
         None
     }
 }
+
+impl TrackEndNotifier {
+    /// Queue a recommendation based on the guild's history. Returns the title
+    /// of the queued track.
+    async fn autoplay(&self) -> Option<String> {
+        let video_id = find_recommendation(&self.data, self.guild_id).await?;
+
+        let http_client = get_http_client_from_data(&self.data).await;
+        let cache = get_metadata_cache(&self.data).await;
+        let mut source = cached_source(http_client.clone(), &cache, watch_url(&video_id));
+        let metadata = resolve_metadata(&http_client, &mut source, self.guild_id).await;
+        let title = metadata.title.clone();
+
+        info!(
+            "Autoplay queueing '{}' ({}) in guild {}",
+            title, video_id, self.guild_id
+        );
+
+        let mut handler = self.call.lock().await;
+        enqueue_with_metadata(&self.http, &mut handler, source, metadata, self.channel_id).await;
+
+        Some(title)
+    }
+}
@@ -9,36 +9,103 @@ use serenity::{
 use songbird::{Event, EventContext, EventHandler};
 use tracing::{error, info};
 
-use crate::components::music_buttons::create_music_buttons;
+use crate::{
+    components::music_buttons::create_music_buttons,
+    utils::{
+        lazy_metadata::update_announcement, sponsorblock::describe_segments,
+        track_utils::TrackMetadata,
+    },
+};
 
 pub struct TrackPlayHandler {
     pub channel_id: ChannelId,
     pub http: Arc<Http>,
-    pub title: String,
-    pub thumbnail: String,
+}
+
+/// The "Now playing" embed announced when a track starts
+pub fn create_now_playing_embed(
+    title: &str,
+    thumbnail: &str,
+    metadata: &TrackMetadata,
+) -> CreateEmbed {
+    let mut description = format!("**Now playing:** {}", title);
+
+    if let Some(song) = metadata.station.as_ref().and_then(|station| station.song()) {
+        description.push_str(&format!("\n🎵 {}", song));
+    }
+
+    if metadata.is_live {
+        description.push_str("\n\n🔴 **LIVE**");
+    }
+
+    if !metadata.sponsor_segments.is_empty() {
+        description.push_str(&format!(
+            "\n\n**SponsorBlock will skip:**\n{}",
+            describe_segments(&metadata.sponsor_segments)
+        ));
+    }
+
+    let mut embed = CreateEmbed::new()
+        .description(description)
+        .color(Color::DARK_GREEN);
+
+    if !thumbnail.is_empty() {
+        embed = embed.image(thumbnail);
+    }
+
+    embed
 }
 
 #[async_trait]
 impl EventHandler for TrackPlayHandler {
     async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
         // Continue only if this is a Track event
-        let EventContext::Track(_) = ctx else {
+        let EventContext::Track(track_list) = ctx else {
             return None;
         };
 
-        info!("Now playing: '{}' in channel {}", self.title, self.channel_id);
+        let (_, track) = track_list.first()?;
 
-        let embed = CreateEmbed::new()
-            .description(format!("**Now playing:** {}", self.title.clone()))
-            .image(self.thumbnail.clone())
-            .color(Color::DARK_GREEN);
+        let metadata = track.data::<TrackMetadata>();
+        let latest = metadata.latest();
+
+        info!(
+            "Now playing: '{}' in channel {}",
+            latest.title, self.channel_id
+        );
+
+        metadata.play_start.mark();
+
+        let embed = create_now_playing_embed(
+            &latest.title,
+            latest.thumbnail_url.as_deref().unwrap_or_default(),
+            &latest,
+        );
 
         let message = CreateMessage::new()
             .embed(embed)
             .components(create_music_buttons());
 
-        if let Err(err) = self.channel_id.send_message(&self.http, message).await {
-            error!("Failed to send now playing message to channel {}: {}", self.channel_id, err);
+        match self.channel_id.send_message(&self.http, message).await {
+            // Stations edit this message as their song changes
+            Ok(message) => {
+                if let Some(station) = &metadata.station {
+                    station.set_announcement(message.id);
+                }
+
+                // Placeholders edit it once their metadata arrives
+                if let Some(lazy) = &metadata.lazy
+                    && lazy.set_announcement(message.id, latest.lazy.is_none())
+                {
+                    update_announcement(&self.http, self.channel_id, message.id, track).await;
+                }
+            }
+            Err(err) => {
+                error!(
+                    "Failed to send now playing message to channel {}: {}",
+                    self.channel_id, err
+                );
+            }
         }
 
         None
@@ -4,8 +4,9 @@ mod handlers;
 mod utils;
 
 use dotenv::dotenv;
-use std::env;
+use std::{collections::HashMap, env};
 
+use commands::list::QueueMessagesKey;
 use handlers::bot_event::BotEventHandler;
 use reqwest::Client as HttpClient;
 use serenity::client::ClientBuilder;
@@ -13,7 +14,20 @@ use serenity::prelude::*;
 use songbird::SerenityInit;
 use tracing::{error, info};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
-use utils::type_map::HttpKey;
+use utils::{
+    favorites::{Favorites, FavoritesKey},
+    guild_settings::{GuildSettingsKey, GuildSettingsStore},
+    history::HistoryKey,
+    library::{LibraryIndex, LibraryKey, library_dir, watch_library},
+    metadata_cache::{MetadataCache, MetadataCacheKey, flush_metadata_cache, save_metadata_cache},
+    podcast::{OpenedFeedsKey, PodcastSubscriptions, PodcastSubscriptionsKey},
+    radio::{RadioPresets, RadioPresetsKey},
+    saved_playlists::{PlaylistStore, PlaylistStoreKey},
+    search_session::{SearchSessions, SearchSessionsKey},
+    stats::{ListeningStats, ListeningStatsKey, flush_stats, save_stats},
+    type_map::HttpKey,
+    youtube_playlist::{PlaylistImports, PlaylistImportsKey},
+};
 
 #[tokio::main]
 async fn main() {
@@ -49,6 +63,19 @@ async fn main() {
         .event_handler(BotEventHandler)
         .register_songbird()
         .type_map_insert::<HttpKey>(HttpClient::new())
+        .type_map_insert::<HistoryKey>(HashMap::new())
+        .type_map_insert::<GuildSettingsKey>(GuildSettingsStore::load())
+        .type_map_insert::<RadioPresetsKey>(RadioPresets::load())
+        .type_map_insert::<LibraryKey>(LibraryIndex::load())
+        .type_map_insert::<PodcastSubscriptionsKey>(PodcastSubscriptions::load())
+        .type_map_insert::<OpenedFeedsKey>(HashMap::new())
+        .type_map_insert::<PlaylistStoreKey>(PlaylistStore::load())
+        .type_map_insert::<FavoritesKey>(Favorites::load())
+        .type_map_insert::<ListeningStatsKey>(ListeningStats::load())
+        .type_map_insert::<PlaylistImportsKey>(PlaylistImports::default())
+        .type_map_insert::<MetadataCacheKey>(MetadataCache::load())
+        .type_map_insert::<SearchSessionsKey>(SearchSessions::default())
+        .type_map_insert::<QueueMessagesKey>(HashMap::new())
         .await
     {
         Ok(client) => client,
@@ -58,6 +85,26 @@ async fn main() {
         }
     };
 
+    tokio::spawn(save_stats(client.data.clone()));
+    tokio::spawn(save_metadata_cache(client.data.clone()));
+
+    if let Some(dir) = library_dir() {
+        info!("Watching music library in {}", dir.display());
+        tokio::spawn(watch_library(client.data.clone(), dir));
+    }
+
+    // Save what is only kept in memory before the bot goes away
+    let shard_manager = client.shard_manager.clone();
+    let data = client.data.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down, saving listening stats and the metadata cache...");
+
+        flush_stats(&data).await;
+        flush_metadata_cache(&data).await;
+        shard_manager.shutdown_all().await;
+    });
+
     info!("Starting Discord client connection...");
 
     if let Err(why) = client.start().await {
@@ -65,3 +112,28 @@ async fn main() {
         std::process::exit(1);
     }
 }
+
+/// Resolves on Ctrl-C, or when a container or service manager stops the bot
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(err) => error!("Failed to listen for SIGTERM: {}", err),
+        }
+    }
+
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen for Ctrl-C: {}", err);
+        // Without a signal to wait for, never shut down on our own
+        std::future::pending::<()>().await;
+    }
+}
@@ -0,0 +1,211 @@
+use std::{env, time::Duration};
+
+use symphonia::core::{
+    formats::FormatOptions,
+    io::{MediaSource, MediaSourceStream},
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey},
+    probe::Hint,
+};
+
+/// File extensions of the audio formats that can be played
+pub const AUDIO_EXTENSIONS: &[&str] = &["aac", "flac", "m4a", "mka", "mp3", "oga", "ogg", "wav"];
+
+/// Tags and length read from an audio file
+#[derive(Debug, Default, PartialEq)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl AudioTags {
+    /// "Artist - Title", falling back to `fallback` when the file has no
+    /// title tag
+    pub fn display_title(&self, fallback: &str) -> String {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+            (None, Some(title)) => title.clone(),
+            _ => fallback.to_string(),
+        }
+    }
+}
+
+/// Largest attachment `/play-file` accepts. Set MAX_ATTACHMENT_MB to
+/// override the default of 25MB.
+pub fn max_attachment_bytes() -> u64 {
+    env::var("MAX_ATTACHMENT_MB")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(25)
+        * 1024
+        * 1024
+}
+
+/// Lowercase extension of a file name, if it has one
+pub fn file_extension(filename: &str) -> Option<String> {
+    let (_, extension) = filename.rsplit_once('.')?;
+    Some(extension.to_lowercase())
+}
+
+/// Whether a file looks like audio from its MIME type or, when that is
+/// missing, its extension
+pub fn is_audio_file(filename: &str, content_type: Option<&str>) -> bool {
+    match content_type {
+        Some(content_type) => {
+            let content_type = content_type.to_lowercase();
+            content_type.starts_with("audio/") || content_type == "application/ogg"
+        }
+        None => file_extension(filename)
+            .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.as_str())),
+    }
+}
+
+/// Probe an audio source with symphonia and read its tags and length.
+/// Returns `None` when symphonia doesn't recognise it as audio.
+pub fn probe_audio(source: Box<dyn MediaSource>, extension: Option<&str>) -> Option<AudioTags> {
+    let stream = MediaSourceStream::new(source, Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
+
+    let track = probed.format.default_track()?;
+    let params = &track.codec_params;
+    let duration = match (params.n_frames, params.time_base, params.sample_rate) {
+        (Some(frames), Some(time_base), _) => {
+            let time = time_base.calc_time(frames);
+            Some(Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+        }
+        (Some(frames), None, Some(rate)) if rate > 0 => {
+            Some(Duration::from_secs_f64(frames as f64 / rate as f64))
+        }
+        _ => None,
+    };
+
+    let mut tags = AudioTags {
+        duration,
+        ..Default::default()
+    };
+
+    // Tags can come before the container (e.g. ID3v2) or inside it
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        read_tags(revision, &mut tags);
+    }
+
+    if let Some(revision) = probed.format.metadata().current() {
+        read_tags(revision, &mut tags);
+    }
+
+    Some(tags)
+}
+
+fn read_tags(revision: &MetadataRevision, tags: &mut AudioTags) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string().trim_matches(['\0', ' ']).to_string();
+        if value.is_empty() {
+            continue;
+        }
+
+        let field = match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => &mut tags.title,
+            Some(StandardTagKey::Artist) => &mut tags.artist,
+            Some(StandardTagKey::AlbumArtist) if tags.artist.is_none() => &mut tags.artist,
+            Some(StandardTagKey::Album) => &mut tags.album,
+            _ => continue,
+        };
+
+        if field.is_none() {
+            *field = Some(value);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(body);
+        chunk
+    }
+
+    /// One second of 8kHz mono silence as a WAV file with title and artist
+    /// tags
+    pub fn tagged_wav(title: &str, artist: &str) -> Vec<u8> {
+        let mut format = Vec::new();
+        format.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        format.extend_from_slice(&1u16.to_le_bytes()); // mono
+        format.extend_from_slice(&8000u32.to_le_bytes()); // sample rate
+        format.extend_from_slice(&16000u32.to_le_bytes()); // byte rate
+        format.extend_from_slice(&2u16.to_le_bytes()); // block align
+        format.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+
+        let pad = |value: &str| {
+            let mut bytes = value.as_bytes().to_vec();
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            bytes
+        };
+
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(b"INAM", &pad(title)));
+        info.extend(chunk(b"IART", &pad(artist)));
+
+        let mut wave = b"WAVE".to_vec();
+        wave.extend(chunk(b"fmt ", &format));
+        wave.extend(chunk(b"LIST", &info));
+        wave.extend(chunk(b"data", &[0u8; 16000]));
+
+        chunk(b"RIFF", &wave)
+    }
+
+    #[test]
+    fn test_is_audio_file() {
+        assert!(is_audio_file("song.mp3", Some("audio/mpeg")));
+        assert!(is_audio_file("song", Some("application/ogg")));
+        assert!(is_audio_file("song.FLAC", None));
+        assert!(!is_audio_file("song.mp3", Some("image/png")));
+        assert!(!is_audio_file("notes.txt", None));
+        assert!(!is_audio_file("README", None));
+    }
+
+    #[test]
+    fn test_probe_wav_tags() {
+        let wav = tagged_wav("Song", "Band");
+
+        let tags = probe_audio(Box::new(Cursor::new(wav)), Some("wav")).unwrap();
+
+        assert_eq!(tags.title.as_deref(), Some("Song"));
+        assert_eq!(tags.artist.as_deref(), Some("Band"));
+        assert_eq!(tags.duration, Some(Duration::from_secs(1)));
+        assert_eq!(tags.display_title("song.wav"), "Band - Song");
+    }
+
+    #[test]
+    fn test_probe_rejects_non_audio() {
+        let text = b"definitely not audio".to_vec();
+
+        assert_eq!(probe_audio(Box::new(Cursor::new(text)), Some("txt")), None);
+    }
+
+    #[test]
+    fn test_display_title_falls_back_to_file_name() {
+        assert_eq!(AudioTags::default().display_title("song.mp3"), "song.mp3");
+    }
+}
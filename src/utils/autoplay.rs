@@ -0,0 +1,79 @@
+use std::{collections::HashSet, env, sync::Arc};
+
+use serenity::{
+    all::GuildId,
+    prelude::{RwLock, TypeMap},
+};
+use tracing::debug;
+
+use crate::utils::{
+    history::recent_history,
+    youtube::{fetch_video_details, video_id_from_url},
+};
+
+/// How many recently played tracks autoplay avoids repeating.
+/// Set AUTOPLAY_DEDUPE_WINDOW to override the default of 25.
+pub fn dedupe_window() -> usize {
+    env::var("AUTOPLAY_DEDUPE_WINDOW")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(25)
+}
+
+/// Find a video related to the last track played in the guild that has not
+/// been played within the dedupe window. Returns the video id.
+pub async fn find_recommendation(data: &Arc<RwLock<TypeMap>>, guild_id: GuildId) -> Option<String> {
+    let recent = recent_history(data, guild_id, dedupe_window().max(1)).await;
+
+    let recent_ids: HashSet<String> = recent
+        .iter()
+        .filter_map(|entry| video_id_from_url(&entry.source_url))
+        .collect();
+
+    let last_id = video_id_from_url(&recent.first()?.source_url)?;
+
+    debug!(
+        "Fetching related videos for {} in guild {}",
+        last_id, guild_id
+    );
+
+    let details = fetch_video_details(&last_id).await?;
+
+    let candidates = details
+        .recommended
+        .items
+        .iter()
+        .filter(|video| !video.is_live)
+        .map(|video| video.id.as_str());
+
+    first_unplayed(candidates, &recent_ids).map(String::from)
+}
+
+/// Pick the first candidate that was not played recently
+fn first_unplayed<'a>(
+    candidates: impl IntoIterator<Item = &'a str>,
+    recent_ids: &HashSet<String>,
+) -> Option<&'a str> {
+    candidates.into_iter().find(|id| !recent_ids.contains(*id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_unplayed_skips_recent_ids() {
+        let recent: HashSet<String> = ["a".to_string(), "b".to_string()].into();
+        let candidates = vec!["a", "b", "c", "d"];
+
+        assert_eq!(first_unplayed(candidates, &recent), Some("c"));
+    }
+
+    #[test]
+    fn test_first_unplayed_when_everything_was_played() {
+        let recent: HashSet<String> = ["a".to_string()].into();
+
+        assert_eq!(first_unplayed(vec!["a"], &recent), None);
+        assert_eq!(first_unplayed(Vec::new(), &recent), None);
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use rustypipe::model::VideoDetails;
+
+/// A named section of a video, e.g. one song of an album upload
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub start: Duration,
+}
+
+/// Where `/chapter` should jump to
+#[derive(Debug, PartialEq)]
+pub enum ChapterTarget {
+    Next,
+    Previous,
+    /// 1-based chapter number as shown to users
+    Number(usize),
+}
+
+impl ChapterTarget {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "next" | "n" => Some(Self::Next),
+            "prev" | "previous" | "p" => Some(Self::Previous),
+            other => other.parse::<usize>().ok().map(Self::Number),
+        }
+    }
+}
+
+/// Convert the chapters YouTube lists for a video
+pub fn chapters_from_details(details: &VideoDetails) -> Vec<Chapter> {
+    details
+        .chapters
+        .iter()
+        .map(|chapter| Chapter {
+            title: chapter.name.clone(),
+            start: Duration::from_secs(chapter.position.into()),
+        })
+        .collect()
+}
+
+/// Index of the chapter playing at `position`
+pub fn current_chapter(chapters: &[Chapter], position: Duration) -> Option<usize> {
+    chapters
+        .iter()
+        .rposition(|chapter| chapter.start <= position)
+}
+
+/// Index of the chapter `target` points at, if it exists
+pub fn resolve_target(
+    chapters: &[Chapter],
+    current: Option<usize>,
+    target: &ChapterTarget,
+) -> Option<usize> {
+    let index = match target {
+        ChapterTarget::Next => current.map_or(0, |i| i + 1),
+        ChapterTarget::Previous => current?.checked_sub(1)?,
+        ChapterTarget::Number(number) => number.checked_sub(1)?,
+    };
+
+    (index < chapters.len()).then_some(index)
+}
+
+/// "Chapter 2/5: Title"
+pub fn describe_chapter(chapters: &[Chapter], index: usize) -> String {
+    format!(
+        "Chapter {}/{}: {}",
+        index + 1,
+        chapters.len(),
+        chapters[index].title
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapters() -> Vec<Chapter> {
+        ["Intro", "Song One", "Song Two"]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Chapter {
+                title: title.to_string(),
+                start: Duration::from_secs(i as u64 * 60),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_chapter_target() {
+        assert_eq!(ChapterTarget::parse("next"), Some(ChapterTarget::Next));
+        assert_eq!(
+            ChapterTarget::parse(" Prev "),
+            Some(ChapterTarget::Previous)
+        );
+        assert_eq!(ChapterTarget::parse("3"), Some(ChapterTarget::Number(3)));
+        assert_eq!(ChapterTarget::parse("later"), None);
+    }
+
+    #[test]
+    fn test_current_chapter() {
+        let chapters = chapters();
+
+        assert_eq!(current_chapter(&chapters, Duration::from_secs(0)), Some(0));
+        assert_eq!(current_chapter(&chapters, Duration::from_secs(90)), Some(1));
+        assert_eq!(
+            current_chapter(&chapters, Duration::from_secs(600)),
+            Some(2)
+        );
+        assert_eq!(current_chapter(&[], Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let chapters = chapters();
+
+        assert_eq!(
+            resolve_target(&chapters, Some(0), &ChapterTarget::Next),
+            Some(1)
+        );
+        assert_eq!(
+            resolve_target(&chapters, Some(2), &ChapterTarget::Next),
+            None
+        );
+        assert_eq!(
+            resolve_target(&chapters, Some(2), &ChapterTarget::Previous),
+            Some(1)
+        );
+        assert_eq!(
+            resolve_target(&chapters, Some(0), &ChapterTarget::Previous),
+            None
+        );
+        assert_eq!(
+            resolve_target(&chapters, None, &ChapterTarget::Number(3)),
+            Some(2)
+        );
+        assert_eq!(
+            resolve_target(&chapters, None, &ChapterTarget::Number(0)),
+            None
+        );
+        assert_eq!(
+            resolve_target(&chapters, None, &ChapterTarget::Number(4)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_describe_chapter() {
+        assert_eq!(describe_chapter(&chapters(), 1), "Chapter 2/3: Song One");
+    }
+}
@@ -0,0 +1,68 @@
+//! DJs are members trusted to change shared things like server playlists:
+//! anyone with Manage Server, or with the DJ role.
+
+use std::env;
+
+use serenity::{
+    all::{GuildId, Member},
+    client::Context,
+    model::Permissions,
+};
+
+/// Response for members who tried something only DJs can do
+pub const DJ_ONLY: &str = "Only DJs can change server playlists! Ask someone with the DJ role";
+
+/// Name of the role that makes a member a DJ. Set DJ_ROLE to override the
+/// default of `DJ`.
+pub fn dj_role_name() -> String {
+    env::var("DJ_ROLE")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("DJ"))
+}
+
+/// Whether any of `role_names` is the DJ role, ignoring case
+fn has_dj_role<'a>(mut role_names: impl Iterator<Item = &'a str>, dj_role: &str) -> bool {
+    role_names.any(|name| name.eq_ignore_ascii_case(dj_role))
+}
+
+/// Whether the member behind an interaction is a DJ. Role names come from
+/// the cache, so a member whose server isn't cached only counts through
+/// their permissions.
+pub fn is_dj(ctx: &Context, guild_id: GuildId, member: Option<&Member>) -> bool {
+    let Some(member) = member else {
+        return false;
+    };
+
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD))
+    {
+        return true;
+    }
+
+    let Some(guild) = ctx.cache.guild(guild_id) else {
+        return false;
+    };
+
+    let role_names = member
+        .roles
+        .iter()
+        .filter_map(|role_id| guild.roles.get(role_id))
+        .map(|role| role.name.as_str());
+
+    has_dj_role(role_names, &dj_role_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_dj_role_ignores_case() {
+        assert!(has_dj_role(["Members", "dj"].into_iter(), "DJ"));
+        assert!(!has_dj_role(["Members", "DJs"].into_iter(), "DJ"));
+        assert!(!has_dj_role(std::iter::empty(), "DJ"));
+    }
+}
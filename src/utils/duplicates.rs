@@ -0,0 +1,185 @@
+use std::{collections::HashSet, env};
+
+use serde::{Deserialize, Serialize};
+use songbird::tracks::TrackQueue;
+
+use crate::utils::{track_utils::TrackMetadata, youtube::video_id_from_url};
+
+/// What happens when a track that is already queued is requested again
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Queue it again without asking
+    Allow,
+    /// Ask first, with a button to queue it anyway
+    Warn,
+    /// Refuse to queue it
+    Reject,
+}
+
+impl DuplicatePolicy {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "allow" => Some(Self::Allow),
+            "warn" => Some(Self::Warn),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+impl Default for DuplicatePolicy {
+    /// Read from DUPLICATE_POLICY, allowing duplicates unless configured
+    /// otherwise so guilds opt in with `/duplicate-policy`
+    fn default() -> Self {
+        env::var("DUPLICATE_POLICY")
+            .ok()
+            .and_then(|s| Self::parse(&s))
+            .unwrap_or(Self::Allow)
+    }
+}
+
+/// The canonical video id a track is compared by. Tracks that are not
+/// YouTube videos have no key and are never treated as duplicates.
+pub fn track_key(metadata: &TrackMetadata) -> Option<String> {
+    metadata.source_url.as_deref().and_then(video_id_from_url)
+}
+
+/// Index in `queue` of a track with the same video as `metadata`
+pub fn find_duplicate(queue: &TrackQueue, metadata: &TrackMetadata) -> Option<usize> {
+    let key = track_key(metadata)?;
+
+    position_of(
+        queue
+            .current_queue()
+            .iter()
+            .map(|track| track_key(&track.data::<TrackMetadata>())),
+        &key,
+    )
+}
+
+fn position_of(keys: impl IntoIterator<Item = Option<String>>, key: &str) -> Option<usize> {
+    keys.into_iter()
+        .position(|existing| existing.as_deref() == Some(key))
+}
+
+/// Indexes of every track whose video already appeared earlier in the list
+fn duplicate_positions(keys: impl IntoIterator<Item = Option<String>>) -> Vec<usize> {
+    let mut seen = HashSet::new();
+
+    keys.into_iter()
+        .enumerate()
+        .filter_map(|(index, key)| (!seen.insert(key?)).then_some(index))
+        .collect()
+}
+
+/// Remove every repeat of a video from `queue`, keeping its first
+/// occurrence. The playing track is always first, so it is never removed.
+/// Returns the titles of the removed tracks.
+pub fn remove_duplicates(queue: &TrackQueue) -> Vec<String> {
+    let removed = queue.modify_queue(|tracks| {
+        let positions = duplicate_positions(
+            tracks
+                .iter()
+                .map(|track| track_key(&track.data::<TrackMetadata>())),
+        );
+
+        // Remove from the back so earlier indexes stay valid
+        positions
+            .into_iter()
+            .rev()
+            .filter_map(|index| tracks.remove(index))
+            .collect::<Vec<_>>()
+    });
+
+    removed
+        .iter()
+        .rev()
+        .map(|track| {
+            // Removed tracks are still loaded in the mixer until stopped
+            let _ = track.stop();
+            track.data::<TrackMetadata>().title.clone()
+        })
+        .collect()
+}
+
+/// "is playing right now" or "is #3 in the queue"
+pub fn describe_position(index: usize) -> String {
+    if index == 0 {
+        String::from("is playing right now")
+    } else {
+        format!("is #{} in the queue", index + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(ids: &[Option<&str>]) -> Vec<Option<String>> {
+        ids.iter().map(|id| id.map(String::from)).collect()
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(DuplicatePolicy::parse("Warn"), Some(DuplicatePolicy::Warn));
+        assert_eq!(
+            DuplicatePolicy::parse(" reject "),
+            Some(DuplicatePolicy::Reject)
+        );
+        assert_eq!(DuplicatePolicy::parse("sometimes"), None);
+    }
+
+    #[test]
+    fn test_track_key_is_the_video_id() {
+        let short_link = TrackMetadata {
+            source_url: Some("https://youtu.be/dQw4w9WgXcQ".to_string()),
+            ..Default::default()
+        };
+        let watch_link = TrackMetadata {
+            source_url: Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(track_key(&short_link), Some("dQw4w9WgXcQ".to_string()));
+        assert_eq!(track_key(&short_link), track_key(&watch_link));
+        assert_eq!(track_key(&TrackMetadata::default()), None);
+    }
+
+    #[test]
+    fn test_position_of() {
+        let queue = keys(&[Some("a"), None, Some("b")]);
+
+        assert_eq!(position_of(queue.clone(), "b"), Some(2));
+        assert_eq!(position_of(queue, "c"), None);
+    }
+
+    #[test]
+    fn test_duplicate_positions_keep_first_occurrence() {
+        let queue = keys(&[
+            Some("a"),
+            Some("b"),
+            Some("a"),
+            None,
+            None,
+            Some("b"),
+            Some("a"),
+        ]);
+
+        assert_eq!(duplicate_positions(queue), vec![2, 5, 6]);
+    }
+
+    #[test]
+    fn test_describe_position() {
+        assert_eq!(describe_position(0), "is playing right now");
+        assert_eq!(describe_position(2), "is #3 in the queue");
+    }
+}
@@ -0,0 +1,138 @@
+use serenity::model::colour::Color;
+
+use crate::utils::{limits::LimitExceeded, track_utils::EnqueueRejection};
+
+/// Tally of queueing many tracks at once, such as a playlist, reported to
+/// the user when done
+#[derive(Debug, Default)]
+pub struct EnqueueSummary {
+    pub queued: usize,
+    pub skipped_too_long: usize,
+    pub skipped_duplicates: usize,
+    /// Tracks past the server's playlist size limit, never tried
+    pub left_out: usize,
+    /// Why the rest of the tracks could not be queued
+    pub stopped_by: Option<EnqueueRejection>,
+}
+
+impl EnqueueSummary {
+    /// Count the outcome of queueing one track. Returns `false` once the
+    /// queue won't accept anything else, so callers can stop early.
+    pub fn record(&mut self, result: Result<(), EnqueueRejection>) -> bool {
+        match result {
+            Ok(()) => self.queued += 1,
+            Err(EnqueueRejection::Limit(LimitExceeded::TrackTooLong { .. })) => {
+                self.skipped_too_long += 1
+            }
+            Err(EnqueueRejection::Duplicate { .. }) => self.skipped_duplicates += 1,
+            Err(rejection) => {
+                self.stopped_by = Some(rejection);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// `headline` followed by a line for each kind of skipped track
+    pub fn describe(&self, headline: &str) -> String {
+        let mut description = headline.to_string();
+
+        if self.skipped_too_long > 0 {
+            description.push_str(&format!(
+                "\nSkipped {} tracks over this server's track length limit",
+                self.skipped_too_long
+            ));
+        }
+
+        if self.skipped_duplicates > 0 {
+            description.push_str(&format!(
+                "\nSkipped {} tracks that were already queued",
+                self.skipped_duplicates
+            ));
+        }
+
+        if self.left_out > 0 {
+            description.push_str(&format!(
+                "\nLeft out {} tracks over this server's playlist size limit",
+                self.left_out
+            ));
+        }
+
+        if let Some(rejection) = &self.stopped_by {
+            description.push_str(&format!("\n{}", rejection.description()));
+        }
+
+        description
+    }
+
+    pub fn color(&self) -> Color {
+        if self.stopped_by.is_some() || self.queued == 0 {
+            Color::DARK_RED
+        } else {
+            Color::DARK_GREEN
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_summary_counts_and_stops_on_full_queue() {
+        let mut summary = EnqueueSummary::default();
+
+        assert!(summary.record(Ok(())));
+        assert!(
+            summary.record(Err(EnqueueRejection::Limit(LimitExceeded::TrackTooLong {
+                duration: Duration::from_secs(600),
+                max: Duration::from_secs(300),
+            })))
+        );
+        assert!(summary.record(Err(EnqueueRejection::Duplicate {
+            index: 1,
+            warn: true
+        })));
+        assert!(
+            !summary.record(Err(EnqueueRejection::Limit(LimitExceeded::QueueFull {
+                max: 2
+            })))
+        );
+
+        assert_eq!(summary.queued, 1);
+        assert_eq!(summary.color(), Color::DARK_RED);
+
+        let description = summary.describe("**Queued** 1 track!");
+        assert!(description.starts_with("**Queued** 1 track!\nSkipped 1 tracks over"));
+        assert!(description.contains("already queued"));
+        assert!(description.contains("**Queue is full!**"));
+    }
+
+    #[test]
+    fn test_summary_reports_left_out_tracks_and_missing_call() {
+        let mut summary = EnqueueSummary {
+            left_out: 30,
+            ..Default::default()
+        };
+
+        assert!(!summary.record(Err(EnqueueRejection::NotInVoice)));
+        assert_eq!(summary.queued, 0);
+        assert_eq!(summary.color(), Color::DARK_RED);
+
+        let description = summary.describe("**Queued** 0 tracks!");
+        assert!(description.contains("Left out 30 tracks"));
+        assert!(description.contains("**/join**"));
+    }
+
+    #[test]
+    fn test_summary_without_skips_is_just_the_headline() {
+        let mut summary = EnqueueSummary::default();
+        summary.record(Ok(()));
+
+        assert_eq!(summary.describe("Done!"), "Done!");
+        assert_eq!(summary.color(), Color::DARK_GREEN);
+    }
+}
@@ -0,0 +1,199 @@
+//! Tracks users liked with the ❤️ button, kept per user across servers.
+
+use std::{collections::HashMap, io};
+
+use serde::{Deserialize, Serialize};
+use serenity::{all::UserId, prelude::TypeMapKey};
+use tracing::error;
+
+use crate::utils::{
+    json_store::{self, data_file},
+    saved_playlists::SavedTrack,
+    track_utils::TrackMetadata,
+    youtube::video_id_from_url,
+};
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// Most favorites one user can keep
+pub const MAX_FAVORITES: usize = 1000;
+/// Favorites shown on each page of `/favorites list`
+pub const FAVORITES_PER_PAGE: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FavoriteTrack {
+    /// YouTube video id, used to spot the same video under different URLs
+    #[serde(default)]
+    pub video_id: Option<String>,
+    #[serde(flatten)]
+    pub track: SavedTrack,
+}
+
+impl FavoriteTrack {
+    pub fn from_metadata(metadata: &TrackMetadata) -> Option<Self> {
+        let track = SavedTrack::from_metadata(metadata)?;
+
+        Some(Self {
+            video_id: video_id_from_url(&track.url),
+            track,
+        })
+    }
+
+    fn is_same_track(&self, other: &FavoriteTrack) -> bool {
+        match (&self.video_id, &other.video_id) {
+            (Some(id), Some(other_id)) => id == other_id,
+            _ => self.track.url == other.track.url,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FavoriteError {
+    AlreadyFavorite,
+    Full,
+}
+
+impl FavoriteError {
+    pub fn description(&self) -> String {
+        match self {
+            FavoriteError::AlreadyFavorite => String::from("That's already one of your favorites!"),
+            FavoriteError::Full => format!(
+                "**Too many favorites!** Remove some first, up to {} can be kept",
+                MAX_FAVORITES
+            ),
+        }
+    }
+}
+
+/// Number of pages `count` favorites fill, at least one
+pub fn page_count(count: usize) -> usize {
+    count.div_ceil(FAVORITES_PER_PAGE).max(1)
+}
+
+/// Every user's favorites, oldest first, stored in the data directory
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Favorites {
+    users: HashMap<u64, Vec<FavoriteTrack>>,
+}
+
+impl Favorites {
+    pub fn load() -> Self {
+        json_store::load(&data_file(FAVORITES_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        json_store::save(&data_file(FAVORITES_FILE), self)
+    }
+
+    pub fn list(&self, user_id: UserId) -> &[FavoriteTrack] {
+        self.users
+            .get(&user_id.get())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, user_id: UserId, favorite: FavoriteTrack) -> Result<(), FavoriteError> {
+        let favorites = self.users.entry(user_id.get()).or_default();
+
+        if favorites
+            .iter()
+            .any(|existing| existing.is_same_track(&favorite))
+        {
+            return Err(FavoriteError::AlreadyFavorite);
+        }
+
+        if favorites.len() >= MAX_FAVORITES {
+            return Err(FavoriteError::Full);
+        }
+
+        favorites.push(favorite);
+        Ok(())
+    }
+
+    /// Remove the favorite at a 1-based `position`
+    pub fn remove(&mut self, user_id: UserId, position: usize) -> Option<FavoriteTrack> {
+        let favorites = self.users.get_mut(&user_id.get())?;
+
+        if position == 0 || position > favorites.len() {
+            return None;
+        }
+
+        Some(favorites.remove(position - 1))
+    }
+}
+
+/// Save the favorites, logging instead of failing since the change still
+/// applies until the bot restarts
+pub fn save_favorites(favorites: &Favorites) {
+    if let Err(err) = favorites.save() {
+        error!("Failed to save favorites: {}", err);
+    }
+}
+
+pub struct FavoritesKey;
+
+impl TypeMapKey for FavoritesKey {
+    type Value = Favorites;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(url: &str) -> FavoriteTrack {
+        FavoriteTrack::from_metadata(&TrackMetadata {
+            title: String::from("Song"),
+            source_url: Some(url.to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_same_video_is_only_added_once() {
+        let mut favorites = Favorites::default();
+        let user = UserId::new(1);
+
+        favorites
+            .add(
+                user,
+                favorite("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            )
+            .unwrap();
+        assert_eq!(
+            favorites.add(user, favorite("https://youtu.be/dQw4w9WgXcQ")),
+            Err(FavoriteError::AlreadyFavorite)
+        );
+        assert_eq!(
+            favorites.list(user)[0].video_id.as_deref(),
+            Some("dQw4w9WgXcQ")
+        );
+
+        favorites
+            .add(user, favorite("https://example.com/a.mp3"))
+            .unwrap();
+        assert_eq!(favorites.list(user).len(), 2);
+        assert!(favorites.list(UserId::new(2)).is_empty());
+    }
+
+    #[test]
+    fn test_remove_by_position() {
+        let mut favorites = Favorites::default();
+        let user = UserId::new(1);
+        favorites
+            .add(user, favorite("https://example.com/a.mp3"))
+            .unwrap();
+
+        assert!(favorites.remove(user, 2).is_none());
+        assert!(favorites.remove(user, 1).is_some());
+        assert!(favorites.list(user).is_empty());
+    }
+
+    #[test]
+    fn test_page_count() {
+        assert_eq!(page_count(0), 1);
+        assert_eq!(page_count(FAVORITES_PER_PAGE), 1);
+        assert_eq!(page_count(FAVORITES_PER_PAGE + 1), 2);
+    }
+}
@@ -48,6 +48,27 @@ pub fn create_progress_bar(
     format!("[{}{}] {} / {}", filled, empty, current_str, total_str)
 }
 
+/// Create the badge shown instead of a progress bar for live streams
+/// Example: 🔴 LIVE · 12:34 elapsed
+pub fn create_live_badge(elapsed: Duration) -> String {
+    format!("🔴 LIVE · {} elapsed", format_duration(elapsed))
+}
+
+/// Sum the durations of the given tracks, skipping live streams and tracks
+/// with unknown length. Returns the total and how many tracks were skipped.
+pub fn total_duration<I>(tracks: I) -> (Duration, usize)
+where
+    I: IntoIterator<Item = (Option<Duration>, bool)>,
+{
+    tracks.into_iter().fold(
+        (Duration::ZERO, 0),
+        |(total, skipped), (duration, is_live)| match duration {
+            Some(duration) if !is_live => (total + duration, skipped),
+            _ => (total, skipped + 1),
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,6 +159,26 @@ mod tests {
         assert!(bar_20.contains("02:30 / 05:00"));
     }
 
+    #[test]
+    fn test_live_badge() {
+        let badge = create_live_badge(Duration::from_secs(754));
+        assert_eq!(badge, "🔴 LIVE · 12:34 elapsed");
+        assert!(!badge.contains("/"));
+    }
+
+    #[test]
+    fn test_total_duration_skips_live_and_unknown() {
+        let tracks = vec![
+            (Some(Duration::from_secs(120)), false),
+            (Some(Duration::from_secs(60)), false),
+            (None, true),
+            (None, false),
+        ];
+
+        assert_eq!(total_duration(tracks), (Duration::from_secs(180), 2));
+        assert_eq!(total_duration(Vec::new()), (Duration::ZERO, 0));
+    }
+
     #[test]
     fn test_progress_bar_zero_length() {
         let current = Duration::from_secs(0);
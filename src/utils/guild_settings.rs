@@ -0,0 +1,129 @@
+use std::{collections::HashMap, io, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::GuildId,
+    prelude::{RwLock, TypeMap, TypeMapKey},
+};
+use tracing::error;
+
+use crate::utils::{
+    duplicates::DuplicatePolicy,
+    json_store::{self, data_file},
+    limits::QueueLimits,
+};
+
+const SETTINGS_FILE: &str = "guild_settings.json";
+
+/// Per-guild toggles changed through slash commands
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    /// Queue a related video when the queue runs dry
+    pub autoplay: bool,
+    /// What members are allowed to queue
+    pub limits: QueueLimits,
+    /// What happens when a track already in the queue is requested again
+    pub duplicate_policy: DuplicatePolicy,
+}
+
+/// Settings of every guild, stored in the data directory
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GuildSettingsStore {
+    guilds: HashMap<u64, GuildSettings>,
+}
+
+impl GuildSettingsStore {
+    pub fn load() -> Self {
+        json_store::load(&data_file(SETTINGS_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        json_store::save(&data_file(SETTINGS_FILE), self)
+    }
+
+    /// The guild's settings, or the defaults if it never changed any
+    pub fn get(&self, guild_id: GuildId) -> GuildSettings {
+        self.guilds
+            .get(&guild_id.get())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, guild_id: GuildId) -> &mut GuildSettings {
+        self.guilds.entry(guild_id.get()).or_default()
+    }
+}
+
+pub struct GuildSettingsKey;
+
+impl TypeMapKey for GuildSettingsKey {
+    type Value = GuildSettingsStore;
+}
+
+pub async fn get_guild_settings(data: &Arc<RwLock<TypeMap>>, guild_id: GuildId) -> GuildSettings {
+    let data = data.read().await;
+    data.get::<GuildSettingsKey>()
+        .expect("Guaranteed to exist in the typemap.")
+        .get(guild_id)
+}
+
+/// Apply `update` to the guild's settings, creating the defaults first if
+/// the guild has none yet, and save them
+pub async fn update_guild_settings<F, R>(
+    data: &Arc<RwLock<TypeMap>>,
+    guild_id: GuildId,
+    update: F,
+) -> R
+where
+    F: FnOnce(&mut GuildSettings) -> R,
+{
+    let mut data = data.write().await;
+    let store = data
+        .get_mut::<GuildSettingsKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    let result = update(store.get_mut(guild_id));
+    let store = store.clone();
+    drop(data);
+
+    // Logged instead of failing since the change still applies until the
+    // bot restarts
+    if let Err(err) = store.save() {
+        error!("Failed to save guild settings: {}", err);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_settings_survive_a_round_trip() {
+        let mut store = GuildSettingsStore::default();
+        let guild_id = GuildId::new(1);
+        store.get_mut(guild_id).limits = QueueLimits {
+            max_track_duration: Some(Duration::from_secs(600)),
+            max_queue_length: None,
+            max_tracks_per_user: Some(3),
+            max_playlist_size: None,
+        };
+        store.get_mut(guild_id).autoplay = true;
+        store.get_mut(guild_id).duplicate_policy = DuplicatePolicy::Reject;
+
+        let json = serde_json::to_string(&store).unwrap();
+        let loaded: GuildSettingsStore = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.get(guild_id).limits, store.get(guild_id).limits);
+        assert!(loaded.get(guild_id).autoplay);
+        assert_eq!(
+            loaded.get(guild_id).duplicate_policy,
+            DuplicatePolicy::Reject
+        );
+    }
+}
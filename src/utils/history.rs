@@ -0,0 +1,227 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    sync::Arc,
+};
+
+use serenity::{
+    all::GuildId,
+    prelude::{RwLock, TypeMap, TypeMapKey},
+};
+
+use crate::utils::{track_utils::TrackMetadata, youtube::video_id_from_url};
+
+/// A track that finished playing, kept so it can be queued again
+#[derive(Clone)]
+pub struct HistoryEntry {
+    pub source_url: String,
+    pub metadata: TrackMetadata,
+}
+
+impl HistoryEntry {
+    /// The entry for a finished track. Only YouTube videos can be queued again
+    /// from their URL, so radio stations, podcasts and attachments are left out.
+    pub fn from_metadata(metadata: &TrackMetadata) -> Option<Self> {
+        let source_url = metadata.source_url.clone()?;
+
+        if metadata.station.is_some() || video_id_from_url(&source_url).is_none() {
+            return None;
+        }
+
+        Some(Self {
+            source_url,
+            metadata: metadata.clone(),
+        })
+    }
+}
+
+/// Bounded list of finished tracks for a guild. The oldest entry is dropped
+/// once the capacity is reached.
+pub struct TrackHistory {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+}
+
+impl TrackHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Remove and return the most recently finished track
+    pub fn pop_last(&mut self) -> Option<HistoryEntry> {
+        self.entries.pop_back()
+    }
+
+    /// Iterate over the remembered tracks, most recent first
+    pub fn iter_recent(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+}
+
+pub struct HistoryKey;
+
+impl TypeMapKey for HistoryKey {
+    type Value = HashMap<GuildId, TrackHistory>;
+}
+
+/// Number of finished tracks remembered per guild.
+/// Set TRACK_HISTORY_SIZE to override the default of 50.
+pub fn history_capacity() -> usize {
+    env::var("TRACK_HISTORY_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(50)
+}
+
+pub async fn record_played(data: &Arc<RwLock<TypeMap>>, guild_id: GuildId, entry: HistoryEntry) {
+    let mut data = data.write().await;
+    let histories = data
+        .get_mut::<HistoryKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    histories
+        .entry(guild_id)
+        .or_insert_with(|| TrackHistory::new(history_capacity()))
+        .push(entry);
+}
+
+pub async fn pop_previous(data: &Arc<RwLock<TypeMap>>, guild_id: GuildId) -> Option<HistoryEntry> {
+    let mut data = data.write().await;
+    let histories = data
+        .get_mut::<HistoryKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    histories.get_mut(&guild_id).and_then(|h| h.pop_last())
+}
+
+/// Clone up to `limit` of the guild's most recently finished tracks,
+/// most recent first.
+pub async fn recent_history(
+    data: &Arc<RwLock<TypeMap>>,
+    guild_id: GuildId,
+    limit: usize,
+) -> Vec<HistoryEntry> {
+    let data = data.read().await;
+    let histories = data
+        .get::<HistoryKey>()
+        .expect("Guaranteed to exist in the typemap.");
+
+    histories
+        .get(&guild_id)
+        .map(|h| h.iter_recent().take(limit).cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Keep the first entry of each track, so a song played twice is only
+/// listed once
+pub fn unique_tracks(entries: Vec<HistoryEntry>) -> Vec<HistoryEntry> {
+    let mut seen = HashSet::new();
+
+    entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.source_url.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> HistoryEntry {
+        HistoryEntry {
+            source_url: format!("https://www.youtube.com/watch?v={}", title),
+            metadata: TrackMetadata {
+                title: title.to_string(),
+                thumbnail_url: None,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_history_pops_most_recent_first() {
+        let mut history = TrackHistory::new(5);
+        history.push(entry("first"));
+        history.push(entry("second"));
+
+        assert_eq!(history.pop_last().unwrap().metadata.title, "second");
+        assert_eq!(history.pop_last().unwrap().metadata.title, "first");
+        assert!(history.pop_last().is_none());
+    }
+
+    #[test]
+    fn test_history_drops_oldest_when_full() {
+        let mut history = TrackHistory::new(2);
+        history.push(entry("one"));
+        history.push(entry("two"));
+        history.push(entry("three"));
+
+        assert_eq!(history.pop_last().unwrap().metadata.title, "three");
+        assert_eq!(history.pop_last().unwrap().metadata.title, "two");
+        assert!(history.pop_last().is_none());
+    }
+
+    #[test]
+    fn test_history_iterates_most_recent_first() {
+        let mut history = TrackHistory::new(5);
+        history.push(entry("one"));
+        history.push(entry("two"));
+        history.push(entry("three"));
+
+        let titles: Vec<&str> = history
+            .iter_recent()
+            .map(|e| e.metadata.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["three", "two", "one"]);
+    }
+
+    #[test]
+    fn test_history_with_zero_capacity_keeps_nothing() {
+        let mut history = TrackHistory::new(0);
+        history.push(entry("ignored"));
+
+        assert!(history.pop_last().is_none());
+    }
+
+    #[test]
+    fn test_history_entry_only_from_youtube_tracks() {
+        let video = TrackMetadata {
+            source_url: Some(String::from("https://youtu.be/dQw4w9WgXcQ")),
+            ..Default::default()
+        };
+        let podcast = TrackMetadata {
+            source_url: Some(String::from("https://example.com/episode.mp3")),
+            ..Default::default()
+        };
+
+        assert!(HistoryEntry::from_metadata(&video).is_some());
+        assert!(HistoryEntry::from_metadata(&podcast).is_none());
+        assert!(HistoryEntry::from_metadata(&TrackMetadata::default()).is_none());
+    }
+
+    #[test]
+    fn test_unique_tracks_keeps_most_recent_play() {
+        let entries = vec![entry("a"), entry("b"), entry("a"), entry("c")];
+
+        let titles: Vec<String> = unique_tracks(entries)
+            .into_iter()
+            .map(|e| e.metadata.title)
+            .collect();
+        assert_eq!(titles, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Small JSON files for data that has to survive a restart.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use tracing::warn;
+
+/// Directory the bot keeps its files in. Set DATA_DIR to override the
+/// default of `data` in the working directory.
+pub fn data_dir() -> PathBuf {
+    env::var("DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("data"))
+}
+
+/// Path of a file inside the data directory
+pub fn data_file(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+/// Read a JSON file, falling back to the default value when it is missing
+/// or can't be parsed
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return T::default(),
+        Err(err) => {
+            warn!("Failed to read {}: {}", path.display(), err);
+            return T::default();
+        }
+    };
+
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        warn!("Failed to parse {}: {}", path.display(), err);
+        T::default()
+    })
+}
+
+/// Write `value` as JSON. The file is replaced in one step so a crash
+/// never leaves it half written.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("poor-jimmy-test-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_file("round_trip.json");
+        let value: HashMap<u64, Vec<String>> = [(1, vec!["a".to_string()])].into();
+
+        save(&path, &value).unwrap();
+
+        assert_eq!(load::<HashMap<u64, Vec<String>>>(&path), value);
+    }
+
+    #[test]
+    fn test_missing_or_invalid_file_loads_default() {
+        let missing = temp_file("missing.json");
+        assert!(load::<Vec<String>>(&missing).is_empty());
+
+        let invalid = temp_file("invalid.json");
+        fs::create_dir_all(invalid.parent().unwrap()).unwrap();
+        fs::write(&invalid, "not json").unwrap();
+        assert!(load::<Vec<String>>(&invalid).is_empty());
+    }
+}
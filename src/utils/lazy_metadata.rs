@@ -0,0 +1,239 @@
+//! Tracks queued with placeholder metadata, such as a search result, so the
+//! user hears back right away. The full metadata is looked up afterwards and
+//! the "Now playing" and `/list` messages are updated once it arrives.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Client as HttpClient;
+use serenity::{
+    all::{ChannelId, GuildId, Http, MessageId},
+    builder::EditMessage,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::{Event, tracks::TrackHandle};
+use tracing::{info, warn};
+
+use crate::{
+    commands::list::refresh_queue_message,
+    handlers::track_play::create_now_playing_embed,
+    utils::{
+        metadata_cache::{MetadataCache, cached_source},
+        sponsorblock::SegmentSkipper,
+        track_utils::{TrackMetadata, track_metadata, try_resolve_metadata},
+        youtube::watch_url,
+    },
+};
+
+#[derive(Default)]
+struct LazyStatus {
+    resolved: Option<TrackMetadata>,
+    announcement: Option<MessageId>,
+}
+
+/// Shared between a track queued with placeholder metadata and the task
+/// looking up the rest
+#[derive(Clone)]
+pub struct LazyMetadata {
+    data: Arc<RwLock<TypeMap>>,
+    http_client: HttpClient,
+    cache: MetadataCache,
+    guild_id: GuildId,
+    status: Arc<Mutex<LazyStatus>>,
+}
+
+impl LazyMetadata {
+    pub fn new(
+        data: Arc<RwLock<TypeMap>>,
+        http_client: HttpClient,
+        cache: MetadataCache,
+        guild_id: GuildId,
+    ) -> Self {
+        Self {
+            data,
+            http_client,
+            cache,
+            guild_id,
+            status: Arc::new(Mutex::new(LazyStatus::default())),
+        }
+    }
+
+    /// The full metadata, once it was looked up
+    pub fn resolved(&self) -> Option<TrackMetadata> {
+        self.status.lock().ok()?.resolved.clone()
+    }
+
+    /// Returns the "Now playing" message to update, if the track already
+    /// started
+    fn set_resolved(&self, metadata: TrackMetadata) -> Option<MessageId> {
+        let mut status = self.status.lock().ok()?;
+        status.resolved = Some(metadata);
+        status.announcement
+    }
+
+    /// Remember the "Now playing" message. Returns whether the full metadata
+    /// arrived in the meantime, so the message can be updated straight away.
+    pub fn set_announcement(&self, message_id: MessageId, shown_resolved: bool) -> bool {
+        let Ok(mut status) = self.status.lock() else {
+            return false;
+        };
+
+        status.announcement = Some(message_id);
+        status.resolved.is_some() && !shown_resolved
+    }
+
+    /// Look up the full metadata of `track` in the background. Segments
+    /// SponsorBlock knows about are skipped from then on.
+    pub fn resolve_in_background(
+        &self,
+        http: Arc<Http>,
+        track: TrackHandle,
+        channel_id: ChannelId,
+    ) {
+        let lazy = self.clone();
+
+        tokio::spawn(async move {
+            let placeholder = track.data::<TrackMetadata>();
+            let Some(source_url) = placeholder.source_url.clone() else {
+                return;
+            };
+
+            let mut source = cached_source(lazy.http_client.clone(), &lazy.cache, source_url);
+            let resolved =
+                match try_resolve_metadata(&lazy.http_client, &mut source, lazy.guild_id).await {
+                    Ok(resolved) => resolved,
+                    Err(err) => {
+                        warn!(
+                            "Failed to look up metadata of '{}', keeping its placeholder: {}",
+                            placeholder.title, err
+                        );
+                        return;
+                    }
+                };
+
+            info!(
+                "Resolved metadata of '{}' in guild {}",
+                resolved.title, lazy.guild_id
+            );
+
+            if !resolved.is_live && !resolved.sponsor_segments.is_empty() {
+                let _ = track.add_event(
+                    Event::Periodic(Duration::from_secs(1), None),
+                    SegmentSkipper {
+                        segments: resolved.sponsor_segments.clone(),
+                    },
+                );
+            }
+
+            if let Some(message_id) = lazy.set_resolved(resolved) {
+                update_announcement(&http, channel_id, message_id, &track).await;
+            }
+
+            refresh_queue_message(&http, &lazy.data, lazy.guild_id, &track).await;
+        });
+    }
+}
+
+/// Show the latest metadata of `track` in its "Now playing" message
+pub async fn update_announcement(
+    http: &Http,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    track: &TrackHandle,
+) {
+    let metadata = track_metadata(track);
+    let embed = create_now_playing_embed(
+        &metadata.title,
+        metadata.thumbnail_url.as_deref().unwrap_or_default(),
+        &metadata,
+    );
+
+    if let Err(err) = channel_id
+        .edit_message(http, message_id, EditMessage::new().embed(embed))
+        .await
+    {
+        warn!("Failed to update now playing message: {}", err);
+    }
+}
+
+/// Placeholder metadata for a YouTube video from what a listing already
+/// showed, completed in the background once queued
+pub fn placeholder_metadata(
+    lazy: LazyMetadata,
+    video_id: &str,
+    title: String,
+    duration: Option<Duration>,
+    thumbnail_url: Option<String>,
+) -> TrackMetadata {
+    TrackMetadata {
+        title,
+        thumbnail_url,
+        duration,
+        source_url: Some(watch_url(video_id)),
+        lazy: Some(lazy),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lazy() -> LazyMetadata {
+        let cache = MetadataCache::open(
+            std::env::temp_dir().join("poor-jimmy-lazy-cache.json"),
+            10,
+            Duration::from_secs(60),
+        );
+
+        LazyMetadata::new(
+            Arc::new(RwLock::new(TypeMap::new())),
+            HttpClient::new(),
+            cache,
+            GuildId::new(1),
+        )
+    }
+
+    #[test]
+    fn test_latest_metadata_keeps_the_request() {
+        let lazy = lazy();
+        let mut placeholder = placeholder_metadata(
+            lazy.clone(),
+            "dQw4w9WgXcQ",
+            String::from("Placeholder"),
+            Some(Duration::from_secs(60)),
+            None,
+        );
+        placeholder.requested_by = Some(serenity::all::UserId::new(7));
+        placeholder.play_start.mark();
+
+        assert_eq!(placeholder.latest().title, "Placeholder");
+
+        lazy.set_resolved(TrackMetadata {
+            title: String::from("Full title"),
+            duration: Some(Duration::from_secs(212)),
+            ..Default::default()
+        });
+
+        let latest = placeholder.latest();
+        assert_eq!(latest.title, "Full title");
+        assert_eq!(latest.duration, Some(Duration::from_secs(212)));
+        assert_eq!(latest.requested_by, placeholder.requested_by);
+        assert_eq!(latest.play_start.get(), placeholder.play_start.get());
+    }
+
+    #[test]
+    fn test_announcement_is_updated_when_resolved_meanwhile() {
+        let lazy = lazy();
+
+        assert!(!lazy.set_announcement(MessageId::new(1), false));
+        assert_eq!(
+            lazy.set_resolved(TrackMetadata::default()),
+            Some(MessageId::new(1))
+        );
+        assert!(lazy.set_announcement(MessageId::new(2), false));
+        assert!(!lazy.set_announcement(MessageId::new(3), true));
+    }
+}
@@ -0,0 +1,424 @@
+//! A local music library: audio files in a directory on the bot's machine,
+//! indexed by their tags so they can be searched and queued.
+
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::{RwLock, TypeMap, TypeMapKey};
+use tracing::{info, warn};
+
+use crate::utils::{
+    audio_file::{AUDIO_EXTENSIONS, file_extension, probe_audio},
+    json_store,
+    track_utils::TrackMetadata,
+};
+
+/// Most names offered by autocomplete, which is Discord's limit
+pub const MAX_SUGGESTIONS: usize = 25;
+
+/// Directory the library is read from. The library is disabled when
+/// LIBRARY_DIR isn't set.
+pub fn library_dir() -> Option<PathBuf> {
+    env::var("LIBRARY_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// How often the library directory is checked for changes. Set
+/// LIBRARY_SCAN_MINUTES to override the default of 5 minutes.
+pub fn scan_interval() -> Duration {
+    let minutes = env::var("LIBRARY_SCAN_MINUTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(5);
+
+    Duration::from_secs(minutes * 60)
+}
+
+/// A playable file in the library
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LibraryTrack {
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub duration: Option<Duration>,
+    /// Modification time and size the tags were read at, so unchanged files
+    /// aren't probed again on the next scan
+    pub modified: u64,
+    pub size: u64,
+}
+
+impl LibraryTrack {
+    /// "Artist - Title", or just the title for untagged files
+    pub fn display_title(&self) -> String {
+        match &self.artist {
+            Some(artist) => format!("{} - {}", artist, self.title),
+            None => self.title.clone(),
+        }
+    }
+
+    pub fn metadata(&self) -> TrackMetadata {
+        TrackMetadata {
+            title: self.display_title(),
+            duration: self.duration,
+            ..Default::default()
+        }
+    }
+
+    fn matches(&self, words: &[String]) -> bool {
+        let haystack = format!(
+            "{} {} {}",
+            self.title,
+            self.artist.as_deref().unwrap_or_default(),
+            self.album.as_deref().unwrap_or_default()
+        )
+        .to_lowercase();
+
+        words.iter().all(|word| haystack.contains(word.as_str()))
+    }
+}
+
+/// Whether `/library play` picks tracks by album or by artist
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LibraryGroup {
+    Album,
+    Artist,
+}
+
+impl LibraryGroup {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "album" => Some(Self::Album),
+            "artist" => Some(Self::Artist),
+            _ => None,
+        }
+    }
+
+    fn of(self, track: &LibraryTrack) -> Option<&str> {
+        match self {
+            Self::Album => track.album.as_deref(),
+            Self::Artist => track.artist.as_deref(),
+        }
+    }
+}
+
+/// Every track found in the library directory, ordered by path
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    pub tracks: Vec<LibraryTrack>,
+}
+
+impl LibraryIndex {
+    fn path() -> PathBuf {
+        json_store::data_file("library.json")
+    }
+
+    pub fn load() -> Self {
+        json_store::load(&Self::path())
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = json_store::save(&Self::path(), self) {
+            warn!("Failed to save library index: {}", err);
+        }
+    }
+
+    /// Index the audio files under `root`. Files that haven't changed since
+    /// they were indexed in `self` keep their entry instead of being read
+    /// again. This does blocking IO.
+    pub fn rescan(&self, root: &Path) -> Self {
+        let mut files = Vec::new();
+        collect_audio_files(root, &mut files);
+        files.sort();
+
+        let indexed: HashMap<&Path, &LibraryTrack> = self
+            .tracks
+            .iter()
+            .map(|track| (track.path.as_path(), track))
+            .collect();
+
+        let tracks = files
+            .into_iter()
+            .filter_map(|path| {
+                let file_info = fs::metadata(&path).ok()?;
+                let size = file_info.len();
+                let modified = file_info
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|time| time.as_secs())
+                    .unwrap_or_default();
+
+                match indexed.get(path.as_path()) {
+                    Some(track) if track.modified == modified && track.size == size => {
+                        Some((*track).clone())
+                    }
+                    _ => read_track(path, modified, size),
+                }
+            })
+            .collect();
+
+        Self { tracks }
+    }
+
+    /// Tracks whose title, artist or album contain every word of `query`
+    pub fn search(&self, query: &str) -> Vec<&LibraryTrack> {
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect();
+
+        self.tracks
+            .iter()
+            .filter(|track| track.matches(&words))
+            .collect()
+    }
+
+    /// Album or artist names containing `query`, for autocomplete
+    pub fn names(&self, group: LibraryGroup, query: &str) -> Vec<String> {
+        let query = query.to_lowercase();
+
+        let mut names: Vec<String> = self
+            .tracks
+            .iter()
+            .filter_map(|track| group.of(track))
+            .filter(|name| name.to_lowercase().contains(&query))
+            .map(String::from)
+            .collect();
+
+        names.sort_by_key(|name| name.to_lowercase());
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        names.truncate(MAX_SUGGESTIONS);
+        names
+    }
+
+    /// The tracks of an album or artist, ignoring case
+    pub fn tracks_in(&self, group: LibraryGroup, name: &str) -> Vec<&LibraryTrack> {
+        self.tracks
+            .iter()
+            .filter(|track| {
+                group
+                    .of(track)
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+            .collect()
+    }
+}
+
+fn collect_audio_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!(
+                "Failed to read library directory {}: {}",
+                dir.display(),
+                err
+            );
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        // Symlinked directories aren't followed, a link back up the tree
+        // would otherwise be scanned forever
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            collect_audio_files(&path, files);
+        } else if extension_of(&path)
+            .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.as_str()))
+        {
+            files.push(path);
+        }
+    }
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(file_extension)
+}
+
+fn read_track(path: PathBuf, modified: u64, size: u64) -> Option<LibraryTrack> {
+    let file = File::open(&path).ok()?;
+    let extension = extension_of(&path);
+
+    let Some(tags) = probe_audio(Box::new(file), extension.as_deref()) else {
+        warn!("Skipping unreadable library file {}", path.display());
+        return None;
+    };
+
+    let file_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Some(LibraryTrack {
+        title: tags.title.unwrap_or(file_name),
+        artist: tags.artist,
+        album: tags.album,
+        duration: tags.duration,
+        modified,
+        size,
+        path,
+    })
+}
+
+/// Rescan the library every `scan_interval`, saving the index whenever
+/// files were added, changed or removed.
+pub async fn watch_library(data: Arc<RwLock<TypeMap>>, root: PathBuf) {
+    let interval = scan_interval();
+
+    loop {
+        let current = {
+            let data = data.read().await;
+            data.get::<LibraryKey>()
+                .cloned()
+                .expect("Guaranteed to exist in the typemap.")
+        };
+
+        let scan_root = root.clone();
+        let scanned = tokio::task::spawn_blocking(move || {
+            let scanned = current.rescan(&scan_root);
+            (scanned != current).then_some(scanned)
+        })
+        .await;
+
+        match scanned {
+            Ok(Some(index)) => {
+                info!("Library index updated, {} tracks", index.tracks.len());
+                index.save();
+
+                let mut data = data.write().await;
+                data.insert::<LibraryKey>(index);
+            }
+            Ok(None) => {}
+            Err(err) => warn!("Library scan failed: {}", err),
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+pub struct LibraryKey;
+
+impl TypeMapKey for LibraryKey {
+    type Value = LibraryIndex;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::audio_file::tests::tagged_wav;
+
+    fn track(title: &str, artist: &str, album: &str) -> LibraryTrack {
+        LibraryTrack {
+            path: PathBuf::from(format!("{}.flac", title)),
+            title: title.to_string(),
+            artist: Some(artist.to_string()),
+            album: Some(album.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn index() -> LibraryIndex {
+        LibraryIndex {
+            tracks: vec![
+                track("Airbag", "Radiohead", "OK Computer"),
+                track("Karma Police", "Radiohead", "OK Computer"),
+                track("Teardrop", "Massive Attack", "Mezzanine"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_search_matches_every_word() {
+        let index = index();
+
+        let titles: Vec<&str> = index
+            .search("radiohead police")
+            .iter()
+            .map(|track| track.title.as_str())
+            .collect();
+
+        assert_eq!(titles, vec!["Karma Police"]);
+        assert_eq!(index.search("MEZZANINE").len(), 1);
+        assert!(index.search("nothing").is_empty());
+    }
+
+    #[test]
+    fn test_names_are_distinct_and_filtered() {
+        let index = index();
+
+        assert_eq!(
+            index.names(LibraryGroup::Artist, ""),
+            vec!["Massive Attack", "Radiohead"]
+        );
+        assert_eq!(
+            index.names(LibraryGroup::Album, "comp"),
+            vec!["OK Computer"]
+        );
+    }
+
+    #[test]
+    fn test_tracks_in_ignores_case() {
+        let index = index();
+
+        assert_eq!(index.tracks_in(LibraryGroup::Album, "ok computer").len(), 2);
+        assert_eq!(
+            index
+                .tracks_in(LibraryGroup::Artist, "massive attack")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_rescan_picks_up_changes() {
+        let root = env::temp_dir().join(format!("poor-jimmy-library-{}", std::process::id()));
+        fs::create_dir_all(root.join("album")).unwrap();
+        fs::write(root.join("album/one.wav"), tagged_wav("One", "Band")).unwrap();
+        fs::write(root.join("notes.txt"), "not audio").unwrap();
+
+        let first = LibraryIndex::default().rescan(&root);
+        assert_eq!(first.tracks.len(), 1);
+        assert_eq!(first.tracks[0].display_title(), "Band - One");
+        assert_eq!(first.tracks[0].duration, Some(Duration::from_secs(1)));
+
+        // Unchanged files keep their entry
+        assert_eq!(first.rescan(&root), first);
+
+        fs::write(root.join("two.wav"), tagged_wav("Two", "Band")).unwrap();
+        fs::remove_file(root.join("album/one.wav")).unwrap();
+
+        let second = first.rescan(&root);
+        let titles: Vec<&str> = second.tracks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Two"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rescan_skips_symlinked_directories() {
+        let root = env::temp_dir().join(format!("poor-jimmy-symlinks-{}", std::process::id()));
+        fs::create_dir_all(root.join("album")).unwrap();
+        fs::write(root.join("album/one.wav"), tagged_wav("One", "Band")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("album/loop")).unwrap();
+
+        let index = LibraryIndex::default().rescan(&root);
+        assert_eq!(index.tracks.len(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
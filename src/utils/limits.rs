@@ -0,0 +1,262 @@
+use std::{env, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use songbird::tracks::TrackQueue;
+
+use crate::utils::{format::format_duration, track_utils::TrackMetadata};
+
+/// Limits on what can be queued in a guild. `None` means unlimited.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueLimits {
+    pub max_track_duration: Option<Duration>,
+    pub max_queue_length: Option<usize>,
+    pub max_tracks_per_user: Option<usize>,
+    pub max_playlist_size: Option<usize>,
+}
+
+impl QueueLimits {
+    /// Read the defaults for every guild from MAX_TRACK_MINUTES,
+    /// MAX_QUEUE_LENGTH, MAX_TRACKS_PER_USER and MAX_PLAYLIST_SIZE.
+    /// Playlists are capped at 50 tracks unless configured otherwise.
+    pub fn from_env() -> Self {
+        Self {
+            max_track_duration: env_limit("MAX_TRACK_MINUTES")
+                .map(|minutes| Duration::from_secs(minutes as u64 * 60)),
+            max_queue_length: env_limit("MAX_QUEUE_LENGTH"),
+            max_tracks_per_user: env_limit("MAX_TRACKS_PER_USER"),
+            max_playlist_size: env::var("MAX_PLAYLIST_SIZE")
+                .map_or(Some(50), |_| env_limit("MAX_PLAYLIST_SIZE")),
+        }
+    }
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+/// Parse a limit from the environment, treating 0 or invalid values as no
+/// limit
+fn env_limit(name: &str) -> Option<usize> {
+    env::var(name)
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+}
+
+/// The limit a request ran into
+#[derive(Debug, PartialEq)]
+pub enum LimitExceeded {
+    TrackTooLong { duration: Duration, max: Duration },
+    QueueFull { max: usize },
+    UserQueueFull { max: usize },
+}
+
+impl LimitExceeded {
+    /// Message explaining which limit was hit
+    pub fn description(&self) -> String {
+        match self {
+            LimitExceeded::TrackTooLong { duration, max } => format!(
+                "**Track too long!** It is {} but this server allows up to {} per track",
+                format_duration(*duration),
+                format_duration(*max)
+            ),
+            LimitExceeded::QueueFull { max } => format!(
+                "**Queue is full!** This server allows up to {} tracks in the queue",
+                max
+            ),
+            LimitExceeded::UserQueueFull { max } => format!(
+                "**You have too many tracks queued!** This server allows up to {} tracks per user",
+                max
+            ),
+        }
+    }
+}
+
+/// Check whether a resolved track can be added to `queue`
+pub fn check_queue_limits(
+    limits: &QueueLimits,
+    queue: &TrackQueue,
+    metadata: &TrackMetadata,
+) -> Result<(), LimitExceeded> {
+    let queued = queue.current_queue();
+    let user_queued = metadata.requested_by.map_or(0, |user_id| {
+        count_user_tracks(
+            queued
+                .iter()
+                .map(|track| track.data::<TrackMetadata>().requested_by),
+            user_id,
+        )
+    });
+
+    check_track(limits, queued.len(), user_queued, metadata)
+}
+
+fn count_user_tracks(requesters: impl Iterator<Item = Option<UserId>>, user_id: UserId) -> usize {
+    requesters
+        .filter(|requester| *requester == Some(user_id))
+        .count()
+}
+
+fn check_track(
+    limits: &QueueLimits,
+    queue_length: usize,
+    user_queued: usize,
+    metadata: &TrackMetadata,
+) -> Result<(), LimitExceeded> {
+    // Live streams have no length to compare against
+    if let (Some(max), Some(duration)) = (limits.max_track_duration, metadata.duration)
+        && !metadata.is_live
+        && duration > max
+    {
+        return Err(LimitExceeded::TrackTooLong { duration, max });
+    }
+
+    if let Some(max) = limits.max_queue_length
+        && queue_length >= max
+    {
+        return Err(LimitExceeded::QueueFull { max });
+    }
+
+    if let Some(max) = limits.max_tracks_per_user
+        && metadata.requested_by.is_some()
+        && user_queued >= max
+    {
+        return Err(LimitExceeded::UserQueueFull { max });
+    }
+
+    Ok(())
+}
+
+/// How many of a list's `size` tracks a bulk enqueue takes, the rest are
+/// left out
+pub fn playlist_size_allowed(limits: &QueueLimits, size: usize) -> usize {
+    limits.max_playlist_size.map_or(size, |max| size.min(max))
+}
+
+/// One line per limit for showing the current configuration
+pub fn describe_limits(limits: &QueueLimits) -> String {
+    fn or_unlimited(value: Option<String>) -> String {
+        value.unwrap_or_else(|| String::from("unlimited"))
+    }
+
+    format!(
+        "**Max track length:** {}\n**Max queue length:** {}\n**Max tracks per user:** {}\n**Max playlist size:** {}",
+        or_unlimited(limits.max_track_duration.map(format_duration)),
+        or_unlimited(limits.max_queue_length.map(|n| n.to_string())),
+        or_unlimited(limits.max_tracks_per_user.map(|n| n.to_string())),
+        or_unlimited(limits.max_playlist_size.map(|n| n.to_string())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> QueueLimits {
+        QueueLimits {
+            max_track_duration: Some(Duration::from_secs(600)),
+            max_queue_length: Some(10),
+            max_tracks_per_user: Some(3),
+            max_playlist_size: Some(20),
+        }
+    }
+
+    fn track(seconds: u64) -> TrackMetadata {
+        TrackMetadata {
+            title: "Song".to_string(),
+            duration: Some(Duration::from_secs(seconds)),
+            requested_by: Some(UserId::new(1)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_track_within_limits() {
+        assert_eq!(check_track(&limits(), 0, 0, &track(300)), Ok(()));
+    }
+
+    #[test]
+    fn test_track_too_long() {
+        assert_eq!(
+            check_track(&limits(), 0, 0, &track(36000)),
+            Err(LimitExceeded::TrackTooLong {
+                duration: Duration::from_secs(36000),
+                max: Duration::from_secs(600),
+            })
+        );
+    }
+
+    #[test]
+    fn test_live_streams_skip_duration_limit() {
+        let mut live = track(36000);
+        live.is_live = true;
+
+        assert_eq!(check_track(&limits(), 0, 0, &live), Ok(()));
+    }
+
+    #[test]
+    fn test_queue_full() {
+        assert_eq!(
+            check_track(&limits(), 10, 0, &track(60)),
+            Err(LimitExceeded::QueueFull { max: 10 })
+        );
+    }
+
+    #[test]
+    fn test_user_queue_full() {
+        assert_eq!(
+            check_track(&limits(), 5, 3, &track(60)),
+            Err(LimitExceeded::UserQueueFull { max: 3 })
+        );
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let unlimited = QueueLimits {
+            max_track_duration: None,
+            max_queue_length: None,
+            max_tracks_per_user: None,
+            max_playlist_size: None,
+        };
+
+        assert_eq!(check_track(&unlimited, 1000, 1000, &track(36000)), Ok(()));
+        assert_eq!(playlist_size_allowed(&unlimited, 1000), 1000);
+    }
+
+    #[test]
+    fn test_playlist_size_allowed() {
+        assert_eq!(playlist_size_allowed(&limits(), 5), 5);
+        assert_eq!(playlist_size_allowed(&limits(), 20), 20);
+        assert_eq!(playlist_size_allowed(&limits(), 500), 20);
+    }
+
+    #[test]
+    fn test_count_user_tracks() {
+        let requesters = vec![
+            Some(UserId::new(1)),
+            Some(UserId::new(2)),
+            None,
+            Some(UserId::new(1)),
+        ];
+
+        assert_eq!(count_user_tracks(requesters.into_iter(), UserId::new(1)), 2);
+    }
+
+    #[test]
+    fn test_limit_descriptions_name_the_limit() {
+        assert!(
+            LimitExceeded::QueueFull { max: 10 }
+                .description()
+                .contains("Queue is full")
+        );
+        assert!(
+            LimitExceeded::UserQueueFull { max: 3 }
+                .description()
+                .contains("up to 3 tracks per user")
+        );
+    }
+}
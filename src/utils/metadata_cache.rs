@@ -0,0 +1,501 @@
+//! Metadata of YouTube videos kept on disk, so a track queued again doesn't
+//! wait on another yt-dlp run to learn its title, thumbnail and duration.
+
+use std::{
+    collections::HashMap,
+    env,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    prelude::{RwLock, TypeMap, TypeMapKey},
+};
+use songbird::input::{
+    AudioStream, AudioStreamError, AuxMetadata, Compose, Input, YoutubeDl, core::io::MediaSource,
+};
+use tracing::{debug, error};
+
+use crate::utils::{
+    json_store::{self, data_file},
+    stats::unix_now,
+    youtube::video_id_from_url,
+};
+
+const CACHE_FILE: &str = "metadata_cache.json";
+/// How often changes to the cache are written to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most videos kept, the least recently used are forgotten first.
+/// Set METADATA_CACHE_SIZE to override the default of 2000.
+pub fn metadata_cache_size() -> usize {
+    env::var("METADATA_CACHE_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|size| *size > 0)
+        .unwrap_or(2000)
+}
+
+/// How long a video's metadata is trusted before yt-dlp is asked again.
+/// Set METADATA_CACHE_TTL_HOURS to override the default of a week.
+pub fn metadata_cache_ttl() -> Duration {
+    let hours = env::var("METADATA_CACHE_TTL_HOURS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(24 * 7);
+
+    Duration::from_secs(hours * 60 * 60)
+}
+
+/// The parts of `AuxMetadata` worth keeping
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct CachedMetadata {
+    title: Option<String>,
+    #[serde(default)]
+    artist: Option<String>,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    duration_secs: Option<u64>,
+    #[serde(default)]
+    source_url: Option<String>,
+    #[serde(default)]
+    thumbnail: Option<String>,
+    /// Unix time the metadata was looked up
+    cached_at: u64,
+    /// Higher for entries read more recently
+    last_used: u64,
+}
+
+impl CachedMetadata {
+    fn aux_metadata(&self) -> AuxMetadata {
+        AuxMetadata {
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            channel: self.channel.clone(),
+            date: self.date.clone(),
+            duration: self.duration_secs.map(Duration::from_secs),
+            source_url: self.source_url.clone(),
+            thumbnail: self.thumbnail.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct StoredCache {
+    entries: HashMap<String, CachedMetadata>,
+    /// Bumped on every use to order entries by recency
+    #[serde(default)]
+    tick: u64,
+}
+
+/// How well the cache is doing, for diagnostics
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheCounters {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheCounters {
+    pub fn describe(&self) -> String {
+        let rate = (self.hits * 100)
+            .checked_div(self.hits + self.misses)
+            .unwrap_or_default();
+
+        format!(
+            "{} videos cached, {} hits and {} misses ({}% hit rate)",
+            self.entries, self.hits, self.misses, rate
+        )
+    }
+}
+
+struct CacheState {
+    path: PathBuf,
+    capacity: usize,
+    ttl: Duration,
+    stored: StoredCache,
+    /// Set when entries changed since the file was last written
+    unsaved: bool,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn is_fresh(&self, entry: &CachedMetadata, now: u64) -> bool {
+        now.saturating_sub(entry.cached_at) < self.ttl.as_secs()
+    }
+}
+
+/// LRU cache of video metadata keyed by YouTube video id, shared by every
+/// source that looks metadata up
+#[derive(Clone)]
+pub struct MetadataCache(Arc<Mutex<CacheState>>);
+
+impl MetadataCache {
+    pub fn load() -> Self {
+        Self::open(
+            data_file(CACHE_FILE),
+            metadata_cache_size(),
+            metadata_cache_ttl(),
+        )
+    }
+
+    pub fn open(path: PathBuf, capacity: usize, ttl: Duration) -> Self {
+        let stored = json_store::load(&path);
+
+        Self(Arc::new(Mutex::new(CacheState {
+            path,
+            capacity: capacity.max(1),
+            ttl,
+            stored,
+            unsaved: false,
+            hits: 0,
+            misses: 0,
+        })))
+    }
+
+    /// A lookup that panicked while holding the lock leaves at worst one
+    /// odd entry behind, so carry on with the cache rather than missing on
+    /// every lookup from then on
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        self.0.lock().unwrap_or_else(|poisoned| {
+            error!("Metadata cache lock was poisoned, recovering it");
+            self.0.clear_poison();
+            poisoned.into_inner()
+        })
+    }
+
+    /// The metadata of `video_id` if it is cached and still fresh, counted
+    /// as a hit or a miss
+    pub fn get(&self, video_id: &str) -> Option<AuxMetadata> {
+        self.get_at(video_id, unix_now())
+    }
+
+    fn get_at(&self, video_id: &str, now: u64) -> Option<AuxMetadata> {
+        let mut state = self.state();
+        let state = &mut *state;
+
+        let fresh = state
+            .stored
+            .entries
+            .get(video_id)
+            .map(|entry| state.is_fresh(entry, now));
+
+        match fresh {
+            Some(true) => {
+                state.hits += 1;
+                state.stored.tick += 1;
+
+                let entry = state.stored.entries.get_mut(video_id)?;
+                entry.last_used = state.stored.tick;
+                Some(entry.aux_metadata())
+            }
+            Some(false) => {
+                state.misses += 1;
+                state.stored.entries.remove(video_id);
+                state.unsaved = true;
+                None
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Same as `get` without counting the lookup or marking the entry used
+    pub fn peek(&self, video_id: &str) -> Option<AuxMetadata> {
+        let state = self.state();
+        let entry = state.stored.entries.get(video_id)?;
+
+        state
+            .is_fresh(entry, unix_now())
+            .then(|| entry.aux_metadata())
+    }
+
+    /// Remember the metadata of `video_id`, forgetting the least recently
+    /// used video when the cache is full
+    pub fn insert(&self, video_id: &str, metadata: &AuxMetadata) {
+        self.insert_at(video_id, metadata, unix_now());
+    }
+
+    fn insert_at(&self, video_id: &str, metadata: &AuxMetadata, now: u64) {
+        let mut state = self.state();
+
+        state.stored.tick += 1;
+        let last_used = state.stored.tick;
+
+        state.stored.entries.insert(
+            video_id.to_string(),
+            CachedMetadata {
+                title: metadata.title.clone(),
+                artist: metadata.artist.clone(),
+                channel: metadata.channel.clone(),
+                date: metadata.date.clone(),
+                duration_secs: metadata.duration.map(|duration| duration.as_secs()),
+                source_url: metadata.source_url.clone(),
+                thumbnail: metadata.thumbnail.clone(),
+                cached_at: now,
+                last_used,
+            },
+        );
+
+        while state.stored.entries.len() > state.capacity {
+            let Some(oldest) = state
+                .stored
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(video_id, _)| video_id.clone())
+            else {
+                break;
+            };
+
+            state.stored.entries.remove(&oldest);
+        }
+
+        state.unsaved = true;
+    }
+
+    pub fn counters(&self) -> CacheCounters {
+        let state = self.state();
+
+        CacheCounters {
+            entries: state.stored.entries.len(),
+            hits: state.hits,
+            misses: state.misses,
+        }
+    }
+
+    /// Write the cache out if it changed since the last save. The entries
+    /// are copied so the lock isn't held while the file is written.
+    fn save_unsaved(&self) {
+        let (path, stored) = {
+            let mut state = self.state();
+            if !std::mem::take(&mut state.unsaved) {
+                return;
+            }
+            (state.path.clone(), state.stored.clone())
+        };
+
+        if let Err(err) = json_store::save(&path, &stored) {
+            error!("Failed to save metadata cache: {}", err);
+        }
+    }
+}
+
+/// Write changes to the cache out every `SAVE_INTERVAL`
+pub async fn save_metadata_cache(data: Arc<RwLock<TypeMap>>) {
+    loop {
+        tokio::time::sleep(SAVE_INTERVAL).await;
+        flush_metadata_cache(&data).await;
+    }
+}
+
+/// Write the cache out if it changed, on a blocking thread so lookups never
+/// wait on the disk
+pub async fn flush_metadata_cache(data: &Arc<RwLock<TypeMap>>) {
+    let cache = get_metadata_cache(data).await;
+
+    if let Err(err) = tokio::task::spawn_blocking(move || cache.save_unsaved()).await {
+        error!("Failed to save metadata cache: {}", err);
+    }
+}
+
+pub struct MetadataCacheKey;
+
+impl TypeMapKey for MetadataCacheKey {
+    type Value = MetadataCache;
+}
+
+pub async fn get_metadata_cache(data: &Arc<RwLock<TypeMap>>) -> MetadataCache {
+    let data = data.read().await;
+    data.get::<MetadataCacheKey>()
+        .cloned()
+        .expect("Guaranteed to exist in the typemap.")
+}
+
+/// A yt-dlp source that reads its metadata from the cache when it can and
+/// stores what yt-dlp finds otherwise
+struct CachedSource {
+    inner: YoutubeDl<'static>,
+    /// Known up front for YouTube links, searches are only cached once found
+    video_id: Option<String>,
+    cache: MetadataCache,
+}
+
+#[async_trait]
+impl Compose for CachedSource {
+    fn create(&mut self) -> Result<AudioStream<Box<dyn MediaSource>>, AudioStreamError> {
+        self.inner.create()
+    }
+
+    async fn create_async(
+        &mut self,
+    ) -> Result<AudioStream<Box<dyn MediaSource>>, AudioStreamError> {
+        self.inner.create_async().await
+    }
+
+    fn should_create_async(&self) -> bool {
+        self.inner.should_create_async()
+    }
+
+    async fn aux_metadata(&mut self) -> Result<AuxMetadata, AudioStreamError> {
+        if let Some(metadata) = self
+            .video_id
+            .as_deref()
+            .and_then(|video_id| self.cache.get(video_id))
+        {
+            debug!("Metadata cache hit for {:?}", self.video_id);
+            return Ok(metadata);
+        }
+
+        let metadata = self.inner.aux_metadata().await?;
+
+        if let Some(video_id) = metadata.source_url.as_deref().and_then(video_id_from_url) {
+            self.cache.insert(&video_id, &metadata);
+        }
+
+        Ok(metadata)
+    }
+}
+
+/// A source for a link yt-dlp can play, with its metadata cached
+pub fn cached_source(http_client: HttpClient, cache: &MetadataCache, url: String) -> Input {
+    Input::Lazy(Box::new(CachedSource {
+        video_id: video_id_from_url(&url),
+        inner: YoutubeDl::new(http_client, url),
+        cache: cache.clone(),
+    }))
+}
+
+/// A source for the first YouTube result of `query`, whose metadata is
+/// cached once found
+pub fn cached_search_source(
+    http_client: HttpClient,
+    cache: &MetadataCache,
+    query: String,
+) -> Input {
+    Input::Lazy(Box::new(CachedSource {
+        video_id: None,
+        inner: YoutubeDl::new_search(http_client, query),
+        cache: cache.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = env::temp_dir()
+            .join(format!("poor-jimmy-test-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn metadata(title: &str) -> AuxMetadata {
+        AuxMetadata {
+            title: Some(title.to_string()),
+            duration: Some(Duration::from_secs(212)),
+            source_url: Some(String::from("https://www.youtube.com/watch?v=dQw4w9WgXcQ")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_hits_and_misses_are_counted() {
+        let cache = MetadataCache::open(
+            temp_file("cache_counters.json"),
+            10,
+            Duration::from_secs(60),
+        );
+
+        assert!(cache.get("dQw4w9WgXcQ").is_none());
+        cache.insert("dQw4w9WgXcQ", &metadata("Song"));
+
+        let cached = cache.get("dQw4w9WgXcQ").unwrap();
+        assert_eq!(cached.title.as_deref(), Some("Song"));
+        assert_eq!(cached.duration, Some(Duration::from_secs(212)));
+
+        assert_eq!(
+            cache.counters(),
+            CacheCounters {
+                entries: 1,
+                hits: 1,
+                misses: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = MetadataCache::open(temp_file("cache_lru.json"), 2, Duration::from_secs(60));
+
+        cache.insert("one", &metadata("One"));
+        cache.insert("two", &metadata("Two"));
+        assert!(cache.get("one").is_some());
+        cache.insert("three", &metadata("Three"));
+
+        assert!(cache.peek("one").is_some());
+        assert!(cache.peek("two").is_none());
+        assert!(cache.peek("three").is_some());
+    }
+
+    #[test]
+    fn test_expired_entries_are_dropped() {
+        let cache = MetadataCache::open(temp_file("cache_ttl.json"), 10, Duration::from_secs(60));
+
+        cache.insert_at("dQw4w9WgXcQ", &metadata("Song"), 1_000);
+
+        assert!(cache.get_at("dQw4w9WgXcQ", 1_059).is_some());
+        assert!(cache.get_at("dQw4w9WgXcQ", 1_060).is_none());
+        assert_eq!(cache.counters().entries, 0);
+    }
+
+    #[test]
+    fn test_survives_a_restart() {
+        let path = temp_file("cache_restart.json");
+        let cache = MetadataCache::open(path.clone(), 10, Duration::from_secs(60));
+        cache.insert("dQw4w9WgXcQ", &metadata("Song"));
+
+        // Nothing is written until the next save
+        assert!(!path.exists());
+        cache.save_unsaved();
+
+        let reopened = MetadataCache::open(path, 10, Duration::from_secs(60));
+        assert_eq!(
+            reopened
+                .get("dQw4w9WgXcQ")
+                .and_then(|metadata| metadata.title),
+            Some(String::from("Song"))
+        );
+    }
+
+    #[test]
+    fn test_recovers_from_a_poisoned_lock() {
+        let cache =
+            MetadataCache::open(temp_file("cache_poison.json"), 10, Duration::from_secs(60));
+        cache.insert("dQw4w9WgXcQ", &metadata("Song"));
+
+        let poisoner = cache.clone();
+        let _ = std::thread::spawn(move || {
+            let _state = poisoner.0.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+
+        assert!(cache.0.is_poisoned());
+        assert!(cache.get("dQw4w9WgXcQ").is_some());
+        assert!(!cache.0.is_poisoned());
+    }
+}
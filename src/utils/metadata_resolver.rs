@@ -0,0 +1,259 @@
+//! Resolving the metadata of many tracks at once for bulk enqueues, so a
+//! playlist doesn't wait on one yt-dlp run after another.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+};
+
+use reqwest::Client as HttpClient;
+use serenity::all::GuildId;
+use songbird::input::{AuxMetadataError, Input};
+use tokio::task::{Id, JoinSet};
+use tracing::error;
+
+use crate::utils::track_utils::{TrackMetadata, try_resolve_metadata};
+
+/// Metadata lookups run at the same time during a bulk enqueue.
+/// Set METADATA_WORKERS to override the default of 4.
+pub fn metadata_workers() -> usize {
+    env::var("METADATA_WORKERS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|workers| *workers > 0)
+        .unwrap_or(4)
+}
+
+/// A source and the outcome of looking up its metadata
+pub struct ResolvedSource {
+    /// Position of the source in the list given to the resolver. Sources
+    /// whose lookup panicked are skipped, so callers pair results with
+    /// their own lists through this.
+    pub index: usize,
+    pub source: Input,
+    pub metadata: Result<TrackMetadata, AuxMetadataError>,
+}
+
+/// Resolves the metadata of a list of sources with a bounded pool of
+/// workers, handing them back in their original order.
+///
+/// Only a few sources past the one being waited for are resolved ahead, and
+/// dropping the resolver aborts the lookups still running, so callers can
+/// stop early.
+pub struct MetadataResolver {
+    http_client: HttpClient,
+    guild_id: GuildId,
+    workers: usize,
+    pending: std::iter::Enumerate<std::vec::IntoIter<Input>>,
+    running: JoinSet<ResolvedSource>,
+    running_indexes: HashMap<Id, usize>,
+    /// Resolved sources waiting for those before them, `None` when the
+    /// lookup failed outright
+    finished: BTreeMap<usize, Option<ResolvedSource>>,
+    next: usize,
+}
+
+impl MetadataResolver {
+    pub fn new(http_client: HttpClient, guild_id: GuildId, sources: Vec<Input>) -> Self {
+        Self::with_workers(http_client, guild_id, sources, metadata_workers())
+    }
+
+    pub fn with_workers(
+        http_client: HttpClient,
+        guild_id: GuildId,
+        sources: Vec<Input>,
+        workers: usize,
+    ) -> Self {
+        let mut resolver = Self {
+            http_client,
+            guild_id,
+            workers: workers.max(1),
+            pending: sources.into_iter().enumerate(),
+            running: JoinSet::new(),
+            running_indexes: HashMap::new(),
+            finished: BTreeMap::new(),
+            next: 0,
+        };
+
+        resolver.fill();
+        resolver
+    }
+
+    /// The next source in order, once its metadata is known. Returns `None`
+    /// when every source was handed out.
+    pub async fn next(&mut self) -> Option<ResolvedSource> {
+        loop {
+            if let Some(resolved) = self.finished.remove(&self.next) {
+                self.next += 1;
+                self.fill();
+
+                match resolved {
+                    Some(resolved) => return Some(resolved),
+                    None => continue,
+                }
+            }
+
+            let (index, resolved) = match self.running.join_next_with_id().await? {
+                Ok((id, resolved)) => (self.running_indexes.remove(&id), Some(resolved)),
+                Err(err) => {
+                    error!("Metadata lookup task failed: {}", err);
+                    (self.running_indexes.remove(&err.id()), None)
+                }
+            };
+
+            if let Some(index) = index {
+                self.finished.insert(index, resolved);
+            }
+
+            self.fill();
+        }
+    }
+
+    /// Start lookups until the workers are busy, keeping no more than twice
+    /// as many sources resolved ahead of the one being waited for
+    fn fill(&mut self) {
+        while self.running.len() < self.workers
+            && self.running.len() + self.finished.len() < self.workers * 2
+        {
+            let Some((index, mut source)) = self.pending.next() else {
+                return;
+            };
+
+            let http_client = self.http_client.clone();
+            let guild_id = self.guild_id;
+
+            let handle = self.running.spawn(async move {
+                let metadata = try_resolve_metadata(&http_client, &mut source, guild_id).await;
+                ResolvedSource {
+                    index,
+                    source,
+                    metadata,
+                }
+            });
+
+            self.running_indexes.insert(handle.id(), index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serenity::async_trait;
+    use songbird::input::{
+        AudioStream, AudioStreamError, AuxMetadata, Compose, core::io::MediaSource,
+    };
+
+    use super::*;
+
+    /// A source whose metadata takes `delay` to arrive, or never does. The
+    /// title "panic" makes the lookup panic.
+    struct SlowSource {
+        title: Option<&'static str>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Compose for SlowSource {
+        fn create(&mut self) -> Result<AudioStream<Box<dyn MediaSource>>, AudioStreamError> {
+            Err(AudioStreamError::Unsupported)
+        }
+
+        async fn create_async(
+            &mut self,
+        ) -> Result<AudioStream<Box<dyn MediaSource>>, AudioStreamError> {
+            Err(AudioStreamError::Unsupported)
+        }
+
+        fn should_create_async(&self) -> bool {
+            true
+        }
+
+        async fn aux_metadata(&mut self) -> Result<AuxMetadata, AudioStreamError> {
+            tokio::time::sleep(self.delay).await;
+
+            if self.title == Some("panic") {
+                panic!("lookup panicked");
+            }
+
+            match self.title {
+                Some(title) => Ok(AuxMetadata {
+                    title: Some(title.to_string()),
+                    ..Default::default()
+                }),
+                None => Err(AudioStreamError::Unsupported),
+            }
+        }
+    }
+
+    fn source(title: Option<&'static str>, delay_ms: u64) -> Input {
+        Input::Lazy(Box::new(SlowSource {
+            title,
+            delay: Duration::from_millis(delay_ms),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_sources_come_back_in_order() {
+        // Later sources finish first
+        let sources = vec![
+            source(Some("one"), 40),
+            source(Some("two"), 30),
+            source(None, 20),
+            source(Some("four"), 10),
+            source(Some("five"), 0),
+        ];
+
+        let mut resolver =
+            MetadataResolver::with_workers(HttpClient::new(), GuildId::new(1), sources, 2);
+        let mut titles = Vec::new();
+
+        while let Some(resolved) = resolver.next().await {
+            titles.push(
+                resolved
+                    .metadata
+                    .map(|metadata| metadata.title)
+                    .unwrap_or_else(|_| String::from("unavailable")),
+            );
+        }
+
+        assert_eq!(titles, vec!["one", "two", "unavailable", "four", "five"]);
+    }
+
+    #[tokio::test]
+    async fn test_panicked_lookups_are_skipped_with_their_index() {
+        let sources = vec![
+            source(Some("one"), 0),
+            source(Some("panic"), 0),
+            source(Some("three"), 0),
+        ];
+
+        let mut resolver =
+            MetadataResolver::with_workers(HttpClient::new(), GuildId::new(1), sources, 2);
+        let mut indexes = Vec::new();
+
+        while let Some(resolved) = resolver.next().await {
+            indexes.push(resolved.index);
+        }
+
+        assert_eq!(indexes, vec![0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_lookups_run_concurrently() {
+        let sources = (0..8).map(|_| source(Some("song"), 100)).collect();
+
+        let started = tokio::time::Instant::now();
+        let mut resolver =
+            MetadataResolver::with_workers(HttpClient::new(), GuildId::new(1), sources, 8);
+        let mut count = 0;
+
+        while resolver.next().await.is_some() {
+            count += 1;
+        }
+
+        assert_eq!(count, 8);
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+}
@@ -1,4 +1,35 @@
+pub mod audio_file;
+pub mod autoplay;
+pub mod chapters;
+pub mod dj;
+pub mod duplicates;
+pub mod enqueue_summary;
+pub mod favorites;
 pub mod format;
+pub mod guild_settings;
+pub mod history;
+pub mod json_store;
+pub mod lazy_metadata;
+pub mod library;
+pub mod limits;
+pub mod metadata_cache;
+pub mod metadata_resolver;
+pub mod music_links;
+pub mod options;
+pub mod playlist_file;
+pub mod podcast;
+pub mod queue_position;
+pub mod radio;
 pub mod response;
+pub mod saved_playlists;
+pub mod search_session;
+pub mod sponsorblock;
+pub mod stats;
+#[cfg(test)]
+pub mod test_server;
 pub mod track_utils;
 pub mod type_map;
+pub mod xml;
+pub mod youtube;
+pub mod youtube_playlist;
+pub mod youtube_search;
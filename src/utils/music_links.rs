@@ -0,0 +1,294 @@
+//! Spotify and Apple Music links. Their tracks can't be streamed, so the
+//! title and artists of each one are looked up through a metadata endpoint
+//! and searched for on YouTube instead.
+
+use std::{env, fmt, time::Duration};
+
+use reqwest::Client as HttpClient;
+use serde::Deserialize;
+use url::Url;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MusicService {
+    Spotify,
+    AppleMusic,
+}
+
+impl MusicService {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spotify => "Spotify",
+            Self::AppleMusic => "Apple Music",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MusicLinkKind {
+    Track,
+    Album,
+    Playlist,
+}
+
+impl MusicLinkKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Track => "track",
+            Self::Album => "album",
+            Self::Playlist => "playlist",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MusicLink {
+    pub service: MusicService,
+    pub kind: MusicLinkKind,
+    pub url: String,
+}
+
+/// Recognise a Spotify or Apple Music track, album or playlist link
+pub fn parse_music_link(link: &str) -> Option<MusicLink> {
+    let link = link.trim();
+
+    // Spotify's app hands out URIs like spotify:track:<id>
+    if let Some(rest) = link.strip_prefix("spotify:") {
+        let (kind, id) = rest.split_once(':')?;
+        return Some(MusicLink {
+            service: MusicService::Spotify,
+            kind: spotify_kind(kind)?,
+            url: format!("https://open.spotify.com/{}/{}", kind, id),
+        });
+    }
+
+    let url = Url::parse(link).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    let (service, kind) = match url.host_str()? {
+        "open.spotify.com" => {
+            // Localised links start with a segment like "intl-de"
+            let kind = segments
+                .iter()
+                .find(|segment| !segment.starts_with("intl-"))?;
+            (MusicService::Spotify, spotify_kind(kind)?)
+        }
+        "music.apple.com" => {
+            // Paths look like /us/album/<name>/<id>, and ?i=<id> picks one
+            // song of the album
+            let kind = match segments.get(1).copied()? {
+                "song" => MusicLinkKind::Track,
+                "album" if url.query_pairs().any(|(key, _)| key == "i") => MusicLinkKind::Track,
+                "album" => MusicLinkKind::Album,
+                "playlist" => MusicLinkKind::Playlist,
+                _ => return None,
+            };
+            (MusicService::AppleMusic, kind)
+        }
+        _ => return None,
+    };
+
+    Some(MusicLink {
+        service,
+        kind,
+        url: link.to_string(),
+    })
+}
+
+fn spotify_kind(kind: &str) -> Option<MusicLinkKind> {
+    match kind {
+        "track" => Some(MusicLinkKind::Track),
+        "album" => Some(MusicLinkKind::Album),
+        "playlist" => Some(MusicLinkKind::Playlist),
+        _ => None,
+    }
+}
+
+/// Endpoint that lists the tracks behind a music link. Set
+/// LINK_METADATA_URL to enable Spotify and Apple Music links.
+pub fn metadata_endpoint() -> Option<String> {
+    env::var("LINK_METADATA_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct LinkedTrack {
+    pub title: String,
+    #[serde(default)]
+    pub artists: Vec<String>,
+}
+
+impl LinkedTrack {
+    /// "Artist - Title", used both to search YouTube and to report tracks
+    /// that couldn't be found
+    pub fn search_query(&self) -> String {
+        if self.artists.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artists.join(", "), self.title)
+        }
+    }
+}
+
+/// The tracks of a music link as returned by the metadata endpoint
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct LinkedTracks {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub tracks: Vec<LinkedTrack>,
+}
+
+#[derive(Debug)]
+pub enum LinkError {
+    NotConfigured,
+    InvalidResponse,
+    NoTracks,
+    Request(reqwest::Error),
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::NotConfigured => {
+                write!(f, "Spotify and Apple Music links aren't set up on this bot")
+            }
+            LinkError::InvalidResponse => {
+                write!(f, "The link lookup service sent back something unexpected")
+            }
+            LinkError::NoTracks => write!(f, "That link has no tracks"),
+            LinkError::Request(err) => write!(f, "Couldn't look up that link: {}", err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for LinkError {
+    fn from(err: reqwest::Error) -> Self {
+        LinkError::Request(err)
+    }
+}
+
+/// Ask the metadata endpoint for the tracks behind `link`. The endpoint is
+/// called as `GET <endpoint>?url=<link>` and answers with
+/// `{"name": ..., "tracks": [{"title": ..., "artists": [...]}]}`.
+pub async fn fetch_linked_tracks(
+    client: &HttpClient,
+    endpoint: &str,
+    link: &MusicLink,
+) -> Result<LinkedTracks, LinkError> {
+    let body = client
+        .get(endpoint)
+        .query(&[("url", link.url.as_str())])
+        // Long playlists take the service a while to look up
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let linked: LinkedTracks =
+        serde_json::from_str(&body).map_err(|_| LinkError::InvalidResponse)?;
+
+    let tracks: Vec<LinkedTrack> = linked
+        .tracks
+        .into_iter()
+        .filter(|track| !track.title.trim().is_empty())
+        .collect();
+
+    if tracks.is_empty() {
+        return Err(LinkError::NoTracks);
+    }
+
+    Ok(LinkedTracks {
+        name: linked.name,
+        tracks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server;
+
+    fn kind_of(link: &str) -> Option<(MusicService, MusicLinkKind)> {
+        parse_music_link(link).map(|link| (link.service, link.kind))
+    }
+
+    #[test]
+    fn test_parse_spotify_links() {
+        assert_eq!(
+            kind_of("https://open.spotify.com/track/4cOdK2wGLETKBW3PvgPWqT?si=abc"),
+            Some((MusicService::Spotify, MusicLinkKind::Track))
+        );
+        assert_eq!(
+            kind_of("https://open.spotify.com/intl-de/album/1DFixLWuPkv3KT3TnV35m3"),
+            Some((MusicService::Spotify, MusicLinkKind::Album))
+        );
+        assert_eq!(
+            parse_music_link("spotify:playlist:37i9dQZF1DXcBWIGoYBM5M").map(|link| link.url),
+            Some(String::from(
+                "https://open.spotify.com/playlist/37i9dQZF1DXcBWIGoYBM5M"
+            ))
+        );
+        assert_eq!(kind_of("https://open.spotify.com/show/abc"), None);
+    }
+
+    #[test]
+    fn test_parse_apple_music_links() {
+        assert_eq!(
+            kind_of("https://music.apple.com/us/album/abbey-road/1441164426"),
+            Some((MusicService::AppleMusic, MusicLinkKind::Album))
+        );
+        assert_eq!(
+            kind_of("https://music.apple.com/us/album/abbey-road/1441164426?i=1441164430"),
+            Some((MusicService::AppleMusic, MusicLinkKind::Track))
+        );
+        assert_eq!(
+            kind_of(
+                "https://music.apple.com/gb/playlist/todays-hits/pl.f4d106fed2bd41149aaacabb233eb5eb"
+            ),
+            Some((MusicService::AppleMusic, MusicLinkKind::Playlist))
+        );
+    }
+
+    #[test]
+    fn test_other_links_are_ignored() {
+        assert_eq!(kind_of("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(kind_of("never gonna give you up"), None);
+    }
+
+    #[test]
+    fn test_search_query() {
+        let track = LinkedTrack {
+            title: String::from("Under Pressure"),
+            artists: vec![String::from("Queen"), String::from("David Bowie")],
+        };
+
+        assert_eq!(track.search_query(), "Queen, David Bowie - Under Pressure");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_linked_tracks() {
+        let body = r#"{"name": "Mix", "tracks": [
+            {"title": "Song", "artists": ["Band"]},
+            {"title": " "},
+            {"title": "Untitled artist"}
+        ]}"#;
+        let endpoint = test_server::serve(200, "application/json", body).await;
+        let link = parse_music_link("https://open.spotify.com/playlist/abc").unwrap();
+
+        let linked = fetch_linked_tracks(&HttpClient::new(), &endpoint, &link)
+            .await
+            .unwrap();
+
+        assert_eq!(linked.name.as_deref(), Some("Mix"));
+        assert_eq!(linked.tracks.len(), 2);
+        assert_eq!(linked.tracks[1].search_query(), "Untitled artist");
+
+        let empty = test_server::serve(200, "application/json", r#"{"tracks": []}"#).await;
+        assert!(matches!(
+            fetch_linked_tracks(&HttpClient::new(), &empty, &link).await,
+            Err(LinkError::NoTracks)
+        ));
+    }
+}
@@ -0,0 +1,23 @@
+use serenity::all::{CommandDataOption, CommandDataOptionValue, CommandInteraction};
+
+/// The subcommand a command was invoked with and its options
+pub fn subcommand(command: &CommandInteraction) -> Option<(&str, &[CommandDataOption])> {
+    let option = command.data.options.first()?;
+
+    match &option.value {
+        CommandDataOptionValue::SubCommand(options) => {
+            Some((option.name.as_str(), options.as_slice()))
+        }
+        _ => None,
+    }
+}
+
+/// Value of a string option, ignoring surrounding whitespace
+pub fn string_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
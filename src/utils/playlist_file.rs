@@ -0,0 +1,419 @@
+//! Playlist files for moving playlists between bots and players: JSON,
+//! M3U and XSPF.
+
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    saved_playlists::{SavedPlaylist, SavedTrack},
+    xml,
+    youtube::video_id_from_url,
+};
+
+/// Largest playlist file accepted for import
+pub const MAX_PLAYLIST_FILE_BYTES: u32 = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaylistFormat {
+    Json,
+    M3u,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "xspf" => Some(Self::Xspf),
+            _ => None,
+        }
+    }
+
+    /// Format of a file going by its extension
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        Self::parse(extension)
+    }
+
+    /// Best guess at the format of a file with an unknown extension
+    pub fn sniff(contents: &str) -> Self {
+        let contents = contents.trim_start_matches('\u{feff}').trim_start();
+
+        if contents.starts_with('{') {
+            Self::Json
+        } else if contents.starts_with('<') {
+            Self::Xspf
+        } else {
+            Self::M3u
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::M3u => "m3u8",
+            Self::Xspf => "xspf",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlaylistFileError {
+    Invalid(PlaylistFormat),
+    NoTracks,
+}
+
+impl fmt::Display for PlaylistFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaylistFileError::Invalid(format) => write!(
+                f,
+                "That isn't a valid {} playlist",
+                format.extension().to_uppercase()
+            ),
+            PlaylistFileError::NoTracks => write!(f, "That playlist has no YouTube videos to play"),
+        }
+    }
+}
+
+/// JSON layout of an exported playlist. Durations are whole seconds so the
+/// file is easy to produce from other tools.
+#[derive(Serialize, Deserialize)]
+struct JsonPlaylist {
+    name: String,
+    tracks: Vec<JsonTrack>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonTrack {
+    title: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thumbnail_url: Option<String>,
+}
+
+/// Write a playlist in the given format
+pub fn export_playlist(playlist: &SavedPlaylist, format: PlaylistFormat) -> String {
+    match format {
+        PlaylistFormat::Json => export_json(playlist),
+        PlaylistFormat::M3u => export_m3u(playlist),
+        PlaylistFormat::Xspf => export_xspf(playlist),
+    }
+}
+
+/// Read a playlist file. `fallback_name` names playlists whose file doesn't
+/// carry a name of its own. Only YouTube videos are kept, the same as
+/// `/play-url` accepts, since anything else would have the bot fetch
+/// whatever URL the file points at.
+pub fn import_playlist(
+    contents: &str,
+    format: PlaylistFormat,
+    fallback_name: &str,
+) -> Result<SavedPlaylist, PlaylistFileError> {
+    let contents = contents.trim_start_matches('\u{feff}');
+
+    let mut playlist = match format {
+        PlaylistFormat::Json => import_json(contents)?,
+        PlaylistFormat::M3u => import_m3u(contents),
+        PlaylistFormat::Xspf => import_xspf(contents)?,
+    };
+
+    playlist
+        .tracks
+        .retain(|track| video_id_from_url(&track.url).is_some());
+
+    if playlist.tracks.is_empty() {
+        return Err(PlaylistFileError::NoTracks);
+    }
+
+    if playlist.name.trim().is_empty() {
+        playlist.name = fallback_name.to_string();
+    }
+
+    Ok(playlist)
+}
+
+fn export_json(playlist: &SavedPlaylist) -> String {
+    let json = JsonPlaylist {
+        name: playlist.name.clone(),
+        tracks: playlist
+            .tracks
+            .iter()
+            .map(|track| JsonTrack {
+                title: track.title.clone(),
+                url: track.url.clone(),
+                duration: track.duration.map(|duration| duration.as_secs()),
+                thumbnail_url: track.thumbnail_url.clone(),
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&json).unwrap_or_default()
+}
+
+fn import_json(contents: &str) -> Result<SavedPlaylist, PlaylistFileError> {
+    let json: JsonPlaylist = serde_json::from_str(contents)
+        .map_err(|_| PlaylistFileError::Invalid(PlaylistFormat::Json))?;
+
+    Ok(SavedPlaylist {
+        name: json.name,
+        tracks: json
+            .tracks
+            .into_iter()
+            .map(|track| SavedTrack {
+                title: track.title,
+                url: track.url,
+                duration: track.duration.map(Duration::from_secs),
+                thumbnail_url: track.thumbnail_url,
+            })
+            .collect(),
+    })
+}
+
+/// Extended M3U: `#EXTINF:<seconds>,<title>` before each URL, and the
+/// playlist name in `#PLAYLIST:`
+fn export_m3u(playlist: &SavedPlaylist) -> String {
+    let mut m3u = format!("#EXTM3U\n#PLAYLIST:{}\n", one_line(&playlist.name));
+
+    for track in playlist.tracks.iter() {
+        let seconds = track
+            .duration
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(-1);
+
+        m3u.push_str(&format!(
+            "#EXTINF:{},{}\n{}\n",
+            seconds,
+            one_line(&track.title),
+            track.url
+        ));
+    }
+
+    m3u
+}
+
+fn import_m3u(contents: &str) -> SavedPlaylist {
+    let mut name = String::new();
+    let mut tracks = Vec::new();
+    // Title and duration from the #EXTINF line before the next URL
+    let mut pending: Option<(String, Option<Duration>)> = None;
+
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        if let Some(playlist_name) = line.strip_prefix("#PLAYLIST:") {
+            name = playlist_name.trim().to_string();
+        } else if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (seconds, title) = info.split_once(',').unwrap_or((info, ""));
+            // Attributes like tvg-id="..." may follow the duration
+            let duration = seconds
+                .split_whitespace()
+                .next()
+                .and_then(|seconds| seconds.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            pending = Some((title.trim().to_string(), duration));
+        } else if !line.starts_with('#') {
+            let (title, duration) = pending.take().unwrap_or_default();
+
+            tracks.push(SavedTrack {
+                title: if title.is_empty() {
+                    line.to_string()
+                } else {
+                    title
+                },
+                url: line.to_string(),
+                duration,
+                thumbnail_url: None,
+            });
+        }
+    }
+
+    SavedPlaylist { name, tracks }
+}
+
+/// XSPF with durations in milliseconds, as the format specifies
+fn export_xspf(playlist: &SavedPlaylist) -> String {
+    let mut xspf = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+    xspf.push_str(&format!(
+        "  <title>{}</title>\n",
+        xml::escape(&playlist.name)
+    ));
+    xspf.push_str("  <trackList>\n");
+
+    for track in playlist.tracks.iter() {
+        xspf.push_str("    <track>\n");
+        xspf.push_str(&format!(
+            "      <location>{}</location>\n",
+            xml::escape(&track.url)
+        ));
+        xspf.push_str(&format!(
+            "      <title>{}</title>\n",
+            xml::escape(&track.title)
+        ));
+        if let Some(duration) = track.duration {
+            xspf.push_str(&format!(
+                "      <duration>{}</duration>\n",
+                duration.as_millis()
+            ));
+        }
+        if let Some(thumbnail_url) = &track.thumbnail_url {
+            xspf.push_str(&format!(
+                "      <image>{}</image>\n",
+                xml::escape(thumbnail_url)
+            ));
+        }
+        xspf.push_str("    </track>\n");
+    }
+
+    xspf.push_str("  </trackList>\n</playlist>\n");
+    xspf
+}
+
+fn import_xspf(contents: &str) -> Result<SavedPlaylist, PlaylistFileError> {
+    let invalid = || PlaylistFileError::Invalid(PlaylistFormat::Xspf);
+
+    let playlist = xml::parse(contents).ok_or_else(invalid)?;
+    let track_list = playlist.child("trackList").ok_or_else(invalid)?;
+
+    let tracks = track_list
+        .children("track")
+        .filter_map(|track| {
+            let url = track.child_text("location")?;
+
+            Some(SavedTrack {
+                title: track.child_text("title").unwrap_or_else(|| url.clone()),
+                duration: track
+                    .child_text("duration")
+                    .and_then(|millis| millis.parse::<u64>().ok())
+                    .map(Duration::from_millis),
+                thumbnail_url: track.child_text("image"),
+                url,
+            })
+        })
+        .collect();
+
+    Ok(SavedPlaylist {
+        name: playlist.child_text("title").unwrap_or_default(),
+        tracks,
+    })
+}
+
+/// Keep a value on one line of a line-based format
+fn one_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist() -> SavedPlaylist {
+        SavedPlaylist {
+            name: String::from("Rock & Roll <Live>"),
+            tracks: vec![
+                SavedTrack {
+                    title: String::from("Highway to Hell, \"Live\""),
+                    url: String::from("https://www.youtube.com/watch?v=l482T0yNkeo&t=5"),
+                    duration: Some(Duration::from_secs(208)),
+                    thumbnail_url: Some(String::from("https://i.ytimg.com/vi/l482T0yNkeo/hq.jpg")),
+                },
+                SavedTrack {
+                    title: String::from("Untimed"),
+                    url: String::from("https://youtu.be/dQw4w9WgXcQ"),
+                    duration: None,
+                    thumbnail_url: None,
+                },
+            ],
+        }
+    }
+
+    fn round_trip(format: PlaylistFormat) -> SavedPlaylist {
+        let exported = export_playlist(&playlist(), format);
+        import_playlist(&exported, format, "fallback").unwrap()
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        assert_eq!(round_trip(PlaylistFormat::Json), playlist());
+    }
+
+    #[test]
+    fn test_xspf_round_trip() {
+        assert_eq!(round_trip(PlaylistFormat::Xspf), playlist());
+    }
+
+    #[test]
+    fn test_m3u_round_trip() {
+        // M3U has nowhere to keep thumbnails
+        let mut expected = playlist();
+        expected.tracks[0].thumbnail_url = None;
+
+        assert_eq!(round_trip(PlaylistFormat::M3u), expected);
+    }
+
+    #[test]
+    fn test_plain_m3u_without_extinf() {
+        let m3u = "https://youtu.be/dQw4w9WgXcQ\r\n/home/me/music/b.mp3\r\n";
+
+        let imported = import_playlist(m3u, PlaylistFormat::M3u, "Imported").unwrap();
+
+        assert_eq!(imported.name, "Imported");
+        assert_eq!(imported.tracks.len(), 1);
+        assert_eq!(imported.tracks[0].title, "https://youtu.be/dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn test_only_youtube_videos_are_imported() {
+        let m3u = "http://169.254.169.254/latest/meta-data\n\
+                   https://example.com/a.mp3\n\
+                   https://www.youtube.com/watch?v=l482T0yNkeo\n";
+
+        let imported = import_playlist(m3u, PlaylistFormat::M3u, "Imported").unwrap();
+        assert_eq!(imported.tracks.len(), 1);
+
+        assert_eq!(
+            import_playlist("http://localhost:8080/\n", PlaylistFormat::M3u, "x"),
+            Err(PlaylistFileError::NoTracks)
+        );
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert_eq!(
+            import_playlist("not json", PlaylistFormat::Json, "x"),
+            Err(PlaylistFileError::Invalid(PlaylistFormat::Json))
+        );
+        assert_eq!(
+            import_playlist("<playlist/>", PlaylistFormat::Xspf, "x"),
+            Err(PlaylistFileError::Invalid(PlaylistFormat::Xspf))
+        );
+        assert_eq!(
+            import_playlist("#EXTM3U\n", PlaylistFormat::M3u, "x"),
+            Err(PlaylistFileError::NoTracks)
+        );
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            PlaylistFormat::from_filename("mix.M3U8"),
+            Some(PlaylistFormat::M3u)
+        );
+        assert_eq!(PlaylistFormat::from_filename("mix.txt"), None);
+        assert_eq!(
+            PlaylistFormat::sniff("\u{feff} {\"name\": 1}"),
+            PlaylistFormat::Json
+        );
+        assert_eq!(PlaylistFormat::sniff("<?xml?>"), PlaylistFormat::Xspf);
+        assert_eq!(PlaylistFormat::sniff("#EXTM3U"), PlaylistFormat::M3u);
+    }
+}
@@ -0,0 +1,497 @@
+//! Podcast RSS and Atom feeds, and the feeds each guild subscribed to.
+
+use std::{collections::HashMap, fmt, io, time::Duration};
+
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serenity::{all::GuildId, prelude::TypeMapKey};
+use tracing::error;
+use url::Url;
+
+use crate::utils::{
+    json_store::{self, data_file},
+    track_utils::TrackMetadata,
+    xml::{self, Element},
+};
+
+const SUBSCRIPTIONS_FILE: &str = "podcasts.json";
+
+/// Episodes shown on each page of a feed
+pub const EPISODES_PER_PAGE: usize = 5;
+
+/// Feeds with years of episodes run to a few MiB, anything far beyond that
+/// isn't worth downloading
+const MAX_FEED_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Episode {
+    pub title: String,
+    /// The enclosure, i.e. the episode's audio file
+    pub audio_url: String,
+    pub duration: Option<Duration>,
+    pub image: Option<String>,
+    pub published: Option<String>,
+}
+
+impl Episode {
+    /// Stable key for the episode's buttons, from its enclosure URL
+    pub fn key(&self) -> u64 {
+        url_key(&self.audio_url)
+    }
+
+    pub fn metadata(&self) -> TrackMetadata {
+        TrackMetadata {
+            title: self.title.clone(),
+            thumbnail_url: self.image.clone(),
+            duration: self.duration,
+            source_url: Some(self.audio_url.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub image: Option<String>,
+    /// Newest first, as feeds list them
+    pub episodes: Vec<Episode>,
+}
+
+impl Feed {
+    pub fn page_count(&self) -> usize {
+        self.episodes.len().div_ceil(EPISODES_PER_PAGE).max(1)
+    }
+
+    /// The episodes on `page` along with the index of the first one
+    pub fn page(&self, page: usize) -> (usize, &[Episode]) {
+        let start = (page * EPISODES_PER_PAGE).min(self.episodes.len());
+        let end = (start + EPISODES_PER_PAGE).min(self.episodes.len());
+        (start, &self.episodes[start..end])
+    }
+}
+
+/// Short key for a feed or episode URL, since button custom ids are too
+/// short for the URLs themselves. FNV-1a, so keys on buttons that are
+/// already posted still match after a restart.
+pub fn url_key(url: &str) -> u64 {
+    url.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug)]
+pub enum PodcastError {
+    InvalidUrl,
+    NotAFeed,
+    NoEpisodes,
+    TooLarge,
+    Request(reqwest::Error),
+}
+
+impl fmt::Display for PodcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PodcastError::InvalidUrl => write!(f, "That isn't a valid http(s) feed URL"),
+            PodcastError::NotAFeed => write!(f, "That URL isn't an RSS or Atom feed"),
+            PodcastError::NoEpisodes => write!(f, "That feed has no playable episodes"),
+            PodcastError::TooLarge => write!(f, "That feed is too large"),
+            PodcastError::Request(err) => write!(f, "Couldn't fetch the feed: {}", err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for PodcastError {
+    fn from(err: reqwest::Error) -> Self {
+        PodcastError::Request(err)
+    }
+}
+
+/// Download and parse a podcast feed
+pub async fn fetch_feed(client: &HttpClient, url: &str) -> Result<Feed, PodcastError> {
+    let url = match Url::parse(url.trim()) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => return Err(PodcastError::InvalidUrl),
+    };
+
+    let mut response = client
+        .get(url)
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_FEED_BYTES as u64)
+    {
+        return Err(PodcastError::TooLarge);
+    }
+
+    // Read it in chunks so a server that doesn't say how long the feed is
+    // can't make us buffer it all
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+
+        if body.len() > MAX_FEED_BYTES {
+            return Err(PodcastError::TooLarge);
+        }
+    }
+
+    parse_feed(&String::from_utf8_lossy(&body))
+}
+
+/// Read the title, artwork and episodes of an RSS or Atom feed
+pub fn parse_feed(body: &str) -> Result<Feed, PodcastError> {
+    let root = xml::parse(body).ok_or(PodcastError::NotAFeed)?;
+
+    // RSS keeps the feed's details and episodes in its channel, Atom at the
+    // top level
+    let (channel, is_atom) = match root.name.as_str() {
+        "rss" => (root.child("channel").ok_or(PodcastError::NotAFeed)?, false),
+        "feed" => (&root, true),
+        _ => return Err(PodcastError::NotAFeed),
+    };
+
+    let title = channel
+        .child_text("title")
+        .unwrap_or_else(|| String::from("Podcast"));
+    let image = if is_atom {
+        channel
+            .child_text("logo")
+            .or_else(|| channel.child_text("icon"))
+    } else {
+        itunes_image(channel).or_else(|| {
+            channel
+                .children("image")
+                .find_map(|image| image.child_text("url"))
+        })
+    };
+
+    let episodes: Vec<Episode> = channel
+        .children(if is_atom { "entry" } else { "item" })
+        .filter_map(|entry| parse_episode(entry, is_atom))
+        .map(|episode| Episode {
+            image: episode.image.or_else(|| image.clone()),
+            ..episode
+        })
+        .collect();
+
+    if episodes.is_empty() {
+        return Err(PodcastError::NoEpisodes);
+    }
+
+    Ok(Feed {
+        title,
+        image,
+        episodes,
+    })
+}
+
+fn parse_episode(entry: &Element, is_atom: bool) -> Option<Episode> {
+    let audio_url = if is_atom {
+        entry
+            .children("link")
+            .find(|link| link.attribute("rel") == Some("enclosure"))
+            .and_then(|link| link.attribute("href"))
+    } else {
+        entry
+            .children("enclosure")
+            .find_map(|enclosure| enclosure.attribute("url"))
+    }?;
+
+    let published = if is_atom {
+        entry
+            .child_text("published")
+            .or_else(|| entry.child_text("updated"))
+    } else {
+        entry.child_text("pubDate")
+    };
+
+    Some(Episode {
+        title: entry
+            .child_text("title")
+            .unwrap_or_else(|| String::from("Untitled episode")),
+        audio_url: audio_url.to_string(),
+        duration: entry
+            .child_text("itunes:duration")
+            .and_then(|d| parse_duration(&d)),
+        image: itunes_image(entry),
+        published: published.map(|date| short_date(&date)),
+    })
+}
+
+fn itunes_image(element: &Element) -> Option<String> {
+    element
+        .children("itunes:image")
+        .find_map(|image| image.attribute("href"))
+        .map(String::from)
+}
+
+/// Parse an `itunes:duration`, given either in seconds or as [HH:]MM:SS
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let seconds = value.trim().split(':').try_fold(0u64, |total, part| {
+        Some(total * 60 + part.parse::<u64>().ok()?)
+    })?;
+
+    Some(Duration::from_secs(seconds))
+}
+
+/// The date part of an RSS (RFC 2822) or Atom (RFC 3339) date
+fn short_date(date: &str) -> String {
+    match date.split_once('T') {
+        Some((day, _)) if !date.contains(' ') => day.to_string(),
+        _ => date
+            .split_whitespace()
+            .take(4)
+            .collect::<Vec<&str>>()
+            .join(" "),
+    }
+}
+
+/// A feed a guild subscribed to, offered again by autocomplete
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PodcastSubscription {
+    pub title: String,
+    pub url: String,
+}
+
+/// Subscribed feeds of every guild, stored in the data directory
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PodcastSubscriptions {
+    guilds: HashMap<u64, Vec<PodcastSubscription>>,
+}
+
+impl PodcastSubscriptions {
+    pub fn load() -> Self {
+        json_store::load(&data_file(SUBSCRIPTIONS_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        json_store::save(&data_file(SUBSCRIPTIONS_FILE), self)
+    }
+
+    pub fn list(&self, guild_id: GuildId) -> &[PodcastSubscription] {
+        self.guilds
+            .get(&guild_id.get())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The subscription whose URL has the given `url_key`
+    pub fn get(&self, guild_id: GuildId, key: u64) -> Option<&PodcastSubscription> {
+        self.list(guild_id)
+            .iter()
+            .find(|subscription| url_key(&subscription.url) == key)
+    }
+
+    /// Subscribe to a feed, updating its title if it was already
+    /// subscribed. Returns the feed's index.
+    pub fn subscribe(&mut self, guild_id: GuildId, subscription: PodcastSubscription) -> usize {
+        let subscriptions = self.guilds.entry(guild_id.get()).or_default();
+
+        match subscriptions
+            .iter()
+            .position(|existing| existing.url == subscription.url)
+        {
+            Some(index) => {
+                subscriptions[index] = subscription;
+                index
+            }
+            None => {
+                subscriptions.push(subscription);
+                subscriptions.len() - 1
+            }
+        }
+    }
+
+    /// Unsubscribe from the feed whose URL has the given `url_key`. Returns
+    /// the removed subscription.
+    pub fn unsubscribe(&mut self, guild_id: GuildId, key: u64) -> Option<PodcastSubscription> {
+        let subscriptions = self.guilds.get_mut(&guild_id.get())?;
+        let index = subscriptions
+            .iter()
+            .position(|subscription| url_key(&subscription.url) == key)?;
+        Some(subscriptions.remove(index))
+    }
+}
+
+/// Save the subscriptions, logging instead of failing since the change
+/// still applies until the bot restarts
+pub fn save_subscriptions(subscriptions: &PodcastSubscriptions) {
+    if let Err(err) = subscriptions.save() {
+        error!("Failed to save podcast subscriptions: {}", err);
+    }
+}
+
+pub struct PodcastSubscriptionsKey;
+
+impl TypeMapKey for PodcastSubscriptionsKey {
+    type Value = PodcastSubscriptions;
+}
+
+/// Feeds opened with `/podcast` since the bot started, by the `url_key` of
+/// their URL, so their buttons work without subscribing
+pub struct OpenedFeedsKey;
+
+impl TypeMapKey for OpenedFeedsKey {
+    type Value = HashMap<u64, PodcastSubscription>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+  <channel>
+    <title>Rust &amp; Friends</title>
+    <itunes:image href="https://example.com/show.jpg"/>
+    <item>
+      <title><![CDATA[Episode 2: Lifetimes]]></title>
+      <pubDate>Tue, 08 Oct 2024 10:00:00 GMT</pubDate>
+      <enclosure url="https://example.com/2.mp3" length="1000" type="audio/mpeg"/>
+      <itunes:duration>1:02:03</itunes:duration>
+      <itunes:image href="https://example.com/2.jpg"/>
+    </item>
+    <item>
+      <title>Announcement without audio</title>
+    </item>
+    <item>
+      <title>Episode 1: Ownership</title>
+      <enclosure url="https://example.com/1.mp3" type="audio/mpeg"/>
+      <itunes:duration>1800</itunes:duration>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Cast</title>
+  <logo>https://example.com/logo.png</logo>
+  <entry>
+    <title>Pilot</title>
+    <published>2024-10-01T12:00:00Z</published>
+    <link rel="alternate" href="https://example.com/pilot"/>
+    <link rel="enclosure" type="audio/mpeg" href="https://example.com/pilot.mp3"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn test_parse_rss_feed() {
+        let feed = parse_feed(RSS).unwrap();
+
+        assert_eq!(feed.title, "Rust & Friends");
+        assert_eq!(feed.image.as_deref(), Some("https://example.com/show.jpg"));
+        assert_eq!(feed.episodes.len(), 2);
+
+        let latest = &feed.episodes[0];
+        assert_eq!(latest.title, "Episode 2: Lifetimes");
+        assert_eq!(latest.audio_url, "https://example.com/2.mp3");
+        assert_eq!(latest.duration, Some(Duration::from_secs(3723)));
+        assert_eq!(latest.image.as_deref(), Some("https://example.com/2.jpg"));
+        assert_eq!(latest.published.as_deref(), Some("Tue, 08 Oct 2024"));
+
+        // Episodes without artwork use the show's
+        assert_eq!(
+            feed.episodes[1].image.as_deref(),
+            Some("https://example.com/show.jpg")
+        );
+        assert_eq!(feed.episodes[1].duration, Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn test_parse_atom_feed() {
+        let feed = parse_feed(ATOM).unwrap();
+
+        assert_eq!(feed.title, "Atom Cast");
+        assert_eq!(feed.image.as_deref(), Some("https://example.com/logo.png"));
+        assert_eq!(feed.episodes[0].audio_url, "https://example.com/pilot.mp3");
+        assert_eq!(feed.episodes[0].published.as_deref(), Some("2024-10-01"));
+    }
+
+    #[test]
+    fn test_parse_rejects_non_feeds() {
+        assert!(matches!(
+            parse_feed("<html><body>hi</body></html>"),
+            Err(PodcastError::NotAFeed)
+        ));
+        assert!(matches!(
+            parse_feed("<rss><channel><title>Empty</title></channel></rss>"),
+            Err(PodcastError::NoEpisodes)
+        ));
+    }
+
+    #[test]
+    fn test_pages() {
+        let mut feed = parse_feed(RSS).unwrap();
+        let episode = feed.episodes[0].clone();
+        feed.episodes = vec![episode; 12];
+
+        assert_eq!(feed.page_count(), 3);
+        assert_eq!(feed.page(2).0, 10);
+        assert_eq!(feed.page(2).1.len(), 2);
+        assert!(feed.page(5).1.is_empty());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("12:34"), Some(Duration::from_secs(754)));
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_subscribe_updates_existing_feeds() {
+        let guild_id = GuildId::new(1);
+        let mut subscriptions = PodcastSubscriptions::default();
+
+        let subscription = |title: &str| PodcastSubscription {
+            title: title.to_string(),
+            url: String::from("https://example.com/feed.xml"),
+        };
+
+        assert_eq!(subscriptions.subscribe(guild_id, subscription("Old")), 0);
+        assert_eq!(subscriptions.subscribe(guild_id, subscription("New")), 0);
+        assert_eq!(subscriptions.list(guild_id).len(), 1);
+        assert_eq!(subscriptions.list(guild_id)[0].title, "New");
+
+        let key = url_key("https://example.com/feed.xml");
+        assert_eq!(subscriptions.get(guild_id, key).unwrap().title, "New");
+        assert!(subscriptions.unsubscribe(guild_id, key).is_some());
+        assert!(subscriptions.unsubscribe(guild_id, key).is_none());
+    }
+
+    #[test]
+    fn test_url_keys_are_stable() {
+        assert_eq!(url_key(""), 0xcbf29ce484222325);
+        assert_eq!(url_key("a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(
+            url_key("https://example.com/1.mp3"),
+            url_key("https://example.com/2.mp3")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_feed() {
+        let url = test_server::serve(200, "application/rss+xml", RSS).await;
+
+        let feed = fetch_feed(&HttpClient::new(), &url).await.unwrap();
+        assert_eq!(feed.episodes.len(), 2);
+
+        assert!(matches!(
+            fetch_feed(&HttpClient::new(), "ftp://example.com/feed").await,
+            Err(PodcastError::InvalidUrl)
+        ));
+
+        let huge =
+            test_server::serve(200, "application/rss+xml", vec![b' '; MAX_FEED_BYTES + 1]).await;
+        assert!(matches!(
+            fetch_feed(&HttpClient::new(), &huge).await,
+            Err(PodcastError::TooLarge)
+        ));
+    }
+}
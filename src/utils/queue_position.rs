@@ -0,0 +1,154 @@
+use serenity::all::{CommandDataOptionValue, CommandInteraction, CommandOptionType};
+use songbird::tracks::TrackQueue;
+
+use crate::utils::track_utils::move_last_to;
+
+/// Where a newly requested track goes in the queue
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum QueuePosition {
+    /// After everything already queued
+    #[default]
+    End,
+    /// Straight after the current track
+    Next,
+    /// Interrupt the current track, which resumes once the new one ends
+    Now,
+    /// 1-based position as numbered by `/list`, where 1 is the current track
+    Index(usize),
+}
+
+impl QueuePosition {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "end" | "last" => Some(Self::End),
+            "next" => Some(Self::Next),
+            "now" => Some(Self::Now),
+            other => other.parse::<usize>().ok().map(Self::Index),
+        }
+    }
+
+    /// Response shown once a track was placed at this position
+    pub fn describe(&self, title: &str, index: usize) -> String {
+        match self {
+            Self::End => format!("**Queued** {}!", title),
+            Self::Next => format!("**Playing next:** {}!", title),
+            Self::Now => format!("**Now playing** {}!", title),
+            Self::Index(_) => format!("**Queued** {} at #{}!", title, index + 1),
+        }
+    }
+}
+
+/// Read the optional `position` option of a play command, defaulting to the
+/// end of the queue. Returns `None` when the value can't be understood.
+pub fn position_option(command: &CommandInteraction) -> Option<QueuePosition> {
+    let option = command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == "position");
+
+    match option.map(|option| &option.value) {
+        None => Some(QueuePosition::End),
+        Some(CommandDataOptionValue::String(value)) => QueuePosition::parse(value),
+        Some(_) => None,
+    }
+}
+
+/// The `position` option added to play commands
+pub fn register_position_option() -> serenity::builder::CreateCommandOption {
+    serenity::builder::CreateCommandOption::new(
+        CommandOptionType::String,
+        "position",
+        "Where to queue it: next, now, end or a position in /list",
+    )
+    .required(false)
+}
+
+/// Queue index the newest of `queue_length` tracks moves to. The current
+/// track stays at index 0 unless the new track interrupts it.
+fn target_index(position: QueuePosition, queue_length: usize) -> usize {
+    let last = queue_length.saturating_sub(1);
+
+    match position {
+        QueuePosition::End => last,
+        QueuePosition::Next => last.min(1),
+        QueuePosition::Now => 0,
+        QueuePosition::Index(number) => number.saturating_sub(1).max(1).min(last),
+    }
+}
+
+/// Move the most recently enqueued track to `position` and return the index
+/// it ended up at.
+pub fn place_last_track(queue: &TrackQueue, position: QueuePosition) -> usize {
+    let index = target_index(position, queue.len());
+
+    if position != QueuePosition::Now || index == queue.len().saturating_sub(1) {
+        move_last_to(queue, index);
+        return index;
+    }
+
+    // Pause rather than stop the current track so the queue picks it back up,
+    // from where it was, once the new track ends
+    queue.modify_queue(|tracks| {
+        if let Some(current) = tracks.front() {
+            let _ = current.pause();
+        }
+
+        if let Some(track) = tracks.pop_back() {
+            let _ = track.play();
+            tracks.push_front(track);
+        }
+    });
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queue_position() {
+        assert_eq!(QueuePosition::parse("next"), Some(QueuePosition::Next));
+        assert_eq!(QueuePosition::parse(" NOW "), Some(QueuePosition::Now));
+        assert_eq!(QueuePosition::parse("end"), Some(QueuePosition::End));
+        assert_eq!(QueuePosition::parse("3"), Some(QueuePosition::Index(3)));
+        assert_eq!(QueuePosition::parse("soon"), None);
+    }
+
+    #[test]
+    fn test_target_index_end_and_next() {
+        assert_eq!(target_index(QueuePosition::End, 5), 4);
+        assert_eq!(target_index(QueuePosition::Next, 5), 1);
+        // The only track in the queue is already playing
+        assert_eq!(target_index(QueuePosition::Next, 1), 0);
+    }
+
+    #[test]
+    fn test_target_index_now() {
+        assert_eq!(target_index(QueuePosition::Now, 5), 0);
+        assert_eq!(target_index(QueuePosition::Now, 1), 0);
+    }
+
+    #[test]
+    fn test_target_index_clamps_list_numbers() {
+        assert_eq!(target_index(QueuePosition::Index(3), 5), 2);
+        // Position 1 is the current track, which an index can't replace
+        assert_eq!(target_index(QueuePosition::Index(1), 5), 1);
+        assert_eq!(target_index(QueuePosition::Index(0), 5), 1);
+        assert_eq!(target_index(QueuePosition::Index(99), 5), 4);
+        assert_eq!(target_index(QueuePosition::Index(3), 1), 0);
+    }
+
+    #[test]
+    fn test_describe_position() {
+        assert_eq!(
+            QueuePosition::Index(3).describe("Song", 2),
+            "**Queued** Song at #3!"
+        );
+        assert_eq!(
+            QueuePosition::Next.describe("Song", 1),
+            "**Playing next:** Song!"
+        );
+    }
+}
@@ -0,0 +1,538 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, Http, MessageId},
+    async_trait,
+    builder::EditMessage,
+    prelude::TypeMapKey,
+};
+use songbird::{
+    Event, EventContext, EventHandler,
+    input::{HlsRequest, HttpRequest, Input},
+    tracks::TrackHandle,
+};
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use crate::{
+    handlers::track_play::create_now_playing_embed,
+    utils::{
+        json_store::{self, data_file},
+        track_utils::TrackMetadata,
+    },
+};
+
+const PRESETS_FILE: &str = "radio_presets.json";
+
+/// ICY metadata blocks are usually every 8-16KiB, anything far beyond that
+/// isn't worth downloading just for a title
+const MAX_METADATA_INTERVAL: usize = 256 * 1024;
+
+/// How often a playing station is asked what song is on
+pub const TITLE_POLL_INTERVAL: Duration = Duration::from_secs(20);
+
+/// A station saved in a guild under a short name
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StationPreset {
+    pub name: String,
+    pub url: String,
+}
+
+/// Saved stations of every guild, stored in the data directory
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RadioPresets {
+    guilds: HashMap<u64, Vec<StationPreset>>,
+}
+
+impl RadioPresets {
+    pub fn load() -> Self {
+        json_store::load(&data_file(PRESETS_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        json_store::save(&data_file(PRESETS_FILE), self)
+    }
+
+    pub fn list(&self, guild_id: GuildId) -> &[StationPreset] {
+        self.guilds
+            .get(&guild_id.get())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Look a preset up by name, ignoring case
+    pub fn find(&self, guild_id: GuildId, name: &str) -> Option<&StationPreset> {
+        self.list(guild_id)
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Save a preset, replacing one with the same name. Returns whether an
+    /// existing preset was replaced.
+    pub fn upsert(&mut self, guild_id: GuildId, preset: StationPreset) -> bool {
+        let presets = self.guilds.entry(guild_id.get()).or_default();
+
+        match presets
+            .iter_mut()
+            .find(|existing| existing.name.eq_ignore_ascii_case(&preset.name))
+        {
+            Some(existing) => {
+                *existing = preset;
+                true
+            }
+            None => {
+                presets.push(preset);
+                false
+            }
+        }
+    }
+
+    /// Returns whether a preset with that name existed
+    pub fn remove(&mut self, guild_id: GuildId, name: &str) -> bool {
+        let Some(presets) = self.guilds.get_mut(&guild_id.get()) else {
+            return false;
+        };
+
+        let before = presets.len();
+        presets.retain(|preset| !preset.name.eq_ignore_ascii_case(name.trim()));
+        before != presets.len()
+    }
+}
+
+/// Save the presets, logging instead of failing since the change still
+/// applies until the bot restarts
+pub fn save_presets(presets: &RadioPresets) {
+    if let Err(err) = presets.save() {
+        error!("Failed to save radio presets: {}", err);
+    }
+}
+
+pub struct RadioPresetsKey;
+
+impl TypeMapKey for RadioPresetsKey {
+    type Value = RadioPresets;
+}
+
+#[derive(Default)]
+struct StationStatus {
+    song: Option<String>,
+    announcement: Option<MessageId>,
+}
+
+/// A radio station attached to a queued track. The song playing changes
+/// while the track plays, so it is shared with the title poller.
+#[derive(Clone)]
+pub struct RadioStation {
+    pub name: String,
+    pub stream_url: String,
+    status: Arc<Mutex<StationStatus>>,
+}
+
+impl RadioStation {
+    pub fn new(name: String, stream_url: String, song: Option<String>) -> Self {
+        Self {
+            name,
+            stream_url,
+            status: Arc::new(Mutex::new(StationStatus {
+                song,
+                announcement: None,
+            })),
+        }
+    }
+
+    pub fn song(&self) -> Option<String> {
+        self.status.lock().ok()?.song.clone()
+    }
+
+    /// Returns whether the song changed
+    pub fn set_song(&self, song: String) -> bool {
+        let Ok(mut status) = self.status.lock() else {
+            return false;
+        };
+
+        if status.song.as_ref() == Some(&song) {
+            return false;
+        }
+
+        status.song = Some(song);
+        true
+    }
+
+    /// The "Now playing" message that is kept up to date with the song
+    pub fn announcement(&self) -> Option<MessageId> {
+        self.status.lock().ok()?.announcement
+    }
+
+    pub fn set_announcement(&self, message_id: MessageId) {
+        if let Ok(mut status) = self.status.lock() {
+            status.announcement = Some(message_id);
+        }
+    }
+}
+
+/// Why a station couldn't be played
+#[derive(Debug)]
+pub enum RadioError {
+    InvalidUrl,
+    EmptyPlaylist,
+    Request(reqwest::Error),
+}
+
+impl fmt::Display for RadioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RadioError::InvalidUrl => write!(f, "That isn't a valid http(s) stream URL"),
+            RadioError::EmptyPlaylist => write!(f, "That playlist file lists no streams"),
+            RadioError::Request(err) => write!(f, "Couldn't reach the station: {}", err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for RadioError {
+    fn from(err: reqwest::Error) -> Self {
+        RadioError::Request(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamKind {
+    /// An HLS playlist of audio segments
+    Hls,
+    /// A continuous Icecast/Shoutcast style stream
+    Direct,
+}
+
+fn parse_stream_url(url: &str) -> Result<Url, RadioError> {
+    match Url::parse(url.trim()) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(url),
+        _ => Err(RadioError::InvalidUrl),
+    }
+}
+
+fn path_extension(url: &Url) -> String {
+    url.path()
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn stream_kind(url: &Url) -> StreamKind {
+    if path_extension(url) == "m3u8" {
+        StreamKind::Hls
+    } else {
+        StreamKind::Direct
+    }
+}
+
+/// First stream listed in a .pls or .m3u playlist file
+fn parse_playlist_file(body: &str) -> Option<String> {
+    body.lines()
+        .map(str::trim)
+        .map(|line| match line.split_once('=') {
+            // PLS entries look like "File1=http://..."
+            Some((key, value)) if key.to_lowercase().starts_with("file") => value.trim(),
+            _ => line,
+        })
+        .find(|line| line.starts_with("http://") || line.starts_with("https://"))
+        .map(String::from)
+}
+
+/// Work out the URL to stream from and how to play it. Links to .pls and
+/// .m3u playlist files, as stations often share, are followed to the first
+/// stream they list.
+pub async fn resolve_stream(
+    client: &HttpClient,
+    url: &str,
+) -> Result<(String, StreamKind), RadioError> {
+    let mut url = parse_stream_url(url)?;
+
+    if matches!(path_extension(&url).as_str(), "pls" | "m3u") {
+        let body = client
+            .get(url.as_str())
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let stream = parse_playlist_file(&body).ok_or(RadioError::EmptyPlaylist)?;
+        url = parse_stream_url(&stream)?;
+    }
+
+    let kind = stream_kind(&url);
+    Ok((url.into(), kind))
+}
+
+/// Songbird input for a resolved stream
+pub fn stream_input(client: HttpClient, url: String, kind: StreamKind) -> Input {
+    match kind {
+        StreamKind::Hls => HlsRequest::new(client, url).into(),
+        StreamKind::Direct => HttpRequest::new(client, url).into(),
+    }
+}
+
+/// What an Icecast/Shoutcast server says about itself
+#[derive(Debug, Default, PartialEq)]
+pub struct IcyInfo {
+    pub name: Option<String>,
+    pub song: Option<String>,
+}
+
+/// Ask a stream for its ICY metadata: the station name from the headers and
+/// the current song from the first metadata block. Streams without ICY
+/// support return empty info.
+pub async fn fetch_icy_info(client: &HttpClient, url: &str) -> Result<IcyInfo, reqwest::Error> {
+    let mut response = client
+        .get(url)
+        .header("Icy-MetaData", "1")
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let name = header("icy-name");
+    let metadata_interval = header("icy-metaint")
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|interval| *interval <= MAX_METADATA_INTERVAL);
+
+    let Some(interval) = metadata_interval else {
+        return Ok(IcyInfo { name, song: None });
+    };
+
+    // The metadata block follows `interval` bytes of audio, prefixed with
+    // its length in units of 16 bytes
+    let mut buffer = Vec::with_capacity(interval + 1);
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+
+        let Some(length) = buffer.get(interval).map(|length| *length as usize * 16) else {
+            continue;
+        };
+
+        if buffer.len() > interval + length {
+            let song = parse_stream_title(&buffer[interval + 1..=interval + length]);
+            return Ok(IcyInfo { name, song });
+        }
+    }
+
+    Ok(IcyInfo { name, song: None })
+}
+
+/// Pull the song out of a metadata block like `StreamTitle='Artist - Song';`
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    let end = rest.find("';").unwrap_or(rest.trim_end_matches('\0').len());
+
+    let title = rest[..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// Periodic track event that keeps the station's current song, and its
+/// "Now playing" message, up to date
+#[derive(Clone)]
+pub struct StationTitlePoller {
+    pub client: HttpClient,
+    pub station: RadioStation,
+    pub channel_id: ChannelId,
+    pub http: Arc<Http>,
+}
+
+impl StationTitlePoller {
+    async fn refresh(self, track: Option<TrackHandle>) {
+        let song = match fetch_icy_info(&self.client, &self.station.stream_url).await {
+            Ok(info) => match info.song {
+                Some(song) => song,
+                None => return,
+            },
+            Err(err) => {
+                debug!(
+                    "Failed to fetch ICY metadata for {}: {}",
+                    self.station.name, err
+                );
+                return;
+            }
+        };
+
+        if !self.station.set_song(song.clone()) {
+            return;
+        }
+
+        info!("{} is now playing '{}'", self.station.name, song);
+
+        let (Some(message_id), Some(track)) = (self.station.announcement(), track) else {
+            return;
+        };
+
+        let metadata = track.data::<TrackMetadata>();
+        let embed = create_now_playing_embed(
+            &metadata.title,
+            metadata.thumbnail_url.as_deref().unwrap_or_default(),
+            &metadata,
+        );
+
+        if let Err(err) = self
+            .channel_id
+            .edit_message(&self.http, message_id, EditMessage::new().embed(embed))
+            .await
+        {
+            warn!("Failed to update now playing message: {}", err);
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for StationTitlePoller {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let EventContext::Track(track_list) = ctx else {
+            return None;
+        };
+
+        // Stations can take a while to answer, which must not hold up the
+        // driver's other events
+        let track = track_list.first().map(|(_, track)| (*track).clone());
+        tokio::spawn(self.clone().refresh(track));
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server;
+
+    #[test]
+    fn test_stream_kind() {
+        let hls = Url::parse("https://example.com/live/stream.m3u8?token=1").unwrap();
+        let icecast = Url::parse("http://example.com:8000/lofi").unwrap();
+
+        assert_eq!(stream_kind(&hls), StreamKind::Hls);
+        assert_eq!(stream_kind(&icecast), StreamKind::Direct);
+    }
+
+    #[test]
+    fn test_invalid_stream_urls() {
+        assert!(parse_stream_url("ftp://example.com/stream").is_err());
+        assert!(parse_stream_url("lofi radio").is_err());
+        assert!(parse_stream_url(" https://example.com/stream ").is_ok());
+    }
+
+    #[test]
+    fn test_parse_pls_playlist() {
+        let pls = "[playlist]\nNumberOfEntries=2\nFile1=http://example.com:8000/lofi\nTitle1=Lofi\nFile2=http://backup.example.com/lofi\n";
+
+        assert_eq!(
+            parse_playlist_file(pls),
+            Some("http://example.com:8000/lofi".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_m3u_playlist() {
+        let m3u = "#EXTM3U\n#EXTINF:-1,Lofi Radio\nhttps://example.com/lofi.mp3\n";
+
+        assert_eq!(
+            parse_playlist_file(m3u),
+            Some("https://example.com/lofi.mp3".to_string())
+        );
+        assert_eq!(parse_playlist_file("#EXTM3U\n"), None);
+    }
+
+    #[test]
+    fn test_parse_stream_title() {
+        assert_eq!(
+            parse_stream_title(b"StreamTitle='Artist - Song';StreamUrl='';\0\0\0"),
+            Some("Artist - Song".to_string())
+        );
+        assert_eq!(parse_stream_title(b"StreamTitle='';\0\0"), None);
+        assert_eq!(parse_stream_title(b"\0\0\0\0"), None);
+    }
+
+    #[test]
+    fn test_station_song_changes() {
+        let station = RadioStation::new("Lofi".into(), "http://example.com".into(), None);
+
+        assert!(station.set_song("One".into()));
+        assert!(!station.set_song("One".into()));
+        assert!(station.set_song("Two".into()));
+        assert_eq!(station.song(), Some("Two".to_string()));
+    }
+
+    #[test]
+    fn test_presets() {
+        let guild = GuildId::new(1);
+        let mut presets = RadioPresets::default();
+        let preset = |url: &str| StationPreset {
+            name: "Lofi".to_string(),
+            url: url.to_string(),
+        };
+
+        assert!(!presets.upsert(guild, preset("http://one")));
+        assert!(presets.upsert(guild, preset("http://two")));
+        assert_eq!(presets.list(guild).len(), 1);
+        assert_eq!(presets.find(guild, "lofi").unwrap().url, "http://two");
+        assert!(presets.list(GuildId::new(2)).is_empty());
+
+        assert!(presets.remove(guild, "LOFI"));
+        assert!(!presets.remove(guild, "lofi"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_icy_info() {
+        let mut body = vec![0u8; 16];
+        let metadata = b"StreamTitle='Artist - Song';";
+        let blocks = metadata.len().div_ceil(16);
+        body.push(blocks as u8);
+        body.extend_from_slice(metadata);
+        body.resize(16 + 1 + blocks * 16, 0);
+        body.extend_from_slice(&[0u8; 16]);
+
+        let url = test_server::serve_with_headers(
+            200,
+            "audio/mpeg",
+            &[("icy-metaint", "16"), ("icy-name", "Lofi Radio")],
+            body,
+        )
+        .await;
+
+        let info = fetch_icy_info(&HttpClient::new(), &url).await.unwrap();
+
+        assert_eq!(
+            info,
+            IcyInfo {
+                name: Some("Lofi Radio".to_string()),
+                song: Some("Artist - Song".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_icy_info_without_metadata() {
+        let url = test_server::serve(200, "audio/mpeg", vec![0u8; 64]).await;
+
+        let info = fetch_icy_info(&HttpClient::new(), &url).await.unwrap();
+
+        assert_eq!(info, IcyInfo::default());
+    }
+}
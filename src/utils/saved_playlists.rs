@@ -0,0 +1,312 @@
+//! Playlists kept by the bot itself rather than on YouTube.
+
+use std::{collections::HashMap, io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{GuildId, UserId},
+    prelude::TypeMapKey,
+};
+use tracing::error;
+
+use crate::utils::{
+    json_store::{self, data_file},
+    track_utils::TrackMetadata,
+};
+
+const PLAYLISTS_FILE: &str = "playlists.json";
+
+/// Most playlists one owner can keep, which is also the most options a
+/// select menu can offer
+pub const MAX_PLAYLISTS: usize = 25;
+/// Most tracks one playlist can hold
+pub const MAX_PLAYLIST_TRACKS: usize = 500;
+/// Longest playlist name, short enough for embeds and menu labels
+pub const MAX_NAME_LENGTH: u16 = 50;
+
+/// A track saved to a playlist. Only tracks with a source URL can be saved
+/// since that is what gets queued again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedTrack {
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub duration: Option<Duration>,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+impl SavedTrack {
+    pub fn from_metadata(metadata: &TrackMetadata) -> Option<Self> {
+        // Live streams and radio stations have no lasting source to save
+        if metadata.is_live {
+            return None;
+        }
+
+        Some(Self {
+            title: metadata.title.clone(),
+            url: metadata.source_url.clone()?,
+            duration: metadata.duration,
+            thumbnail_url: metadata.thumbnail_url.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedPlaylist {
+    pub name: String,
+    pub tracks: Vec<SavedTrack>,
+}
+
+/// Why a playlist couldn't be changed
+#[derive(Debug, PartialEq)]
+pub enum PlaylistError {
+    NotFound,
+    AlreadyExists,
+    TooManyPlaylists,
+    PlaylistFull,
+    NoSuchTrack,
+}
+
+impl PlaylistError {
+    pub fn description(&self, name: &str) -> String {
+        match self {
+            PlaylistError::NotFound => format!("There is no playlist called **{}**!", name),
+            PlaylistError::AlreadyExists => {
+                format!("There is already a playlist called **{}**!", name)
+            }
+            PlaylistError::TooManyPlaylists => format!(
+                "**Too many playlists!** Delete one first, up to {} can be kept",
+                MAX_PLAYLISTS
+            ),
+            PlaylistError::PlaylistFull => format!(
+                "**{}** is full! Playlists can hold up to {} tracks",
+                name, MAX_PLAYLIST_TRACKS
+            ),
+            PlaylistError::NoSuchTrack => format!("**{}** has no track at that position!", name),
+        }
+    }
+}
+
+/// The playlists of one owner, looked up by name ignoring case
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SavedPlaylists {
+    playlists: Vec<SavedPlaylist>,
+}
+
+impl SavedPlaylists {
+    pub fn list(&self) -> &[SavedPlaylist] {
+        &self.playlists
+    }
+
+    pub fn find(&self, name: &str) -> Option<&SavedPlaylist> {
+        self.playlists
+            .iter()
+            .find(|playlist| playlist.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut SavedPlaylist, PlaylistError> {
+        self.playlists
+            .iter_mut()
+            .find(|playlist| playlist.name.eq_ignore_ascii_case(name.trim()))
+            .ok_or(PlaylistError::NotFound)
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<(), PlaylistError> {
+        if self.find(name).is_some() {
+            return Err(PlaylistError::AlreadyExists);
+        }
+
+        if self.playlists.len() >= MAX_PLAYLISTS {
+            return Err(PlaylistError::TooManyPlaylists);
+        }
+
+        self.playlists.push(SavedPlaylist {
+            name: name.trim().to_string(),
+            tracks: Vec::new(),
+        });
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<SavedPlaylist, PlaylistError> {
+        let index = self
+            .playlists
+            .iter()
+            .position(|playlist| playlist.name.eq_ignore_ascii_case(name.trim()))
+            .ok_or(PlaylistError::NotFound)?;
+
+        Ok(self.playlists.remove(index))
+    }
+
+    /// Append tracks to a playlist. Returns how many fit.
+    pub fn add(&mut self, name: &str, tracks: Vec<SavedTrack>) -> Result<usize, PlaylistError> {
+        let playlist = self.find_mut(name)?;
+
+        let space = MAX_PLAYLIST_TRACKS.saturating_sub(playlist.tracks.len());
+        if space == 0 {
+            return Err(PlaylistError::PlaylistFull);
+        }
+
+        let added = tracks.len().min(space);
+        playlist.tracks.extend(tracks.into_iter().take(added));
+
+        Ok(added)
+    }
+
+    /// Remove the track at a 1-based `position`
+    pub fn remove(&mut self, name: &str, position: usize) -> Result<SavedTrack, PlaylistError> {
+        let playlist = self.find_mut(name)?;
+
+        if position == 0 || position > playlist.tracks.len() {
+            return Err(PlaylistError::NoSuchTrack);
+        }
+
+        Ok(playlist.tracks.remove(position - 1))
+    }
+}
+
+/// Whose playlists these are. Users keep their own, and each server has
+/// shared ones that anyone can play and DJs can edit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaylistOwner {
+    User(UserId),
+    Guild(GuildId),
+}
+
+/// Every user's and server's playlists, stored in the data directory
+#[derive(Default, Serialize, Deserialize)]
+pub struct PlaylistStore {
+    #[serde(default)]
+    users: HashMap<u64, SavedPlaylists>,
+    #[serde(default)]
+    guilds: HashMap<u64, SavedPlaylists>,
+}
+
+impl PlaylistStore {
+    pub fn load() -> Self {
+        json_store::load(&data_file(PLAYLISTS_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        json_store::save(&data_file(PLAYLISTS_FILE), self)
+    }
+
+    pub fn playlists(&self, owner: PlaylistOwner) -> Option<&SavedPlaylists> {
+        match owner {
+            PlaylistOwner::User(user_id) => self.users.get(&user_id.get()),
+            PlaylistOwner::Guild(guild_id) => self.guilds.get(&guild_id.get()),
+        }
+    }
+
+    pub fn playlists_mut(&mut self, owner: PlaylistOwner) -> &mut SavedPlaylists {
+        match owner {
+            PlaylistOwner::User(user_id) => self.users.entry(user_id.get()).or_default(),
+            PlaylistOwner::Guild(guild_id) => self.guilds.entry(guild_id.get()).or_default(),
+        }
+    }
+}
+
+/// Save the playlists, logging instead of failing since the change still
+/// applies until the bot restarts
+pub fn save_playlists(store: &PlaylistStore) {
+    if let Err(err) = store.save() {
+        error!("Failed to save playlists: {}", err);
+    }
+}
+
+pub struct PlaylistStoreKey;
+
+impl TypeMapKey for PlaylistStoreKey {
+    type Value = PlaylistStore;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str) -> SavedTrack {
+        SavedTrack {
+            title: title.to_string(),
+            url: format!("https://www.youtube.com/watch?v={}", title),
+            duration: None,
+            thumbnail_url: None,
+        }
+    }
+
+    #[test]
+    fn test_create_add_remove_delete() {
+        let mut playlists = SavedPlaylists::default();
+
+        playlists.create("Road Trip").unwrap();
+        assert_eq!(
+            playlists.create("road trip"),
+            Err(PlaylistError::AlreadyExists)
+        );
+
+        assert_eq!(
+            playlists.add("ROAD TRIP", vec![track("a"), track("b")]),
+            Ok(2)
+        );
+        assert_eq!(playlists.remove("road trip", 1).unwrap().title, "a");
+        assert_eq!(
+            playlists.remove("road trip", 2),
+            Err(PlaylistError::NoSuchTrack)
+        );
+        assert_eq!(playlists.find("Road Trip").unwrap().tracks.len(), 1);
+
+        assert!(playlists.delete("road trip").is_ok());
+        assert_eq!(playlists.delete("road trip"), Err(PlaylistError::NotFound));
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut playlists = SavedPlaylists::default();
+
+        for i in 0..MAX_PLAYLISTS {
+            playlists.create(&format!("list {}", i)).unwrap();
+        }
+        assert_eq!(
+            playlists.create("one more"),
+            Err(PlaylistError::TooManyPlaylists)
+        );
+
+        let tracks = vec![track("x"); MAX_PLAYLIST_TRACKS + 5];
+        assert_eq!(playlists.add("list 0", tracks), Ok(MAX_PLAYLIST_TRACKS));
+        assert_eq!(
+            playlists.add("list 0", vec![track("y")]),
+            Err(PlaylistError::PlaylistFull)
+        );
+    }
+
+    #[test]
+    fn test_user_and_guild_playlists_are_separate() {
+        let mut store = PlaylistStore::default();
+        let user = PlaylistOwner::User(UserId::new(1));
+        let guild = PlaylistOwner::Guild(GuildId::new(1));
+
+        store.playlists_mut(user).create("Mine").unwrap();
+        store.playlists_mut(guild).create("Ours").unwrap();
+
+        assert!(store.playlists(user).unwrap().find("Ours").is_none());
+        assert!(store.playlists(guild).unwrap().find("Ours").is_some());
+    }
+
+    #[test]
+    fn test_only_tracks_with_a_source_can_be_saved() {
+        let mut metadata = TrackMetadata {
+            title: String::from("Song"),
+            source_url: Some(String::from("https://www.youtube.com/watch?v=abc")),
+            ..Default::default()
+        };
+        assert_eq!(SavedTrack::from_metadata(&metadata).unwrap().title, "Song");
+
+        metadata.is_live = true;
+        assert!(SavedTrack::from_metadata(&metadata).is_none());
+
+        metadata.is_live = false;
+        metadata.source_url = None;
+        assert!(SavedTrack::from_metadata(&metadata).is_none());
+    }
+}
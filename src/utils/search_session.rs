@@ -0,0 +1,232 @@
+//! Results of a `/search`, kept while the user pages through them and picks
+//! what to queue. Searches expire after a while and their message stops
+//! taking picks.
+
+use std::{
+    collections::VecDeque,
+    env,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serenity::{
+    all::{ChannelId, MessageId, UserId},
+    prelude::TypeMapKey,
+};
+
+use crate::utils::youtube_search::{SearchFilters, SearchResult};
+
+/// Discord allows at most 25 options in a select menu
+pub const RESULTS_PER_PAGE: usize = 25;
+/// Searches remembered at once, the oldest are forgotten first
+const MAX_SESSIONS: usize = 100;
+
+/// How long search results can be picked from. Set SEARCH_EXPIRY_MINUTES to
+/// override the default of 10 minutes.
+pub fn search_expiry() -> Duration {
+    let minutes = env::var("SEARCH_EXPIRY_MINUTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(10);
+
+    Duration::from_secs(minutes * 60)
+}
+
+pub struct SearchSession {
+    pub query: String,
+    pub filters: SearchFilters,
+    pub results: Vec<SearchResult>,
+    /// The member who searched, only they and DJs can pick
+    pub owner: UserId,
+    pub created: Instant,
+}
+
+impl SearchSession {
+    pub fn page_count(&self) -> usize {
+        self.results.len().div_ceil(RESULTS_PER_PAGE).max(1)
+    }
+
+    /// The results on `page` along with the index of the first one
+    pub fn page(&self, page: usize) -> (usize, &[SearchResult]) {
+        let start = (page * RESULTS_PER_PAGE).min(self.results.len());
+        let end = (start + RESULTS_PER_PAGE).min(self.results.len());
+        (start, &self.results[start..end])
+    }
+
+    pub fn find(&self, video_id: &str) -> Option<&SearchResult> {
+        self.results.iter().find(|result| result.id == video_id)
+    }
+
+    pub fn is_expired(&self, expiry: Duration) -> bool {
+        self.created.elapsed() >= expiry
+    }
+}
+
+/// A search along with the message its results were posted in
+pub struct ActiveSession {
+    pub id: u64,
+    pub session: Arc<SearchSession>,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    /// The page currently shown in the message
+    pub page: usize,
+}
+
+/// Searches that can still be paged and picked from, keyed by the id of the
+/// `/search` interaction that started them
+#[derive(Default)]
+pub struct SearchSessions {
+    sessions: VecDeque<ActiveSession>,
+}
+
+impl SearchSessions {
+    /// Remember a search, handing back the oldest one when it had to be
+    /// forgotten to make room
+    pub fn start(
+        &mut self,
+        session_id: u64,
+        session: SearchSession,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Option<ActiveSession> {
+        let forgotten = if self.sessions.len() >= MAX_SESSIONS {
+            self.sessions.pop_front()
+        } else {
+            None
+        };
+
+        self.sessions.push_back(ActiveSession {
+            id: session_id,
+            session: Arc::new(session),
+            channel_id,
+            message_id,
+            page: 0,
+        });
+
+        forgotten
+    }
+
+    pub fn get(&self, session_id: u64) -> Option<Arc<SearchSession>> {
+        self.sessions
+            .iter()
+            .find(|active| active.id == session_id)
+            .map(|active| active.session.clone())
+    }
+
+    /// Remember which page the message shows, so it stays on it once expired
+    pub fn show_page(&mut self, session_id: u64, page: usize) {
+        if let Some(active) = self
+            .sessions
+            .iter_mut()
+            .find(|active| active.id == session_id)
+        {
+            active.page = page;
+        }
+    }
+
+    /// Forget a search, `None` when it was already forgotten
+    pub fn end(&mut self, session_id: u64) -> Option<ActiveSession> {
+        let index = self
+            .sessions
+            .iter()
+            .position(|active| active.id == session_id)?;
+
+        self.sessions.remove(index)
+    }
+}
+
+pub struct SearchSessionsKey;
+
+impl TypeMapKey for SearchSessionsKey {
+    type Value = SearchSessions;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(count: usize) -> SearchSession {
+        SearchSession {
+            query: String::from("query"),
+            filters: SearchFilters::default(),
+            results: (0..count)
+                .map(|i| SearchResult {
+                    id: format!("video{}", i),
+                    title: format!("Video {}", i),
+                    duration: None,
+                    thumbnail_url: None,
+                    channel: None,
+                    is_live: false,
+                    uploaded_at: None,
+                })
+                .collect(),
+            owner: UserId::new(1),
+            created: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_results_are_paged() {
+        let session = session(30);
+
+        assert_eq!(session.page_count(), 2);
+        assert_eq!(session.page(0).1.len(), RESULTS_PER_PAGE);
+
+        let (first, results) = session.page(1);
+        assert_eq!(first, 25);
+        assert_eq!(results.len(), 5);
+        assert!(session.page(2).1.is_empty());
+
+        assert_eq!(
+            session.find("video29").map(|result| result.title.as_str()),
+            Some("Video 29")
+        );
+    }
+
+    #[test]
+    fn test_oldest_sessions_are_forgotten() {
+        let mut sessions = SearchSessions::default();
+
+        for id in 0..MAX_SESSIONS as u64 {
+            let forgotten = sessions.start(id, session(1), ChannelId::new(2), MessageId::new(3));
+            assert!(forgotten.is_none());
+        }
+
+        let forgotten = sessions.start(
+            MAX_SESSIONS as u64,
+            session(1),
+            ChannelId::new(2),
+            MessageId::new(3),
+        );
+        assert_eq!(forgotten.map(|active| active.id), Some(0));
+
+        assert!(sessions.get(0).is_none());
+        assert!(sessions.get(1).is_some());
+        assert!(sessions.get(MAX_SESSIONS as u64).is_some());
+    }
+
+    #[test]
+    fn test_ended_sessions_keep_their_page() {
+        let mut sessions = SearchSessions::default();
+        sessions.start(7, session(30), ChannelId::new(2), MessageId::new(3));
+        sessions.show_page(7, 1);
+
+        let ended = sessions.end(7).unwrap();
+        assert_eq!(ended.session.results.len(), 30);
+        assert_eq!(ended.message_id, MessageId::new(3));
+        assert_eq!(ended.page, 1);
+
+        assert!(sessions.get(7).is_none());
+        assert!(sessions.end(7).is_none());
+    }
+
+    #[test]
+    fn test_sessions_expire() {
+        let mut search = session(1);
+        assert!(!search.is_expired(Duration::from_secs(60)));
+
+        search.created -= Duration::from_secs(61);
+        assert!(search.is_expired(Duration::from_secs(60)));
+    }
+}
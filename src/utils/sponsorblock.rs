@@ -0,0 +1,261 @@
+use std::{env, time::Duration};
+
+use reqwest::{Client as HttpClient, StatusCode};
+use serde::Deserialize;
+use serenity::async_trait;
+use songbird::{Event, EventContext, EventHandler};
+use tracing::{debug, info};
+
+use crate::utils::format::format_duration;
+
+const DEFAULT_API_URL: &str = "https://sponsor.ajay.app";
+const DEFAULT_CATEGORIES: &str = "sponsor,selfpromo,interaction,intro,outro,music_offtopic";
+
+/// A section of a video that is skipped during playback
+#[derive(Clone, Debug, PartialEq)]
+pub struct SponsorSegment {
+    pub category: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiSegment {
+    category: String,
+    segment: [f64; 2],
+    #[serde(rename = "actionType", default = "default_action_type")]
+    action_type: String,
+}
+
+fn default_action_type() -> String {
+    String::from("skip")
+}
+
+/// SponsorBlock settings read from the environment.
+///
+/// Set SPONSORBLOCK_ENABLED=true to turn skipping on. SPONSORBLOCK_API_URL
+/// points at a different server and SPONSORBLOCK_CATEGORIES is a comma
+/// separated list of the categories to skip.
+pub struct SponsorBlockConfig {
+    pub api_url: String,
+    pub categories: Vec<String>,
+}
+
+impl SponsorBlockConfig {
+    /// Returns `None` when SponsorBlock is disabled
+    pub fn from_env() -> Option<Self> {
+        let enabled = env::var("SPONSORBLOCK_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        if !enabled {
+            return None;
+        }
+
+        let api_url = env::var("SPONSORBLOCK_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.into());
+        let categories = env::var("SPONSORBLOCK_CATEGORIES")
+            .unwrap_or_else(|_| DEFAULT_CATEGORIES.into())
+            .split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+
+        Some(Self {
+            api_url,
+            categories,
+        })
+    }
+}
+
+/// Fetch the skippable segments of a video, sorted by start time.
+/// Videos without any submitted segments return an empty list.
+pub async fn fetch_segments(
+    client: &HttpClient,
+    config: &SponsorBlockConfig,
+    video_id: &str,
+) -> Result<Vec<SponsorSegment>, reqwest::Error> {
+    let categories = serde_json::to_string(&config.categories).unwrap_or_default();
+
+    let response = client
+        .get(format!(
+            "{}/api/skipSegments",
+            config.api_url.trim_end_matches('/')
+        ))
+        .query(&[("videoID", video_id), ("categories", categories.as_str())])
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?;
+
+    // SponsorBlock answers 404 when a video has no segments
+    if response.status() == StatusCode::NOT_FOUND {
+        debug!("No SponsorBlock segments for video {}", video_id);
+        return Ok(Vec::new());
+    }
+
+    let body = response.error_for_status()?.text().await?;
+
+    Ok(parse_segments(&body))
+}
+
+fn parse_segments(body: &str) -> Vec<SponsorSegment> {
+    let api_segments: Vec<ApiSegment> = serde_json::from_str(body).unwrap_or_default();
+
+    let mut segments: Vec<SponsorSegment> = api_segments
+        .into_iter()
+        .filter(|s| s.action_type == "skip" && s.segment[1] > s.segment[0] && s.segment[0] >= 0.0)
+        .map(|s| SponsorSegment {
+            category: s.category,
+            start: Duration::from_secs_f64(s.segment[0]),
+            end: Duration::from_secs_f64(s.segment[1]),
+        })
+        .collect();
+
+    segments.sort_by_key(|s| s.start);
+    segments
+}
+
+/// Find the segment that contains `position`, if any
+pub fn segment_at(segments: &[SponsorSegment], position: Duration) -> Option<&SponsorSegment> {
+    segments
+        .iter()
+        .find(|s| position >= s.start && position < s.end)
+}
+
+/// Human readable name of a SponsorBlock category
+pub fn category_label(category: &str) -> &str {
+    match category {
+        "sponsor" => "Sponsor",
+        "selfpromo" => "Self promotion",
+        "interaction" => "Interaction reminder",
+        "intro" => "Intro",
+        "outro" => "Outro",
+        "preview" => "Preview",
+        "music_offtopic" => "Non-music",
+        "filler" => "Filler",
+        other => other,
+    }
+}
+
+/// One line per segment, e.g. "⏩ Sponsor (00:30 - 01:10)"
+pub fn describe_segments<'a>(segments: impl IntoIterator<Item = &'a SponsorSegment>) -> String {
+    segments
+        .into_iter()
+        .map(|s| {
+            format!(
+                "⏩ {} ({} - {})",
+                category_label(&s.category),
+                format_duration(s.start),
+                format_duration(s.end)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Track event that seeks past SponsorBlock segments while the track plays.
+/// Register it as a periodic event on the track.
+pub struct SegmentSkipper {
+    pub segments: Vec<SponsorSegment>,
+}
+
+#[async_trait]
+impl EventHandler for SegmentSkipper {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let EventContext::Track(track_list) = ctx else {
+            return None;
+        };
+
+        for (state, track) in track_list.iter() {
+            if let Some(segment) = segment_at(&self.segments, state.position) {
+                info!(
+                    "Skipping {} segment from {} to {}",
+                    segment.category,
+                    format_duration(segment.start),
+                    format_duration(segment.end)
+                );
+                // The callback only reports the result, no need to wait on it
+                drop(track.seek(segment.end));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server;
+
+    const SEGMENTS_JSON: &str = r#"[
+        {"category": "intro", "actionType": "skip", "segment": [0.0, 12.5], "UUID": "b"},
+        {"category": "sponsor", "actionType": "skip", "segment": [60.0, 95.0], "UUID": "a"},
+        {"category": "sponsor", "actionType": "mute", "segment": [100.0, 110.0], "UUID": "c"}
+    ]"#;
+
+    fn config(api_url: String) -> SponsorBlockConfig {
+        SponsorBlockConfig {
+            api_url,
+            categories: vec!["sponsor".to_string(), "intro".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_parse_segments_keeps_skips_in_order() {
+        let segments = parse_segments(SEGMENTS_JSON);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].category, "intro");
+        assert_eq!(segments[1].start, Duration::from_secs(60));
+        assert_eq!(segments[1].end, Duration::from_secs(95));
+    }
+
+    #[test]
+    fn test_parse_segments_invalid_body() {
+        assert!(parse_segments("not json").is_empty());
+    }
+
+    #[test]
+    fn test_segment_at() {
+        let segments = parse_segments(SEGMENTS_JSON);
+
+        assert_eq!(
+            segment_at(&segments, Duration::from_secs(5)).map(|s| s.category.as_str()),
+            Some("intro")
+        );
+        assert!(segment_at(&segments, Duration::from_secs(30)).is_none());
+        assert!(segment_at(&segments, Duration::from_secs(95)).is_none());
+    }
+
+    #[test]
+    fn test_describe_segments() {
+        let segments = parse_segments(SEGMENTS_JSON);
+
+        assert_eq!(
+            describe_segments(&segments),
+            "⏩ Intro (00:00 - 00:12)\n⏩ Sponsor (01:00 - 01:35)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_segments_from_local_server() {
+        let api_url = test_server::serve(200, "application/json", SEGMENTS_JSON).await;
+
+        let segments = fetch_segments(&HttpClient::new(), &config(api_url), "abc123")
+            .await
+            .unwrap();
+
+        assert_eq!(segments.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_segments_not_found_is_empty() {
+        let api_url = test_server::serve(404, "text/plain", "Not Found").await;
+
+        let segments = fetch_segments(&HttpClient::new(), &config(api_url), "abc123")
+            .await
+            .unwrap();
+
+        assert!(segments.is_empty());
+    }
+}
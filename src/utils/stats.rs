@@ -0,0 +1,405 @@
+//! Listening statistics: every track that played in a guild, who asked for
+//! it and how long it ran, summarised by `/stats`.
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{GuildId, UserId},
+    prelude::{RwLock, TypeMap, TypeMapKey},
+};
+use tracing::error;
+
+use crate::utils::{
+    json_store::{self, data_file},
+    track_utils::TrackMetadata,
+};
+
+const STATS_FILE: &str = "stats.json";
+
+/// Plays remembered per guild, the oldest are forgotten first
+pub const MAX_PLAYS_PER_GUILD: usize = 10_000;
+/// Entries listed in each ranking of `/stats`
+pub const TOP_COUNT: usize = 5;
+/// How often newly recorded plays are written to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// When a queued track started playing, set by the track's play handler.
+/// Clones share the start since they describe the same play.
+#[derive(Clone, Default)]
+pub struct PlayStart(Arc<Mutex<Option<u64>>>);
+
+impl PlayStart {
+    pub fn mark(&self) {
+        if let Ok(mut start) = self.0.lock() {
+            start.get_or_insert_with(unix_now);
+        }
+    }
+
+    /// Start over unset for a new play of the track, leaving copies made
+    /// for the previous play as they are
+    pub fn restart(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn get(&self) -> Option<u64> {
+        *self.0.lock().ok()?
+    }
+}
+
+/// One track that played
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayRecord {
+    pub title: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub requested_by: Option<u64>,
+    /// Unix time the track started
+    pub started_at: u64,
+    /// Seconds of audio actually played
+    pub played_secs: u64,
+    /// Stopped early by a skip or `/clear` rather than playing to the end
+    pub skipped: bool,
+}
+
+impl PlayRecord {
+    pub fn new(metadata: &TrackMetadata, played: Duration, skipped: bool) -> Self {
+        let played_secs = played.as_secs();
+
+        Self {
+            title: metadata.title.clone(),
+            url: metadata.source_url.clone(),
+            requested_by: metadata.requested_by.map(|user_id| user_id.get()),
+            started_at: metadata
+                .play_start
+                .get()
+                .unwrap_or_else(|| unix_now().saturating_sub(played_secs)),
+            played_secs,
+            skipped,
+        }
+    }
+
+    /// Plays of the same track share a key, even if titles differ
+    fn track_key(&self) -> &str {
+        self.url.as_deref().unwrap_or(&self.title)
+    }
+}
+
+/// How far back `/stats` looks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatsWindow {
+    Day,
+    Week,
+    Month,
+    AllTime,
+}
+
+impl StatsWindow {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            "all" => Some(Self::AllTime),
+            _ => None,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Day => "the last 24 hours",
+            Self::Week => "the last 7 days",
+            Self::Month => "the last 30 days",
+            Self::AllTime => "all time",
+        }
+    }
+
+    /// Earliest start time inside the window
+    pub fn since(self, now: u64) -> u64 {
+        let days = match self {
+            Self::Day => 1,
+            Self::Week => 7,
+            Self::Month => 30,
+            Self::AllTime => return 0,
+        };
+
+        now.saturating_sub(days * 24 * 60 * 60)
+    }
+}
+
+/// What a guild listened to over a window
+#[derive(Debug, Default, PartialEq)]
+pub struct StatsSummary {
+    pub plays: usize,
+    pub listened: Duration,
+    /// Title and number of plays
+    pub top_tracks: Vec<(String, usize)>,
+    /// Requester and number of tracks they queued
+    pub top_requesters: Vec<(UserId, usize)>,
+    /// Title and number of skips
+    pub most_skipped: Vec<(String, usize)>,
+}
+
+/// Highest counts first, ties in the order they were first seen
+fn ranked<K: Clone + Eq + std::hash::Hash>(keys: impl Iterator<Item = K>) -> Vec<(K, usize)> {
+    let mut order: Vec<K> = Vec::new();
+    let mut counts: HashMap<K, usize> = HashMap::new();
+
+    for key in keys {
+        let count = counts.entry(key.clone()).or_insert(0);
+        if *count == 0 {
+            order.push(key);
+        }
+        *count += 1;
+    }
+
+    let mut ranking: Vec<(K, usize)> = order
+        .into_iter()
+        .map(|key| {
+            let count = counts[&key];
+            (key, count)
+        })
+        .collect();
+    ranking.sort_by_key(|(_, count)| Reverse(*count));
+    ranking.truncate(TOP_COUNT);
+    ranking
+}
+
+/// Summarise the plays that started at or after `since`
+pub fn summarize<'a>(plays: impl Iterator<Item = &'a PlayRecord>, since: u64) -> StatsSummary {
+    let plays: Vec<&PlayRecord> = plays.filter(|play| play.started_at >= since).collect();
+
+    // Show the most recent title of each track
+    let titles: HashMap<&str, &str> = plays
+        .iter()
+        .map(|play| (play.track_key(), play.title.as_str()))
+        .collect();
+    let title_of = |(key, count): (&str, usize)| (titles[key].to_string(), count);
+
+    StatsSummary {
+        plays: plays.len(),
+        listened: Duration::from_secs(plays.iter().map(|play| play.played_secs).sum()),
+        top_tracks: ranked(plays.iter().map(|play| play.track_key()))
+            .into_iter()
+            .map(title_of)
+            .collect(),
+        top_requesters: ranked(plays.iter().filter_map(|play| play.requested_by))
+            .into_iter()
+            .map(|(user_id, count)| (UserId::new(user_id), count))
+            .collect(),
+        most_skipped: ranked(
+            plays
+                .iter()
+                .filter(|play| play.skipped)
+                .map(|play| play.track_key()),
+        )
+        .into_iter()
+        .map(title_of)
+        .collect(),
+    }
+}
+
+/// Every guild's plays, oldest first, stored in the data directory
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ListeningStats {
+    guilds: HashMap<u64, VecDeque<PlayRecord>>,
+    /// Set when plays were recorded since the file was last written
+    #[serde(skip)]
+    unsaved: bool,
+}
+
+impl ListeningStats {
+    pub fn load() -> Self {
+        json_store::load(&data_file(STATS_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        json_store::save(&data_file(STATS_FILE), self)
+    }
+
+    pub fn record(&mut self, guild_id: GuildId, play: PlayRecord) {
+        let plays = self.guilds.entry(guild_id.get()).or_default();
+
+        if plays.len() >= MAX_PLAYS_PER_GUILD {
+            plays.pop_front();
+        }
+
+        plays.push_back(play);
+        self.unsaved = true;
+    }
+
+    /// A copy of the plays to write out, when any were recorded since the
+    /// last one was taken
+    pub fn take_unsaved(&mut self) -> Option<ListeningStats> {
+        std::mem::take(&mut self.unsaved).then(|| self.clone())
+    }
+
+    pub fn summary(&self, guild_id: GuildId, since: u64) -> StatsSummary {
+        match self.guilds.get(&guild_id.get()) {
+            Some(plays) => summarize(plays.iter(), since),
+            None => StatsSummary::default(),
+        }
+    }
+}
+
+pub struct ListeningStatsKey;
+
+impl TypeMapKey for ListeningStatsKey {
+    type Value = ListeningStats;
+}
+
+/// Remember a finished play. It is written out by `save_stats`.
+pub async fn record_play(data: &Arc<RwLock<TypeMap>>, guild_id: GuildId, play: PlayRecord) {
+    let mut data = data.write().await;
+    data.get_mut::<ListeningStatsKey>()
+        .expect("Guaranteed to exist in the typemap.")
+        .record(guild_id, play);
+}
+
+/// Write newly recorded plays out every `SAVE_INTERVAL`
+pub async fn save_stats(data: Arc<RwLock<TypeMap>>) {
+    loop {
+        tokio::time::sleep(SAVE_INTERVAL).await;
+        flush_stats(&data).await;
+    }
+}
+
+/// Write out plays recorded since the last save. The file is written on a
+/// blocking thread after the lock is released, so finishing tracks never
+/// waits on the disk.
+pub async fn flush_stats(data: &Arc<RwLock<TypeMap>>) {
+    let unsaved = {
+        let mut data = data.write().await;
+        data.get_mut::<ListeningStatsKey>()
+            .expect("Guaranteed to exist in the typemap.")
+            .take_unsaved()
+    };
+
+    let Some(stats) = unsaved else {
+        return;
+    };
+
+    match tokio::task::spawn_blocking(move || stats.save()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => error!("Failed to save listening stats: {}", err),
+        Err(err) => error!("Failed to save listening stats: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(title: &str, requester: u64, started_at: u64, skipped: bool) -> PlayRecord {
+        PlayRecord {
+            title: title.to_string(),
+            url: Some(format!("https://example.com/{}", title)),
+            requested_by: Some(requester),
+            started_at,
+            played_secs: 60,
+            skipped,
+        }
+    }
+
+    #[test]
+    fn test_summary_ranks_tracks_requesters_and_skips() {
+        let plays = [
+            play("a", 1, 100, false),
+            play("b", 2, 200, true),
+            play("b", 2, 300, true),
+            play("c", 1, 400, false),
+            play("b", 3, 500, false),
+        ];
+
+        let summary = summarize(plays.iter(), 0);
+
+        assert_eq!(summary.plays, 5);
+        assert_eq!(summary.listened, Duration::from_secs(300));
+        assert_eq!(summary.top_tracks[0], (String::from("b"), 3));
+        assert_eq!(summary.top_tracks[1], (String::from("a"), 1));
+        assert_eq!(summary.top_requesters[0], (UserId::new(1), 2));
+        assert_eq!(summary.most_skipped, vec![(String::from("b"), 2)]);
+    }
+
+    #[test]
+    fn test_summary_only_counts_the_window() {
+        let plays = [play("old", 1, 100, false), play("new", 1, 1000, false)];
+
+        let summary = summarize(plays.iter(), 500);
+
+        assert_eq!(summary.plays, 1);
+        assert_eq!(summary.top_tracks, vec![(String::from("new"), 1)]);
+    }
+
+    #[test]
+    fn test_windows() {
+        let now = 100 * 24 * 60 * 60;
+
+        assert_eq!(StatsWindow::Day.since(now), now - 24 * 60 * 60);
+        assert_eq!(StatsWindow::AllTime.since(now), 0);
+        assert_eq!(StatsWindow::parse("month"), Some(StatsWindow::Month));
+    }
+
+    #[test]
+    fn test_oldest_plays_are_forgotten() {
+        let mut stats = ListeningStats::default();
+        let guild_id = GuildId::new(1);
+
+        for i in 0..MAX_PLAYS_PER_GUILD + 1 {
+            stats.record(guild_id, play("a", 1, i as u64, false));
+        }
+
+        let summary = stats.summary(guild_id, 0);
+        assert_eq!(summary.plays, MAX_PLAYS_PER_GUILD);
+        // The play that started at 0 was the one dropped
+        assert_eq!(stats.summary(guild_id, 1).plays, MAX_PLAYS_PER_GUILD);
+    }
+
+    #[test]
+    fn test_play_start_is_set_once_and_restarted_explicitly() {
+        let mut start = PlayStart::default();
+        assert_eq!(start.get(), None);
+
+        start.mark();
+        let first = start.get();
+        start.mark();
+
+        assert!(first.is_some());
+        assert_eq!(start.get(), first);
+
+        let previous_play = start.clone();
+        assert_eq!(previous_play.get(), first);
+
+        start.restart();
+        assert_eq!(start.get(), None);
+        assert_eq!(previous_play.get(), first);
+    }
+
+    #[test]
+    fn test_recorded_plays_are_taken_for_saving_once() {
+        let mut stats = ListeningStats::default();
+        assert!(stats.take_unsaved().is_none());
+
+        stats.record(GuildId::new(1), play("a", 1, 100, false));
+
+        let unsaved = stats.take_unsaved().unwrap();
+        assert_eq!(unsaved.summary(GuildId::new(1), 0).plays, 1);
+        assert!(stats.take_unsaved().is_none());
+    }
+}
@@ -0,0 +1,57 @@
+//! Tiny local HTTP server used by tests in place of external APIs.
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Serve `body` with the given status to every request on a random local
+/// port. Returns the base URL, e.g. `http://127.0.0.1:12345`.
+pub async fn serve(status: u16, content_type: &'static str, body: impl Into<Vec<u8>>) -> String {
+    serve_with_headers(status, content_type, &[], body).await
+}
+
+/// Like `serve`, with extra response headers
+pub async fn serve_with_headers(
+    status: u16,
+    content_type: &'static str,
+    headers: &'static [(&'static str, &'static str)],
+    body: impl Into<Vec<u8>>,
+) -> String {
+    let body = body.into();
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind test server");
+    let address = listener.local_addr().expect("Test server has an address");
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            // Read until the end of the request headers
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buffer[..n]),
+                }
+            }
+
+            let extra_headers: String = headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                .collect();
+            let head = format!(
+                "HTTP/1.1 {} Test\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                status,
+                content_type,
+                body.len(),
+                extra_headers
+            );
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(&body).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+
+    format!("http://{}", address)
+}
@@ -1,82 +1,363 @@
-use serenity::all::{Color, CommandInteraction, ComponentInteraction, Context, CreateEmbed};
-use songbird::{input::Input, tracks::Track};
+use reqwest::Client as HttpClient;
+use serenity::all::{
+    ChannelId, Color, CommandInteraction, ComponentInteraction, Context, CreateEmbed,
+    CreateInteractionResponseFollowup, GuildId, Http, UserId,
+};
+use songbird::{
+    Call, Event,
+    input::{AuxMetadata, AuxMetadataError, Input},
+    tracks::{Track, TrackHandle, TrackQueue},
+};
 use std::{sync::Arc, time::Duration};
 use tracing::{debug, error, info, warn};
 
 use crate::{
+    components::duplicate_buttons::create_queue_anyway_button,
     handlers::track_play::TrackPlayHandler,
-    utils::response::{respond_to_followup, respond_to_followup_component},
+    utils::{
+        chapters::{Chapter, chapters_from_details},
+        duplicates::{DuplicatePolicy, describe_position, find_duplicate, track_key},
+        enqueue_summary::EnqueueSummary,
+        guild_settings::get_guild_settings,
+        lazy_metadata::LazyMetadata,
+        limits::{LimitExceeded, check_queue_limits},
+        metadata_resolver::{MetadataResolver, ResolvedSource},
+        queue_position::{QueuePosition, place_last_track},
+        radio::RadioStation,
+        response::{respond_to_followup, respond_to_followup_component},
+        sponsorblock::{SegmentSkipper, SponsorBlockConfig, SponsorSegment, fetch_segments},
+        stats::PlayStart,
+        type_map::get_http_client,
+        youtube::{fetch_video_details, video_id_from_url},
+    },
 };
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct TrackMetadata {
     pub title: String,
     pub thumbnail_url: Option<String>,
     pub duration: Option<Duration>,
+    /// The page the track was resolved from, used to rebuild the source later
+    pub source_url: Option<String>,
+    /// Sections skipped during playback when SponsorBlock is enabled
+    pub sponsor_segments: Vec<SponsorSegment>,
+    /// Chapters listed on the video, empty when it has none
+    pub chapters: Vec<Chapter>,
+    /// Live streams have no fixed length and can't be seeked or looped
+    pub is_live: bool,
+    /// The user who queued the track, `None` for tracks the bot queued itself
+    pub requested_by: Option<UserId>,
+    /// Set when the track is an internet radio station
+    pub station: Option<RadioStation>,
+    /// When the track started playing, for the listening stats
+    pub play_start: PlayStart,
+    /// Set when the track was queued with placeholder metadata that is
+    /// looked up in the background
+    pub lazy: Option<LazyMetadata>,
 }
 
-pub async fn enqueue_track(ctx: &Context, command: &CommandInteraction, mut source: Input) {
-    let mut response_embed = CreateEmbed::default();
+impl TrackMetadata {
+    /// The metadata looked up for a track queued with a placeholder once it
+    /// arrives, otherwise these. Who asked for the track and when it started
+    /// are kept either way.
+    pub fn latest(&self) -> TrackMetadata {
+        match self.lazy.as_ref().and_then(LazyMetadata::resolved) {
+            Some(resolved) => TrackMetadata {
+                requested_by: self.requested_by,
+                station: self.station.clone(),
+                play_start: self.play_start.clone(),
+                ..resolved
+            },
+            None => self.clone(),
+        }
+    }
+}
 
-    let guild_id = command.guild_id.unwrap();
+/// The latest metadata of a queued track
+pub fn track_metadata(track: &TrackHandle) -> TrackMetadata {
+    track.data::<TrackMetadata>().latest()
+}
 
-    let manager = songbird::get(&ctx)
-        .await
-        .expect("Songbird Voice client placed in at initialization.");
+/// Fetch the auxiliary metadata of a source and convert it into the
+/// `TrackMetadata` attached to every queued track.
+pub async fn resolve_metadata(
+    http_client: &HttpClient,
+    source: &mut Input,
+    guild_id: GuildId,
+) -> TrackMetadata {
+    match try_resolve_metadata(http_client, source, guild_id).await {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            warn!("Failed to fetch track metadata: {}. Using defaults.", err);
+            unresolved_metadata()
+        }
+    }
+}
 
-    if let Some(call) = manager.get(guild_id) {
-        let mut handler = call.lock().await;
+/// Metadata for a track whose details couldn't be looked up
+pub fn unresolved_metadata() -> TrackMetadata {
+    TrackMetadata {
+        title: String::from("Unknown Track Title"),
+        ..Default::default()
+    }
+}
 
-        // Get metadata from the source
-        debug!("Fetching track metadata for guild {}", guild_id);
-        let metadata = match source.aux_metadata().await {
-            Ok(meta) => meta,
-            Err(err) => {
-                warn!("Failed to fetch track metadata: {}. Using defaults.", err);
-                Default::default()
+/// Same as `resolve_metadata`, but fails when the source can't be read, such
+/// as a private or removed video
+pub async fn try_resolve_metadata(
+    http_client: &HttpClient,
+    source: &mut Input,
+    guild_id: GuildId,
+) -> Result<TrackMetadata, AuxMetadataError> {
+    debug!("Fetching track metadata for guild {}", guild_id);
+    let metadata = source.aux_metadata().await?;
+
+    Ok(complete_metadata(http_client, metadata).await)
+}
+
+/// Add what songbird doesn't know about a track, such as its SponsorBlock
+/// segments and chapters
+async fn complete_metadata(http_client: &HttpClient, metadata: AuxMetadata) -> TrackMetadata {
+    let video_id = metadata.source_url.as_deref().and_then(video_id_from_url);
+
+    let sponsor_segments = match (SponsorBlockConfig::from_env(), &video_id) {
+        (Some(config), Some(video_id)) => {
+            match fetch_segments(http_client, &config, video_id).await {
+                Ok(segments) => segments,
+                Err(err) => {
+                    warn!(
+                        "Failed to fetch SponsorBlock segments for {}: {}",
+                        video_id, err
+                    );
+                    Vec::new()
+                }
             }
-        };
+        }
+        _ => Vec::new(),
+    };
+
+    let details = match &video_id {
+        Some(video_id) => fetch_video_details(video_id).await,
+        None => None,
+    };
+
+    let chapters = details
+        .as_ref()
+        .map(chapters_from_details)
+        .unwrap_or_default();
 
-        let track_title = metadata
+    // Sources outside YouTube are only known to be live by their lack of a
+    // duration
+    let is_live = match &details {
+        Some(details) => details.is_live,
+        None => metadata.duration.is_none(),
+    };
+
+    TrackMetadata {
+        title: metadata
             .title
-            .clone()
-            .unwrap_or_else(|| String::from("Unknown Track Title"));
-        let track_thumbnail = metadata.thumbnail.clone();
-        let track_duration = metadata.duration.clone();
+            .unwrap_or_else(|| String::from("Unknown Track Title")),
+        thumbnail_url: metadata.thumbnail,
+        duration: metadata.duration,
+        source_url: metadata.source_url,
+        sponsor_segments,
+        chapters,
+        is_live,
+        requested_by: None,
+        station: None,
+        play_start: PlayStart::default(),
+        lazy: None,
+    }
+}
 
-        info!("Enqueueing track: '{}' in guild {}", track_title, guild_id);
+/// Move the most recently enqueued track to `index` in the queue.
+///
+/// Index 0 is the currently playing track, so 1 is the first upcoming slot.
+/// Indexes past the end of the queue leave the track at the tail.
+pub fn move_last_to(queue: &TrackQueue, index: usize) {
+    queue.modify_queue(|tracks| {
+        if index >= tracks.len() {
+            return;
+        }
 
-        // Create custom metadata to attach to the track
-        let custom_metadata = Arc::new(TrackMetadata {
-            title: track_title.clone(),
-            thumbnail_url: track_thumbnail.clone(),
-            duration: track_duration,
-        });
+        if let Some(track) = tracks.pop_back() {
+            tracks.insert(index, track);
+        }
+    });
+}
 
-        // Create track with attached metadata
-        let track_with_data = Track::new_with_data(source, custom_metadata);
+/// Enqueue a source whose metadata is already known and announce it in
+/// `channel_id` once it starts playing.
+pub async fn enqueue_with_metadata(
+    http: &Arc<Http>,
+    handler: &mut Call,
+    source: Input,
+    mut metadata: TrackMetadata,
+    channel_id: ChannelId,
+) -> TrackHandle {
+    // Metadata copied from an earlier play, e.g. out of the history, must
+    // not carry that play's start over
+    metadata.play_start.restart();
+
+    let lazy = metadata.lazy.clone();
+    // Live streams can't be seeked, so there is nothing to skip
+    let sponsor_segments = if metadata.is_live {
+        Vec::new()
+    } else {
+        metadata.sponsor_segments.clone()
+    };
 
-        // Play/enqueue song
-        let track = handler.enqueue(track_with_data).await;
+    // Create track with attached metadata
+    let track_with_data = Track::new_with_data(source, Arc::new(metadata));
 
+    // Play/enqueue song
+    let track = handler.enqueue(track_with_data).await;
+
+    let _ = track.add_event(
+        Event::Track(songbird::TrackEvent::Playable),
+        TrackPlayHandler {
+            channel_id,
+            http: http.clone(),
+        },
+    );
+
+    // Placeholders are completed once the track is in the queue
+    if let Some(lazy) = lazy {
+        lazy.resolve_in_background(http.clone(), track.clone(), channel_id);
+    }
+
+    if !sponsor_segments.is_empty() {
         let _ = track.add_event(
-            songbird::Event::Track(songbird::TrackEvent::Playable),
-            TrackPlayHandler {
-                channel_id: command.channel_id,
-                http: ctx.http.clone(),
-                title: track_title.clone(),
-                thumbnail: track_thumbnail.clone().unwrap_or_default(),
+            Event::Periodic(Duration::from_secs(1), None),
+            SegmentSkipper {
+                segments: sponsor_segments,
             },
         );
+    }
 
-        let response_description = format!("**Queued** {}!", track_title);
+    track
+}
 
-        response_embed = response_embed
-            .description(response_description)
-            .color(Color::DARK_GREEN);
+/// Why a requested track was not queued
+#[derive(Debug)]
+pub enum EnqueueRejection {
+    Limit(LimitExceeded),
+    /// The video is already in the queue at `index`. `warn` is set when the
+    /// guild lets members queue it anyway.
+    Duplicate {
+        index: usize,
+        warn: bool,
+    },
+    /// Poor Jimmy isn't in a voice channel in the guild
+    NotInVoice,
+}
 
-        respond_to_followup(command, &ctx.http, response_embed, false).await;
-    } else {
+impl EnqueueRejection {
+    /// Message explaining why the track was not queued
+    pub fn description(&self) -> String {
+        match self {
+            EnqueueRejection::Limit(exceeded) => exceeded.description(),
+            EnqueueRejection::Duplicate { .. } => String::from("**Already queued!**"),
+            EnqueueRejection::NotInVoice => String::from(
+                "Error queueing songs! Ensure Poor Jimmy is in a voice channel with **/join**",
+            ),
+        }
+    }
+}
+
+/// Check a resolved track against the guild's limits and duplicate policy
+async fn check_enqueue(
+    ctx: &Context,
+    guild_id: GuildId,
+    queue: &TrackQueue,
+    metadata: &TrackMetadata,
+    allow_duplicate: bool,
+) -> Result<(), EnqueueRejection> {
+    let settings = get_guild_settings(&ctx.data, guild_id).await;
+
+    check_queue_limits(&settings.limits, queue, metadata).map_err(EnqueueRejection::Limit)?;
+
+    if allow_duplicate || settings.duplicate_policy == DuplicatePolicy::Allow {
+        return Ok(());
+    }
+
+    match find_duplicate(queue, metadata) {
+        Some(index) => Err(EnqueueRejection::Duplicate {
+            index,
+            warn: settings.duplicate_policy == DuplicatePolicy::Warn,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The followup explaining why a track was not queued
+fn rejection_message(
+    rejection: &EnqueueRejection,
+    metadata: &TrackMetadata,
+) -> CreateInteractionResponseFollowup {
+    let (description, components, color) = match rejection {
+        EnqueueRejection::Limit(_) | EnqueueRejection::NotInVoice => {
+            (rejection.description(), Vec::new(), Color::DARK_RED)
+        }
+        EnqueueRejection::Duplicate { index, warn } => {
+            let description = format!(
+                "**Already queued!** {} {}",
+                metadata.title,
+                describe_position(*index)
+            );
+
+            match track_key(metadata) {
+                Some(video_id) if *warn => (
+                    format!("{}. Queue it again anyway?", description),
+                    vec![create_queue_anyway_button(&video_id)],
+                    Color::ORANGE,
+                ),
+                _ => (description, Vec::new(), Color::DARK_RED),
+            }
+        }
+    };
+
+    let embed = CreateEmbed::new().description(description).color(color);
+
+    CreateInteractionResponseFollowup::new()
+        .embed(embed)
+        .components(components)
+}
+
+/// Enqueue a source requested through a command and place it at `position`
+pub async fn enqueue_track(
+    ctx: &Context,
+    command: &CommandInteraction,
+    mut source: Input,
+    position: QueuePosition,
+) {
+    let guild_id = command.guild_id.unwrap();
+
+    let http_client = get_http_client(ctx).await;
+    let metadata = resolve_metadata(&http_client, &mut source, guild_id).await;
+
+    enqueue_resolved_track(ctx, command, source, metadata, position).await;
+}
+
+/// Enqueue a source whose metadata the caller already worked out, checking
+/// it against the guild's limits and responding to the command. Returns the
+/// queued track, or `None` when it was not queued.
+pub async fn enqueue_resolved_track(
+    ctx: &Context,
+    command: &CommandInteraction,
+    source: Input,
+    mut metadata: TrackMetadata,
+    position: QueuePosition,
+) -> Option<TrackHandle> {
+    let mut response_embed = CreateEmbed::default();
+
+    let guild_id = command.guild_id.unwrap();
+
+    let manager = songbird::get(&ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialization.");
+
+    let Some(call) = manager.get(guild_id) else {
         error!(
             "Bot is not in a voice channel in guild {}. Cannot enqueue track.",
             guild_id
@@ -89,74 +370,198 @@ pub async fn enqueue_track(ctx: &Context, command: &CommandInteraction, mut sour
             .color(Color::DARK_RED);
 
         respond_to_followup(command, &ctx.http, response_embed, false).await;
+        return None;
+    };
+
+    let mut handler = call.lock().await;
+
+    metadata.requested_by = Some(command.user.id);
+    let track_title = metadata.title.clone();
+
+    if let Err(rejection) = check_enqueue(ctx, guild_id, handler.queue(), &metadata, false).await {
+        info!(
+            "Rejected track '{}' in guild {}: {:?}",
+            track_title, guild_id, rejection
+        );
+
+        let message = rejection_message(&rejection, &metadata);
+        if let Err(err) = command.create_followup(&ctx.http, message).await {
+            error!("Failed to send followup response: {}", err);
+        }
+        return None;
     }
+
+    info!("Enqueueing track: '{}' in guild {}", track_title, guild_id);
+
+    let track = enqueue_with_metadata(
+        &ctx.http,
+        &mut handler,
+        source,
+        metadata,
+        command.channel_id,
+    )
+    .await;
+
+    let index = place_last_track(handler.queue(), position);
+
+    let response_description = position.describe(&track_title, index);
+
+    response_embed = response_embed
+        .description(response_description)
+        .color(Color::DARK_GREEN);
+
+    respond_to_followup(command, &ctx.http, response_embed, false).await;
+
+    Some(track)
 }
 
-pub async fn enqueue_track_list(ctx: &Context, command: &CommandInteraction, mut source: Input) {
+/// Enqueue a track list in order without responding to the command.
+/// Metadata is looked up by a pool of workers ahead of queueing, so the call
+/// stays free for other commands while a long list is added.
+pub async fn enqueue_track_list(
+    ctx: &Context,
+    command: &CommandInteraction,
+    sources: Vec<Input>,
+) -> EnqueueSummary {
     let guild_id = command.guild_id.unwrap();
 
-    let manager = songbird::get(&ctx)
+    let http_client = get_http_client(ctx).await;
+    let mut resolver = MetadataResolver::new(http_client, guild_id, sources);
+    let mut summary = EnqueueSummary::default();
+
+    while let Some(ResolvedSource {
+        source, metadata, ..
+    }) = resolver.next().await
+    {
+        let metadata = metadata.unwrap_or_else(|err| {
+            warn!("Failed to fetch track metadata: {}. Using defaults.", err);
+            unresolved_metadata()
+        });
+
+        if !summary.record(enqueue_resolved_track_list(ctx, command, source, metadata).await) {
+            break;
+        }
+    }
+
+    summary
+}
+
+/// Same as `enqueue_track_list` for a source whose metadata is already known
+pub async fn enqueue_resolved_track_list(
+    ctx: &Context,
+    command: &CommandInteraction,
+    source: Input,
+    metadata: TrackMetadata,
+) -> Result<(), EnqueueRejection> {
+    enqueue_resolved_track_for(
+        ctx,
+        command.guild_id.unwrap(),
+        command.user.id,
+        command.channel_id,
+        source,
+        metadata,
+    )
+    .await
+}
+
+/// Enqueue a source with known metadata on behalf of `user_id` without
+/// responding, announcing it in `channel_id` once it plays. Used when many
+/// tracks are queued from one interaction.
+pub async fn enqueue_resolved_track_for(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+    source: Input,
+    metadata: TrackMetadata,
+) -> Result<(), EnqueueRejection> {
+    enqueue_resolved_track_at(
+        ctx,
+        guild_id,
+        user_id,
+        channel_id,
+        source,
+        metadata,
+        QueuePosition::End,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Same as `enqueue_resolved_track_for`, placing the track at `position`.
+/// Returns the index it ended up at.
+pub async fn enqueue_resolved_track_at(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+    source: Input,
+    mut metadata: TrackMetadata,
+    position: QueuePosition,
+) -> Result<usize, EnqueueRejection> {
+    let manager = songbird::get(ctx)
         .await
         .expect("Songbird Voice client placed in at initialization.");
 
     if let Some(call) = manager.get(guild_id) {
         let mut handler = call.lock().await;
 
-        // Get metadata from the source
-        debug!("Fetching track metadata for guild {}", guild_id);
-        let metadata = match source.aux_metadata().await {
-            Ok(meta) => meta,
-            Err(err) => {
-                warn!("Failed to fetch track metadata: {}. Using defaults.", err);
-                Default::default()
-            }
-        };
-
-        let track_title = metadata
-            .title
-            .clone()
-            .unwrap_or_else(|| String::from("Unknown Track Title"));
-        let track_thumbnail = metadata.thumbnail.clone();
-        let track_duration = metadata.duration.clone();
+        metadata.requested_by = Some(user_id);
+        let track_title = metadata.title.clone();
+
+        if let Err(rejection) =
+            check_enqueue(ctx, guild_id, handler.queue(), &metadata, false).await
+        {
+            info!(
+                "Rejected track '{}' in guild {}: {:?}",
+                track_title, guild_id, rejection
+            );
+            return Err(rejection);
+        }
 
         info!("Enqueueing track: '{}' in guild {}", track_title, guild_id);
 
-        // Create custom metadata to attach to the track
-        let custom_metadata = Arc::new(TrackMetadata {
-            title: track_title.clone(),
-            thumbnail_url: track_thumbnail.clone(),
-            duration: track_duration,
-        });
-
-        // Create track with attached metadata
-        let track_with_data = Track::new_with_data(source, custom_metadata);
+        enqueue_with_metadata(&ctx.http, &mut handler, source, metadata, channel_id).await;
 
-        // Play/enqueue song
-        let track = handler.enqueue(track_with_data).await;
-
-        let _ = track.add_event(
-            songbird::Event::Track(songbird::TrackEvent::Playable),
-            TrackPlayHandler {
-                channel_id: command.channel_id,
-                http: ctx.http.clone(),
-                title: track_title.clone(),
-                thumbnail: track_thumbnail.clone().unwrap_or_default(),
-            },
-        );
+        Ok(place_last_track(handler.queue(), position))
     } else {
         error!(
             "Bot is not in a voice channel in guild {}. Cannot enqueue track.",
             guild_id
         );
+        Err(EnqueueRejection::NotInVoice)
     }
 }
 
 /// Enqueue a track from a ComponentInteraction (e.g., button click).
 /// This is similar to enqueue_track but works with ComponentInteraction instead of CommandInteraction.
+/// `allow_duplicate` skips the guild's duplicate policy, for when the user
+/// already confirmed they want the track again.
 pub async fn enqueue_track_component(
     ctx: &Context,
     interaction: &ComponentInteraction,
     mut source: Input,
+    allow_duplicate: bool,
+) {
+    let metadata = match interaction.guild_id {
+        Some(guild_id) => {
+            let http_client = get_http_client(ctx).await;
+            resolve_metadata(&http_client, &mut source, guild_id).await
+        }
+        None => TrackMetadata::default(),
+    };
+
+    enqueue_resolved_track_component(ctx, interaction, source, metadata, allow_duplicate).await;
+}
+
+/// Same as `enqueue_track_component` for a source whose metadata is
+/// already known
+pub async fn enqueue_resolved_track_component(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    source: Input,
+    mut metadata: TrackMetadata,
+    allow_duplicate: bool,
 ) {
     let mut response_embed = CreateEmbed::default();
 
@@ -179,47 +584,34 @@ pub async fn enqueue_track_component(
     if let Some(call) = manager.get(guild_id) {
         let mut handler = call.lock().await;
 
-        // Get metadata from the source
-        debug!("Fetching track metadata for guild {}", guild_id);
-        let metadata = match source.aux_metadata().await {
-            Ok(meta) => meta,
-            Err(err) => {
-                warn!("Failed to fetch track metadata: {}. Using defaults.", err);
-                Default::default()
-            }
-        };
-
-        let track_title = metadata
-            .title
-            .clone()
-            .unwrap_or_else(|| String::from("Unknown Track Title"));
-        let track_thumbnail = metadata.thumbnail.clone();
-        let track_duration = metadata.duration.clone();
+        metadata.requested_by = Some(interaction.user.id);
+        let track_title = metadata.title.clone();
 
-        info!("Enqueueing track: '{}' in guild {}", track_title, guild_id);
-
-        // Create custom metadata to attach to the track
-        let custom_metadata = Arc::new(TrackMetadata {
-            title: track_title.clone(),
-            thumbnail_url: track_thumbnail.clone(),
-            duration: track_duration,
-        });
+        if let Err(rejection) =
+            check_enqueue(ctx, guild_id, handler.queue(), &metadata, allow_duplicate).await
+        {
+            info!(
+                "Rejected track '{}' in guild {}: {:?}",
+                track_title, guild_id, rejection
+            );
 
-        // Create track with attached metadata
-        let track_with_data = Track::new_with_data(source, custom_metadata);
+            let message = rejection_message(&rejection, &metadata);
+            if let Err(err) = interaction.create_followup(&ctx.http, message).await {
+                error!("Failed to send followup response: {}", err);
+            }
+            return;
+        }
 
-        // Play/enqueue song
-        let track = handler.enqueue(track_with_data).await;
+        info!("Enqueueing track: '{}' in guild {}", track_title, guild_id);
 
-        let _ = track.add_event(
-            songbird::Event::Track(songbird::TrackEvent::Playable),
-            TrackPlayHandler {
-                channel_id: interaction.channel_id,
-                http: ctx.http.clone(),
-                title: track_title.clone(),
-                thumbnail: track_thumbnail.clone().unwrap_or_default(),
-            },
-        );
+        enqueue_with_metadata(
+            &ctx.http,
+            &mut handler,
+            source,
+            metadata,
+            interaction.channel_id,
+        )
+        .await;
 
         let response_description = format!("**Queued** {}!", track_title);
 
@@ -254,6 +646,8 @@ mod tests {
             title: "Test Song".to_string(),
             thumbnail_url: Some("https://example.com/thumb.jpg".to_string()),
             duration: Some(Duration::from_secs(180)),
+            source_url: Some("https://www.youtube.com/watch?v=abc123".to_string()),
+            ..Default::default()
         };
 
         assert_eq!(metadata.title, "Test Song");
@@ -262,6 +656,10 @@ mod tests {
             Some("https://example.com/thumb.jpg".to_string())
         );
         assert_eq!(metadata.duration, Some(Duration::from_secs(180)));
+        assert_eq!(
+            metadata.source_url,
+            Some("https://www.youtube.com/watch?v=abc123".to_string())
+        );
     }
 
     #[test]
@@ -270,6 +668,8 @@ mod tests {
             title: "Original".to_string(),
             thumbnail_url: None,
             duration: None,
+            source_url: None,
+            ..Default::default()
         };
 
         let cloned = metadata.clone();
@@ -284,6 +684,8 @@ mod tests {
             title: "No Thumbnail Song".to_string(),
             thumbnail_url: None,
             duration: Some(Duration::from_secs(240)),
+            source_url: None,
+            ..Default::default()
         };
 
         assert!(metadata.thumbnail_url.is_none());
@@ -295,6 +697,8 @@ mod tests {
             title: "Live Stream".to_string(),
             thumbnail_url: Some("https://example.com/live.jpg".to_string()),
             duration: None,
+            source_url: None,
+            ..Default::default()
         };
 
         assert!(metadata.duration.is_none());
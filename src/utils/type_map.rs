@@ -1,5 +1,10 @@
+use std::sync::Arc;
+
 use reqwest::Client as HttpClient;
-use serenity::{all::Context, prelude::TypeMapKey};
+use serenity::{
+    all::Context,
+    prelude::{RwLock, TypeMap, TypeMapKey},
+};
 
 pub struct HttpKey;
 
@@ -8,7 +13,13 @@ impl TypeMapKey for HttpKey {
 }
 
 pub async fn get_http_client(ctx: &Context) -> HttpClient {
-    let data = ctx.data.read().await;
+    get_http_client_from_data(&ctx.data).await
+}
+
+/// Same as `get_http_client` for places that only hold the shared data,
+/// such as voice event handlers.
+pub async fn get_http_client_from_data(data: &Arc<RwLock<TypeMap>>) -> HttpClient {
+    let data = data.read().await;
     data.get::<HttpKey>()
         .cloned()
         .expect("Guaranteed to exist in the typemap.")
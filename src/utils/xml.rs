@@ -0,0 +1,179 @@
+//! XML documents read with quick-xml into a small tree of elements, which
+//! is all podcast feeds and playlist files need.
+
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+
+pub use quick_xml::escape::escape;
+
+/// An element with its attributes, child elements and text
+#[derive(Debug, Default)]
+pub struct Element {
+    pub name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    /// Text of the element and everything nested in it, in document order
+    text: String,
+}
+
+impl Element {
+    /// Child elements called `name`
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// The first child element called `name`
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Text of the first `name` child that has any
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.children(name)
+            .map(Element::text)
+            .find(|text| !text.is_empty())
+    }
+
+    /// Decoded value of an attribute
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Text content with CDATA sections unwrapped, entities decoded and
+    /// any nested markup dropped
+    pub fn text(&self) -> String {
+        self.text.trim().to_string()
+    }
+}
+
+/// Parse a document into its root element. Returns `None` when it isn't
+/// well-formed XML.
+pub fn parse(xml: &str) -> Option<Element> {
+    let mut reader = Reader::from_str(xml);
+    // Elements that are open, the root first
+    let mut open: Vec<Element> = Vec::new();
+
+    loop {
+        let closed = match reader.read_event().ok()? {
+            Event::Start(start) => {
+                open.push(element(&start));
+                continue;
+            }
+            Event::Empty(start) => element(&start),
+            Event::End(_) => open.pop()?,
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map(|text| text.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&text).into_owned());
+                append_text(&mut open, &text);
+                continue;
+            }
+            Event::CData(cdata) => {
+                append_text(&mut open, &String::from_utf8_lossy(&cdata));
+                continue;
+            }
+            Event::Eof => return None,
+            _ => continue,
+        };
+
+        match open.last_mut() {
+            Some(parent) => parent.children.push(closed),
+            None => return Some(closed),
+        }
+    }
+}
+
+fn element(start: &BytesStart) -> Element {
+    let attributes = start
+        .attributes()
+        .flatten()
+        .map(|attribute| {
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let value = attribute
+                .unescape_value()
+                .map(|value| value.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).into_owned());
+            (key, value)
+        })
+        .collect();
+
+    Element {
+        name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+        attributes,
+        ..Default::default()
+    }
+}
+
+/// Text belongs to the element it is in and to all of that element's parents
+fn append_text(open: &mut [Element], text: &str) {
+    for element in open.iter_mut() {
+        element.text.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_children_match_whole_names() {
+        let root =
+            parse("<items><item>one</item><itemref>x</itemref><item id=\"2\">two</item></items>")
+                .unwrap();
+
+        let texts: Vec<String> = root.children("item").map(Element::text).collect();
+        assert_eq!(texts, vec!["one", "two"]);
+        assert_eq!(root.child("item").unwrap().attribute("id"), None);
+    }
+
+    #[test]
+    fn test_self_closing_elements_and_attributes() {
+        let root = parse(
+            r#"<item><enclosure url="https://example.com/a.mp3?x=1&amp;y=2" length='5' /></item>"#,
+        )
+        .unwrap();
+
+        let enclosure = root.child("enclosure").unwrap();
+        assert_eq!(
+            enclosure.attribute("url"),
+            Some("https://example.com/a.mp3?x=1&y=2")
+        );
+        assert_eq!(enclosure.attribute("length"), Some("5"));
+        assert_eq!(enclosure.attribute("type"), None);
+    }
+
+    #[test]
+    fn test_text_unwraps_cdata_and_entities() {
+        let root = parse(
+            "<item><a><![CDATA[Tom & Jerry <3]]></a><b> Rock &amp; Roll &#8211; &#x41; </b>\
+             <c><i>bold</i> text</c></item>",
+        )
+        .unwrap();
+
+        assert_eq!(root.child_text("a").as_deref(), Some("Tom & Jerry <3"));
+        assert_eq!(root.child_text("b").as_deref(), Some("Rock & Roll – A"));
+        assert_eq!(root.child_text("c").as_deref(), Some("bold text"));
+    }
+
+    #[test]
+    fn test_malformed_documents_are_rejected() {
+        assert!(parse("<rss><channel></rss>").is_none());
+        assert!(parse("<rss>").is_none());
+        assert!(parse("not xml").is_none());
+    }
+
+    #[test]
+    fn test_escape_round_trips_through_text() {
+        let original = "AC/DC <Live> \"Rock & Roll\" Ain't Noise";
+        let root = parse(&format!("<title>{}</title>", escape(original))).unwrap();
+
+        assert_eq!(root.text(), original);
+        assert!(!escape(original).contains('<'));
+    }
+}
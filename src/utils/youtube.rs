@@ -0,0 +1,94 @@
+use rustypipe::{client::RustyPipe, model::VideoDetails};
+use tracing::warn;
+use url::Url;
+
+/// Build the canonical watch URL for a YouTube video id
+pub fn watch_url(video_id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={}", video_id)
+}
+
+/// Extract the video id from the YouTube URL formats users share:
+/// `/watch?v=`, `youtu.be/`, `/shorts/`, `/live/` and `/embed/`.
+pub fn video_id_from_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+
+    let id = if host == "youtu.be" {
+        parsed.path_segments()?.next().map(String::from)
+    } else if host == "youtube.com" || host.ends_with(".youtube.com") {
+        let mut segments = parsed.path_segments()?;
+        match segments.next() {
+            Some("watch") => parsed
+                .query_pairs()
+                .find(|(k, _)| k == "v")
+                .map(|(_, v)| v.into_owned()),
+            Some("shorts" | "live" | "embed") => segments.next().map(String::from),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    id.filter(|id| !id.is_empty())
+}
+
+/// Look up a video's details page (chapters, live status, related videos)
+pub async fn fetch_video_details(video_id: &str) -> Option<VideoDetails> {
+    let rp = RustyPipe::new();
+
+    match rp.query().video_details(video_id).await {
+        Ok(details) => Some(details),
+        Err(err) => {
+            warn!("Failed to fetch video details for {}: {}", video_id, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_id_from_watch_url() {
+        assert_eq!(
+            video_id_from_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123"),
+            Some("dQw4w9WgXcQ".to_string())
+        );
+        assert_eq!(
+            video_id_from_url("https://music.youtube.com/watch?v=dQw4w9WgXcQ"),
+            Some("dQw4w9WgXcQ".to_string())
+        );
+    }
+
+    #[test]
+    fn test_video_id_from_share_and_short_urls() {
+        assert_eq!(
+            video_id_from_url("https://youtu.be/e7qtC_e8Jxc?si=mtCnq8iVc253P89M"),
+            Some("e7qtC_e8Jxc".to_string())
+        );
+        assert_eq!(
+            video_id_from_url("https://youtube.com/shorts/abc123"),
+            Some("abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_video_id_from_invalid_urls() {
+        assert_eq!(video_id_from_url(""), None);
+        assert_eq!(video_id_from_url("https://vimeo.com/12345"), None);
+        assert_eq!(video_id_from_url("https://www.youtube.com/watch"), None);
+        assert_eq!(
+            video_id_from_url("https://www.youtube.com/channel/test"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_watch_url() {
+        assert_eq!(
+            watch_url("abc123"),
+            "https://www.youtube.com/watch?v=abc123"
+        );
+    }
+}
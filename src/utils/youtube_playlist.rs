@@ -0,0 +1,259 @@
+//! Importing YouTube playlists with `/playlist`: reading the link, picking
+//! which videos to queue and letting an import in progress be cancelled.
+
+use std::{
+    collections::HashMap,
+    env,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use rand::seq::SliceRandom;
+use serenity::{all::UserId, prelude::TypeMapKey};
+use url::Url;
+
+/// Unavailable videos named in the summary before the rest are counted
+const MAX_UNAVAILABLE_LISTED: usize = 10;
+
+/// Most videos read from a playlist, however many it has.
+/// Set PLAYLIST_FETCH_LIMIT to override the default of 1000.
+pub fn playlist_fetch_limit() -> usize {
+    env::var("PLAYLIST_FETCH_LIMIT")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(1000)
+}
+
+/// A YouTube link with a playlist in it
+#[derive(Debug, PartialEq)]
+pub struct PlaylistUrl {
+    pub id: String,
+    /// 1-based position of the video the link points at, from `index=`
+    pub index: Option<usize>,
+}
+
+impl PlaylistUrl {
+    /// Read `/playlist?list=` and `/watch?v=...&list=` links
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?;
+
+        if !(host == "youtu.be" || host == "youtube.com" || host.ends_with(".youtube.com")) {
+            return None;
+        }
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        Some(Self {
+            id: param("list").filter(|id| !id.is_empty())?,
+            index: param("index")
+                .and_then(|index| index.parse::<usize>().ok())
+                .filter(|index| *index > 0),
+        })
+    }
+}
+
+/// The videos to queue: from the 1-based `start` on, shuffled if asked, and
+/// no more than `limit`
+pub fn select_videos<T>(
+    videos: Vec<T>,
+    start: usize,
+    limit: Option<usize>,
+    shuffle: bool,
+) -> Vec<T> {
+    let mut selected: Vec<T> = videos.into_iter().skip(start.saturating_sub(1)).collect();
+
+    if shuffle {
+        selected.shuffle(&mut rand::rng());
+    }
+
+    if let Some(limit) = limit {
+        selected.truncate(limit);
+    }
+
+    selected
+}
+
+/// Line of the progress embed while the import runs
+pub fn describe_progress(done: usize, total: usize) -> String {
+    format!("**Queued** {}/{}…", done, total)
+}
+
+/// Summary lines for videos that couldn't be queued: the titles of those
+/// that failed to load, and a count of those YouTube left out of the playlist
+pub fn describe_unavailable(titles: &[String], hidden: usize) -> Option<String> {
+    if titles.is_empty() && hidden == 0 {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+
+    if !titles.is_empty() {
+        lines.push(format!("**{} unavailable videos:**", titles.len()));
+        lines.extend(
+            titles
+                .iter()
+                .take(MAX_UNAVAILABLE_LISTED)
+                .map(|title| format!("• {}", title)),
+        );
+
+        if titles.len() > MAX_UNAVAILABLE_LISTED {
+            lines.push(format!(
+                "…and {} more",
+                titles.len() - MAX_UNAVAILABLE_LISTED
+            ));
+        }
+    }
+
+    if hidden > 0 {
+        lines.push(format!(
+            "{} videos are private or deleted and were left out by YouTube",
+            hidden
+        ));
+    }
+
+    Some(lines.join("\n"))
+}
+
+/// A playlist import that is still queueing videos
+struct PlaylistImport {
+    requested_by: UserId,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Imports in progress, keyed by the id of the `/playlist` interaction that
+/// started them
+#[derive(Default)]
+pub struct PlaylistImports {
+    imports: HashMap<u64, PlaylistImport>,
+}
+
+impl PlaylistImports {
+    /// Track a new import. The returned flag is set once it is cancelled.
+    pub fn start(&mut self, import_id: u64, requested_by: UserId) -> Arc<AtomicBool> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        self.imports.insert(
+            import_id,
+            PlaylistImport {
+                requested_by,
+                cancelled: cancelled.clone(),
+            },
+        );
+
+        cancelled
+    }
+
+    /// Who started an import, `None` once it has finished
+    pub fn requested_by(&self, import_id: u64) -> Option<UserId> {
+        self.imports
+            .get(&import_id)
+            .map(|import| import.requested_by)
+    }
+
+    /// Ask an import to stop. Returns `false` if it already finished.
+    pub fn cancel(&self, import_id: u64) -> bool {
+        match self.imports.get(&import_id) {
+            Some(import) => {
+                import.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&mut self, import_id: u64) {
+        self.imports.remove(&import_id);
+    }
+}
+
+pub struct PlaylistImportsKey;
+
+impl TypeMapKey for PlaylistImportsKey {
+    type Value = PlaylistImports;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_playlist_urls() {
+        assert_eq!(
+            PlaylistUrl::parse("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123&index=4"),
+            Some(PlaylistUrl {
+                id: String::from("PL123"),
+                index: Some(4),
+            })
+        );
+        assert_eq!(
+            PlaylistUrl::parse("https://music.youtube.com/playlist?list=PL123"),
+            Some(PlaylistUrl {
+                id: String::from("PL123"),
+                index: None,
+            })
+        );
+        assert_eq!(
+            PlaylistUrl::parse("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            None
+        );
+        assert_eq!(
+            PlaylistUrl::parse("https://example.com/playlist?list=PL123"),
+            None
+        );
+        assert_eq!(PlaylistUrl::parse("not a url"), None);
+    }
+
+    #[test]
+    fn test_select_videos_from_start_up_to_limit() {
+        let videos: Vec<usize> = (1..=10).collect();
+
+        assert_eq!(
+            select_videos(videos.clone(), 4, Some(3), false),
+            vec![4, 5, 6]
+        );
+        assert_eq!(select_videos(videos.clone(), 1, None, false), videos);
+        assert!(select_videos(videos.clone(), 11, None, false).is_empty());
+
+        let mut shuffled = select_videos(videos, 6, Some(10), true);
+        shuffled.sort();
+        assert_eq!(shuffled, vec![6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_describe_unavailable() {
+        assert_eq!(describe_unavailable(&[], 0), None);
+
+        let titles: Vec<String> = (1..=12).map(|i| format!("Video {}", i)).collect();
+        let description = describe_unavailable(&titles, 2).unwrap();
+
+        assert!(description.starts_with("**12 unavailable videos:**"));
+        assert!(description.contains("• Video 10"));
+        assert!(!description.contains("• Video 11"));
+        assert!(description.contains("…and 2 more"));
+        assert!(
+            description.ends_with("2 videos are private or deleted and were left out by YouTube")
+        );
+    }
+
+    #[test]
+    fn test_cancel_only_running_imports() {
+        let mut imports = PlaylistImports::default();
+        let cancelled = imports.start(1, UserId::new(7));
+
+        assert_eq!(imports.requested_by(1), Some(UserId::new(7)));
+        assert!(imports.cancel(1));
+        assert!(cancelled.load(Ordering::Relaxed));
+
+        imports.finish(1);
+        assert!(!imports.cancel(1));
+        assert_eq!(imports.requested_by(1), None);
+    }
+}
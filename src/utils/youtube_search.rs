@@ -0,0 +1,621 @@
+//! Searching YouTube for `/search`. rustypipe is asked first and yt-dlp is
+//! kept as a fallback, either can be picked with SEARCH_BACKEND.
+
+use std::{env, fmt, time::Duration};
+
+use rustypipe::{
+    client::RustyPipe,
+    model::{TrackItem, TrackType, VideoItem},
+    param::search_filter::{ItemType, SearchFilter, UploadDate},
+};
+use serde::Deserialize;
+use serenity::async_trait;
+use tracing::{debug, error, warn};
+use url::Url;
+
+use crate::utils::stats::unix_now;
+
+/// A video found by a search
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub id: String,
+    pub title: String,
+    /// `None` for live streams and when the backend didn't say
+    pub duration: Option<Duration>,
+    pub thumbnail_url: Option<String>,
+    pub channel: Option<String>,
+    pub is_live: bool,
+    /// Unix time the video was uploaded, when the backend knows it
+    pub uploaded_at: Option<u64>,
+}
+
+/// How recently a result has to have been uploaded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UploadedWithin {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl UploadedWithin {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hour" => Some(Self::Hour),
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            "year" => Some(Self::Year),
+            _ => None,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Hour => "the last hour",
+            Self::Day => "the last day",
+            Self::Week => "the last week",
+            Self::Month => "the last month",
+            Self::Year => "the last year",
+        }
+    }
+
+    fn max_age(self) -> u64 {
+        let hours = match self {
+            Self::Hour => 1,
+            Self::Day => 24,
+            Self::Week => 24 * 7,
+            Self::Month => 24 * 30,
+            Self::Year => 24 * 365,
+        };
+
+        hours * 60 * 60
+    }
+
+    fn upload_date(self) -> UploadDate {
+        match self {
+            Self::Hour => UploadDate::Hour,
+            Self::Day => UploadDate::Day,
+            Self::Week => UploadDate::Week,
+            Self::Month => UploadDate::Month,
+            Self::Year => UploadDate::Year,
+        }
+    }
+}
+
+/// Narrowing down a search. The upload date and music filters are passed
+/// to the backend, the rest are checked on the results.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchFilters {
+    pub max_duration: Option<Duration>,
+    /// Part of the channel name, in any case
+    pub channel: Option<String>,
+    pub uploaded_within: Option<UploadedWithin>,
+    /// Only songs from YouTube Music
+    pub music_only: bool,
+}
+
+impl SearchFilters {
+    /// Whether `result` passes the filters. Results with an unknown upload
+    /// date are kept since not every backend reports one.
+    pub fn matches(&self, result: &SearchResult, now: u64) -> bool {
+        if let Some(max_duration) = self.max_duration
+            && result
+                .duration
+                .is_none_or(|duration| duration > max_duration)
+        {
+            return false;
+        }
+
+        if let Some(channel) = &self.channel {
+            let channel = channel.to_lowercase();
+            if !result
+                .channel
+                .as_ref()
+                .is_some_and(|name| name.to_lowercase().contains(&channel))
+            {
+                return false;
+            }
+        }
+
+        match (self.uploaded_within, result.uploaded_at) {
+            (Some(within), Some(uploaded_at)) => {
+                now.saturating_sub(uploaded_at) <= within.max_age()
+            }
+            _ => true,
+        }
+    }
+
+    /// One line per filter in use, `None` without filters
+    pub fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+
+        if let Some(max_duration) = self.max_duration {
+            parts.push(format!("up to {} minutes", max_duration.as_secs() / 60));
+        }
+        if let Some(channel) = &self.channel {
+            parts.push(format!("channel \"{}\"", channel));
+        }
+        if let Some(within) = self.uploaded_within {
+            parts.push(format!("uploaded in {}", within.describe()));
+        }
+        if self.music_only {
+            parts.push(String::from("music only"));
+        }
+
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub enum SearchError {
+    RustyPipe(rustypipe::error::Error),
+    YtDlp(String),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::RustyPipe(err) => write!(f, "rustypipe search failed: {}", err),
+            SearchError::YtDlp(err) => write!(f, "yt-dlp search failed: {}", err),
+        }
+    }
+}
+
+/// A way of searching YouTube for videos
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Up to `limit` videos matching `query`, best match first. Backends
+    /// apply what they can of `filters`, the rest is left to the caller.
+    async fn search(
+        &self,
+        query: &str,
+        filters: &SearchFilters,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, SearchError>;
+}
+
+/// Searches through YouTube's own API with rustypipe
+pub struct RustyPipeSearch;
+
+#[async_trait]
+impl SearchBackend for RustyPipeSearch {
+    fn name(&self) -> &'static str {
+        "rustypipe"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        filters: &SearchFilters,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, SearchError> {
+        let rp = RustyPipe::new();
+
+        if filters.music_only {
+            let mut found = rp
+                .query()
+                .music_search_tracks(query)
+                .await
+                .map_err(SearchError::RustyPipe)?;
+
+            if let Err(err) = found.items.extend_limit(rp.query(), limit).await {
+                warn!("Failed to page through music search results: {}", err);
+            }
+
+            let mut results = results_from_tracks(found.items.items);
+            results.truncate(limit);
+            return Ok(results);
+        }
+
+        let filter = SearchFilter::new()
+            .item_type(ItemType::Video)
+            .date_opt(filters.uploaded_within.map(UploadedWithin::upload_date));
+
+        let mut found = rp
+            .query()
+            .search_filter::<VideoItem, _>(query, &filter)
+            .await
+            .map_err(SearchError::RustyPipe)?;
+
+        // The first page is usually enough, a failed extra page isn't fatal
+        if let Err(err) = found.items.extend_limit(rp.query(), limit).await {
+            warn!("Failed to page through search results: {}", err);
+        }
+
+        let mut results = results_from_videos(found.items.items);
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+/// Searches by running `yt-dlp ytsearchN:`, slower but independent of
+/// rustypipe
+pub struct YtDlpSearch;
+
+#[async_trait]
+impl SearchBackend for YtDlpSearch {
+    fn name(&self) -> &'static str {
+        "yt-dlp"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        filters: &SearchFilters,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, SearchError> {
+        // YouTube Music searches are read like a playlist of songs
+        let target = if filters.music_only {
+            music_search_url(query)
+        } else {
+            format!("ytsearch{}:{}", limit, query)
+        };
+
+        let output = tokio::process::Command::new("yt-dlp")
+            .args([
+                "--dump-json",
+                "--flat-playlist",
+                "--playlist-end",
+                &limit.to_string(),
+                &target,
+            ])
+            .output()
+            .await
+            .map_err(|err| SearchError::YtDlp(err.to_string()))?;
+
+        if !output.status.success() {
+            return Err(SearchError::YtDlp(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        debug!("Stdout from yt-dlp query: {}", stdout);
+
+        let mut results = parse_ytdlp_results(&stdout);
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+/// The videos of a rustypipe search, leaving out upcoming premieres that
+/// can't be played yet
+pub fn results_from_videos(videos: Vec<VideoItem>) -> Vec<SearchResult> {
+    videos
+        .into_iter()
+        .filter(|video| !video.is_upcoming)
+        .map(|video| SearchResult {
+            // Pick the largest thumbnail
+            thumbnail_url: video
+                .thumbnail
+                .iter()
+                .max_by_key(|thumbnail| thumbnail.width)
+                .map(|thumbnail| thumbnail.url.clone()),
+            duration: video
+                .duration
+                .map(|duration| Duration::from_secs(duration.into())),
+            channel: video.channel.map(|channel| channel.name),
+            is_live: video.is_live,
+            uploaded_at: video
+                .publish_date
+                .and_then(|date| u64::try_from(date.unix_timestamp()).ok()),
+            id: video.id,
+            title: video.name,
+        })
+        .collect()
+}
+
+/// The songs of a rustypipe YouTube Music search, leaving out podcast
+/// episodes
+pub fn results_from_tracks(tracks: Vec<TrackItem>) -> Vec<SearchResult> {
+    tracks
+        .into_iter()
+        .filter(|track| track.track_type != TrackType::Episode)
+        .map(|track| SearchResult {
+            thumbnail_url: track
+                .cover
+                .iter()
+                .max_by_key(|cover| cover.width)
+                .map(|cover| cover.url.clone()),
+            duration: track
+                .duration
+                .map(|duration| Duration::from_secs(duration.into())),
+            channel: (!track.artists.is_empty()).then(|| {
+                track
+                    .artists
+                    .iter()
+                    .map(|artist| artist.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            }),
+            is_live: false,
+            uploaded_at: None,
+            id: track.id,
+            title: track.name,
+        })
+        .collect()
+}
+
+/// YouTube Music's song search page for `query`
+fn music_search_url(query: &str) -> String {
+    let mut url = Url::parse("https://music.youtube.com/search").expect("Valid base URL");
+    url.query_pairs_mut().append_pair("q", query);
+    url.set_fragment(Some("songs"));
+    url.to_string()
+}
+
+#[derive(Deserialize)]
+struct YtDlpThumbnail {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct YtDlpEntry {
+    id: String,
+    title: String,
+    duration: Option<f64>,
+    #[serde(default)]
+    thumbnails: Vec<YtDlpThumbnail>,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    live_status: Option<String>,
+    #[serde(default)]
+    timestamp: Option<i64>,
+}
+
+/// Read yt-dlp's `--dump-json` output, one video per line
+pub fn parse_ytdlp_results(stdout: &str) -> Vec<SearchResult> {
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<YtDlpEntry>(line) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                error!("Failed to parse search result: {}", err);
+                error!("Line that failed: {}", line);
+                None
+            }
+        })
+        .map(|entry| SearchResult {
+            // Use the last thumbnail which is usually the highest quality
+            thumbnail_url: entry
+                .thumbnails
+                .last()
+                .map(|thumbnail| thumbnail.url.clone()),
+            duration: entry
+                .duration
+                .filter(|duration| *duration > 0.0)
+                .map(Duration::from_secs_f64),
+            channel: entry.channel,
+            is_live: entry.live_status.as_deref() == Some("is_live"),
+            uploaded_at: entry
+                .timestamp
+                .and_then(|timestamp| u64::try_from(timestamp).ok()),
+            id: entry.id,
+            title: entry.title,
+        })
+        .collect()
+}
+
+/// The backends tried in turn. SEARCH_BACKEND picks which comes first,
+/// `rustypipe` unless set to `yt-dlp`.
+pub fn search_backends() -> Vec<Box<dyn SearchBackend>> {
+    match env::var("SEARCH_BACKEND").as_deref() {
+        Ok("yt-dlp") | Ok("ytdlp") => vec![Box::new(YtDlpSearch), Box::new(RustyPipeSearch)],
+        _ => vec![Box::new(RustyPipeSearch), Box::new(YtDlpSearch)],
+    }
+}
+
+/// Search with each backend until one answers, then drop the results that
+/// don't pass `filters`. Only when all of them fail is the last error
+/// returned.
+pub async fn search_with(
+    backends: &[Box<dyn SearchBackend>],
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let mut last_error = None;
+
+    for backend in backends {
+        match backend.search(query, filters, limit).await {
+            Ok(results) => {
+                let now = unix_now();
+                let results: Vec<SearchResult> = results
+                    .into_iter()
+                    .filter(|result| filters.matches(result, now))
+                    .collect();
+
+                debug!(
+                    "{} found {} results for \"{}\"",
+                    backend.name(),
+                    results.len(),
+                    query
+                );
+                return Ok(results);
+            }
+            Err(err) => {
+                warn!("Search backend {} failed: {}", backend.name(), err);
+                last_error = Some(err);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| SearchError::YtDlp(String::from("no search backend"))))
+}
+
+/// Search YouTube with the configured backends
+pub async fn search_youtube(
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<SearchResult>, SearchError> {
+    search_with(&search_backends(), query, filters, limit).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output of `yt-dlp --dump-json --flat-playlist ytsearch3:...`, trimmed
+    /// down to a few fields after the first line
+    const YTDLP_FIXTURE: &str = include_str!("fixtures/ytdlp_search.jsonl");
+    /// rustypipe's `VideoItem`s for a similar search, serialized
+    const RUSTYPIPE_FIXTURE: &str = include_str!("fixtures/rustypipe_search.json");
+    /// rustypipe's `TrackItem`s for a YouTube Music search, serialized
+    const RUSTYPIPE_MUSIC_FIXTURE: &str = include_str!("fixtures/rustypipe_music_search.json");
+
+    /// Answers from a recorded fixture, or fails like an unreachable backend
+    struct RecordedSearch(Option<&'static str>);
+
+    #[async_trait]
+    impl SearchBackend for RecordedSearch {
+        fn name(&self) -> &'static str {
+            "recorded"
+        }
+
+        async fn search(
+            &self,
+            _query: &str,
+            _filters: &SearchFilters,
+            limit: usize,
+        ) -> Result<Vec<SearchResult>, SearchError> {
+            let fixture = self
+                .0
+                .ok_or_else(|| SearchError::YtDlp(String::from("offline")))?;
+
+            let mut results = parse_ytdlp_results(fixture);
+            results.truncate(limit);
+            Ok(results)
+        }
+    }
+
+    #[test]
+    fn test_parse_ytdlp_fixture() {
+        let results = parse_ytdlp_results(YTDLP_FIXTURE);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0],
+            SearchResult {
+                id: String::from("dQw4w9WgXcQ"),
+                title: String::from(
+                    "Rick Astley - Never Gonna Give You Up (Official Video) (4K Remaster)"
+                ),
+                duration: Some(Duration::from_secs(213)),
+                thumbnail_url: Some(String::from(
+                    "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"
+                )),
+                channel: Some(String::from("Rick Astley")),
+                is_live: false,
+                uploaded_at: None,
+            }
+        );
+        assert!(results[2].is_live);
+        assert_eq!(results[2].duration, None);
+    }
+
+    #[test]
+    fn test_results_from_rustypipe_fixture() {
+        let videos: Vec<VideoItem> = serde_json::from_str(RUSTYPIPE_FIXTURE).unwrap();
+        let results = results_from_videos(videos);
+
+        // The upcoming premiere is left out
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, "dQw4w9WgXcQ");
+        assert_eq!(results[0].duration, Some(Duration::from_secs(213)));
+        assert_eq!(
+            results[0].thumbnail_url.as_deref(),
+            Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hq720.jpg")
+        );
+        assert_eq!(results[0].channel.as_deref(), Some("Rick Astley"));
+        assert!(results[1].is_live);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_when_a_backend_fails() {
+        let backends: Vec<Box<dyn SearchBackend>> = vec![
+            Box::new(RecordedSearch(None)),
+            Box::new(RecordedSearch(Some(YTDLP_FIXTURE))),
+        ];
+
+        let filters = SearchFilters::default();
+        let results = search_with(&backends, "never gonna give you up", &filters, 2)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+
+        let offline: Vec<Box<dyn SearchBackend>> = vec![Box::new(RecordedSearch(None))];
+        assert!(
+            search_with(&offline, "anything", &filters, 5)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filters_apply_to_any_backend() {
+        let backends: Vec<Box<dyn SearchBackend>> =
+            vec![Box::new(RecordedSearch(Some(YTDLP_FIXTURE)))];
+
+        // Live streams have no duration to compare
+        let short = SearchFilters {
+            max_duration: Some(Duration::from_secs(210)),
+            ..Default::default()
+        };
+        let results = search_with(&backends, "rick", &short, 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "yPYZpwSpKmA");
+
+        let channel = SearchFilters {
+            channel: Some(String::from("lofi")),
+            ..Default::default()
+        };
+        let results = search_with(&backends, "rick", &channel, 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "jfKfPfyJRdk");
+    }
+
+    #[test]
+    fn test_upload_date_filter_keeps_unknown_dates() {
+        let filters = SearchFilters {
+            uploaded_within: Some(UploadedWithin::Day),
+            ..Default::default()
+        };
+        let mut result = parse_ytdlp_results(YTDLP_FIXTURE).remove(0);
+        let now = 1_000_000;
+
+        assert!(filters.matches(&result, now));
+
+        result.uploaded_at = Some(now - 60 * 60);
+        assert!(filters.matches(&result, now));
+
+        result.uploaded_at = Some(now - 2 * 24 * 60 * 60);
+        assert!(!filters.matches(&result, now));
+    }
+
+    #[test]
+    fn test_results_from_rustypipe_music_fixture() {
+        let tracks: Vec<TrackItem> = serde_json::from_str(RUSTYPIPE_MUSIC_FIXTURE).unwrap();
+        let results = results_from_tracks(tracks);
+
+        // The podcast episode is left out
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "lYBUbBu4W08");
+        assert_eq!(results[0].channel.as_deref(), Some("Rick Astley"));
+        assert_eq!(results[0].duration, Some(Duration::from_secs(214)));
+    }
+
+    #[test]
+    fn test_music_search_url() {
+        assert_eq!(
+            music_search_url("never gonna & give"),
+            "https://music.youtube.com/search?q=never+gonna+%26+give#songs"
+        );
+    }
+}